@@ -0,0 +1,452 @@
+//! Browse zip / tar / tar.gz / tar.zst archives as if they were directories.
+//!
+//! Archive members are addressed by *virtual paths*: the archive's own path
+//! followed by the member path, e.g. `/proj/dist/release.zip/bin/tool`. The
+//! nearest existing file among a virtual path's ancestors is the archive; the
+//! rest is the member. The archive itself goes through the usual project-root
+//! validation, and member names are normalised so entries like `../../etc` or
+//! `/abs` (zip-slip) can never be listed or read.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+use super::{canonicalize_path, encoding, validate_within_root, FileEntry, MAX_FILE_SIZE};
+
+/// Listing a tar means streaming the whole thing; stop after this many entries
+/// rather than hang the file panel on a giant archive.
+const MAX_ARCHIVE_ENTRIES: usize = 20_000;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarZst,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArchiveEntry {
+    /// Normalised member path, `/`-separated, without a trailing slash.
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// Identify an archive by extension, falling back to magic bytes for files
+/// named without one.
+pub fn detect_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".zip") || name.ends_with(".jar") || name.ends_with(".whl") {
+        return Some(ArchiveKind::Zip);
+    }
+    if name.ends_with(".tar") {
+        return Some(ArchiveKind::Tar);
+    }
+    if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        return Some(ArchiveKind::TarGz);
+    }
+    if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        return Some(ArchiveKind::TarZst);
+    }
+
+    let mut head = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(512)
+        .read_to_end(&mut head)
+        .ok()?;
+    match encoding::mime_from_magic(&head)? {
+        "application/zip" => Some(ArchiveKind::Zip),
+        "application/x-tar" => Some(ArchiveKind::Tar),
+        _ => None,
+    }
+}
+
+/// Whether a file name looks like a browsable archive. Extension-only, so it's
+/// cheap enough to call for every entry of a directory listing.
+pub fn has_archive_extension(name: &str) -> bool {
+    let name = name.to_lowercase();
+    [
+        ".zip", ".jar", ".whl", ".tar", ".tar.gz", ".tgz", ".tar.zst", ".tzst",
+    ]
+    .iter()
+    .any(|ext| name.ends_with(ext))
+}
+
+/// Normalise a member name to `a/b/c`. Returns None for names that would
+/// escape the archive (absolute paths, `..`) or are empty.
+fn normalize_member(name: &str) -> Option<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for component in Path::new(&name.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+fn tar_reader(path: &Path, kind: ArchiveKind) -> Result<Box<dyn Read>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+    Ok(match kind {
+        ArchiveKind::Tar => Box::new(file),
+        ArchiveKind::TarGz => Box::new(flate2::read::GzDecoder::new(file)),
+        ArchiveKind::TarZst => Box::new(
+            zstd::stream::read::Decoder::new(file)
+                .map_err(|e| format!("Invalid zstd stream: {}", e))?,
+        ),
+        ArchiveKind::Zip => unreachable!("zip archives are not read as tar streams"),
+    })
+}
+
+/// List up to `limit` entries. The flag is true when the listing was cut short.
+pub fn list_entries(
+    path: &Path,
+    kind: ArchiveKind,
+    limit: usize,
+) -> Result<(Vec<ArchiveEntry>, bool), String> {
+    let mut entries = Vec::new();
+
+    if kind == ArchiveKind::Zip {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let mut archive =
+            zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip archive: {}", e))?;
+        for i in 0..archive.len().min(limit) {
+            let entry = archive
+                .by_index_raw(i)
+                .map_err(|e| format!("Invalid zip entry: {}", e))?;
+            if let Some(name) = normalize_member(entry.name()) {
+                entries.push(ArchiveEntry {
+                    path: name,
+                    size: entry.size(),
+                    is_dir: entry.is_dir(),
+                });
+            }
+        }
+        return Ok((entries, archive.len() > limit));
+    }
+
+    let mut archive = tar::Archive::new(tar_reader(path, kind)?);
+    for entry in archive
+        .entries()
+        .map_err(|e| format!("Invalid tar archive: {}", e))?
+    {
+        if entries.len() == limit {
+            return Ok((entries, true));
+        }
+        let entry = entry.map_err(|e| format!("Invalid tar entry: {}", e))?;
+        let header = entry.header();
+        let Some(name) = entry
+            .path()
+            .ok()
+            .and_then(|p| normalize_member(&p.to_string_lossy()))
+        else {
+            continue;
+        };
+        entries.push(ArchiveEntry {
+            path: name,
+            size: header.size().unwrap_or(0),
+            is_dir: header.entry_type().is_dir(),
+        });
+    }
+    Ok((entries, false))
+}
+
+/// Read one member's bytes, refusing anything larger than `max_size` even if
+/// the archive's own size field under-reports it.
+pub fn read_member(
+    path: &Path,
+    kind: ArchiveKind,
+    member: &str,
+    max_size: u64,
+) -> Result<Vec<u8>, String> {
+    let too_large = |size: u64| format!("File too large ({} bytes, max {})", size, max_size);
+    let mut bytes = Vec::new();
+
+    if kind == ArchiveKind::Zip {
+        let file = File::open(path).map_err(|e| format!("Failed to open archive: {}", e))?;
+        let mut archive =
+            zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip archive: {}", e))?;
+        let index = (0..archive.len())
+            .find(|&i| {
+                archive
+                    .name_for_index(i)
+                    .and_then(normalize_member)
+                    .is_some_and(|name| name == member)
+            })
+            .ok_or_else(|| format!("No such archive member: {}", member))?;
+        let entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive member: {}", e))?;
+        if entry.is_dir() {
+            return Err(format!("Not a file: {}", member));
+        }
+        if entry.size() > max_size {
+            return Err(too_large(entry.size()));
+        }
+        entry
+            .take(max_size + 1)
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Failed to read archive member: {}", e))?;
+    } else {
+        let mut archive = tar::Archive::new(tar_reader(path, kind)?);
+        let entries = archive
+            .entries()
+            .map_err(|e| format!("Invalid tar archive: {}", e))?;
+        let mut found = false;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("Invalid tar entry: {}", e))?;
+            let matches = entry
+                .path()
+                .ok()
+                .and_then(|p| normalize_member(&p.to_string_lossy()))
+                .is_some_and(|name| name == member);
+            if !matches {
+                continue;
+            }
+            if !entry.header().entry_type().is_file() {
+                return Err(format!("Not a file: {}", member));
+            }
+            let size = entry.header().size().unwrap_or(0);
+            if size > max_size {
+                return Err(too_large(size));
+            }
+            entry
+                .take(max_size + 1)
+                .read_to_end(&mut bytes)
+                .map_err(|e| format!("Failed to read archive member: {}", e))?;
+            found = true;
+            break;
+        }
+        if !found {
+            return Err(format!("No such archive member: {}", member));
+        }
+    }
+
+    if bytes.len() as u64 > max_size {
+        return Err(too_large(bytes.len() as u64));
+    }
+    Ok(bytes)
+}
+
+/// Split a virtual path into (canonical archive path, kind, member path). The
+/// member is empty for the archive root.
+fn resolve_virtual(
+    path: &str,
+    project_root: &str,
+) -> Result<(PathBuf, ArchiveKind, String), String> {
+    let raw = Path::new(path);
+    let archive = raw
+        .ancestors()
+        .find(|p| p.is_file())
+        .ok_or_else(|| format!("Not inside an archive: {}", path))?;
+    let rest = raw
+        .strip_prefix(archive)
+        .map_err(|_| format!("Not inside an archive: {}", path))?;
+
+    let canonical = canonicalize_path(&archive.to_string_lossy())?;
+    let canonical_root = canonicalize_path(project_root)?;
+    validate_within_root(&canonical, &canonical_root)?;
+
+    let kind = detect_kind(&canonical)
+        .ok_or_else(|| format!("Not a supported archive: {}", archive.display()))?;
+    let member = if rest.as_os_str().is_empty() {
+        String::new()
+    } else {
+        normalize_member(&rest.to_string_lossy())
+            .ok_or_else(|| format!("Invalid archive member path: {}", rest.display()))?
+    };
+    Ok((canonical, kind, member))
+}
+
+/// List the immediate children of a virtual directory inside an archive, in
+/// the same shape and order as `read_directory`. Directories that only exist
+/// implicitly (a zip with `a/b.txt` but no `a/` entry) are synthesised.
+#[tauri::command]
+pub fn read_archive_directory(
+    path: String,
+    project_root: String,
+) -> Result<Vec<FileEntry>, String> {
+    let (archive, kind, member) = resolve_virtual(&path, &project_root)?;
+    let (entries, _) = list_entries(&archive, kind, MAX_ARCHIVE_ENTRIES)?;
+
+    let prefix = if member.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", member)
+    };
+    if !member.is_empty()
+        && !entries
+            .iter()
+            .any(|e| e.path.starts_with(&prefix) || (e.path == member && e.is_dir))
+    {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    // name -> is_dir; a name seen as both a file and a dir prefix is a dir.
+    let mut children: BTreeMap<String, bool> = BTreeMap::new();
+    for entry in &entries {
+        let Some(rest) = entry.path.strip_prefix(&prefix) else {
+            continue;
+        };
+        if rest.is_empty() {
+            continue;
+        }
+        match rest.split_once('/') {
+            Some((dir, _)) => {
+                children.insert(dir.to_string(), true);
+            }
+            None => {
+                *children.entry(rest.to_string()).or_insert(false) |= entry.is_dir;
+            }
+        }
+    }
+
+    let base = archive.to_string_lossy();
+    let mut result: Vec<FileEntry> = children
+        .into_iter()
+        .map(|(name, is_dir)| FileEntry {
+            path: format!("{}/{}{}", base, prefix, name),
+            name,
+            is_dir,
+            // Nested archives aren't browsable; the rest is filesystem metadata.
+            ..Default::default()
+        })
+        .collect();
+
+    result.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    Ok(result)
+}
+
+/// Read a text member of an archive by virtual path, with the same size limit
+/// and encoding handling as `read_file`.
+#[tauri::command]
+pub fn read_archive_file(path: String, project_root: String) -> Result<String, String> {
+    let (archive, kind, member) = resolve_virtual(&path, &project_root)?;
+    if member.is_empty() {
+        return Err(format!("Not a file: {}", path));
+    }
+    let bytes = read_member(&archive, kind, &member, MAX_FILE_SIZE)?;
+    match encoding::decode(&bytes) {
+        Ok(decoded) => Ok(decoded.text),
+        Err(kind) => Err(format!("File is not text: {}", kind)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn test_root(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("codezilla_archive_{}_{}", name, unique));
+        std::fs::create_dir_all(&root).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    fn write_zip(path: &Path, files: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, data) in files {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    fn write_tar_gz(path: &Path, files: &[(&str, &[u8])]) {
+        let gz = flate2::write::GzEncoder::new(File::create(path).unwrap(), Default::default());
+        let mut tar = tar::Builder::new(gz);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, *data).unwrap();
+        }
+        tar.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[test]
+    fn normalizes_and_rejects_escaping_members() {
+        assert_eq!(normalize_member("./a//b/"), Some("a/b".to_string()));
+        assert_eq!(normalize_member("a\\b.txt"), Some("a/b.txt".to_string()));
+        assert_eq!(normalize_member("../etc/passwd"), None);
+        assert_eq!(normalize_member("/etc/passwd"), None);
+        assert_eq!(normalize_member("./"), None);
+    }
+
+    #[test]
+    fn lists_zip_directories_including_implicit_ones() {
+        let root = test_root("zip");
+        let zip_path = root.join("release.zip");
+        write_zip(&zip_path, &[("bin/tool", b"x"), ("README.md", b"hi")]);
+        let root_str = root.to_string_lossy().to_string();
+
+        let top = read_archive_directory(zip_path.to_string_lossy().to_string(), root_str.clone())
+            .unwrap();
+        let names: Vec<(&str, bool)> = top.iter().map(|e| (e.name.as_str(), e.is_dir)).collect();
+        assert_eq!(names, vec![("bin", true), ("README.md", false)]);
+        assert_eq!(top[0].path, format!("{}/bin", zip_path.display()));
+
+        let text =
+            read_archive_file(format!("{}/README.md", zip_path.display()), root_str).unwrap();
+        assert_eq!(text, "hi");
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn reads_tar_gz_members_within_size_limit() {
+        let root = test_root("targz");
+        let tgz = root.join("fixtures.tar.gz");
+        write_tar_gz(&tgz, &[("data/a.txt", b"alpha\r\n")]);
+
+        assert_eq!(detect_kind(&tgz), Some(ArchiveKind::TarGz));
+        assert_eq!(
+            read_member(&tgz, ArchiveKind::TarGz, "data/a.txt", 64).unwrap(),
+            b"alpha\r\n"
+        );
+        let err = read_member(&tgz, ArchiveKind::TarGz, "data/a.txt", 3).unwrap_err();
+        assert!(err.contains("too large"));
+        let err = read_member(&tgz, ArchiveKind::TarGz, "data/missing", 64).unwrap_err();
+        assert!(err.contains("No such archive member"));
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn rejects_archives_outside_project_root() {
+        let root = test_root("root");
+        let outside = test_root("outside");
+        let zip_path = outside.join("x.zip");
+        write_zip(&zip_path, &[("a.txt", b"a")]);
+
+        let err = read_archive_file(
+            format!("{}/a.txt", zip_path.display()),
+            root.to_string_lossy().to_string(),
+        )
+        .unwrap_err();
+
+        assert!(err.contains("outside project root"));
+        let _ = std::fs::remove_dir_all(root);
+        let _ = std::fs::remove_dir_all(outside);
+    }
+}
@@ -0,0 +1,376 @@
+//! "What's eating disk in this project?" — a du-style scan of a project tree.
+//!
+//! [`analyze_disk_usage`] walks the project on a blocking thread, streaming
+//! [`DiskUsageEvent::Progress`] over a `Channel` and checking a per-scan cancel
+//! flag between entries. Sizes are allocated bytes (what `du` reports), so
+//! sparse files and APFS clones aren't over-counted. Linked git worktrees are
+//! walked separately and reported in their own list, so a `.claude/worktrees/`
+//! full of `node_modules` shows up as worktrees rather than inflating the
+//! project's own top-level dirs.
+
+use ignore::WalkBuilder;
+use log::info;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+use super::canonicalize_path;
+
+/// Tree depth kept below the scan root when the caller doesn't ask for one.
+const DEFAULT_MAX_DEPTH: usize = 3;
+/// Children kept per directory; the rest are summed into `other_size`.
+const MAX_CHILDREN: usize = 100;
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Cancel flags for in-flight scans, keyed by the caller-chosen scan id.
+pub type DiskUsageState = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiskUsageNode {
+    pub name: String,
+    /// Path relative to the scan root ("" for the root itself).
+    pub path: String,
+    pub size: u64,
+    pub file_count: u64,
+    /// Largest first; at most [`MAX_CHILDREN`] entries, and empty below the
+    /// requested depth (those bytes still count towards `size`).
+    pub children: Vec<DiskUsageNode>,
+    /// Size and count of the children dropped by the [`MAX_CHILDREN`] cap.
+    pub other_size: u64,
+    pub other_count: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct WorktreeUsage {
+    pub path: String,
+    pub branch: Option<String>,
+    /// "claude" | "codex" | "manual" — as classified by `get_git_worktrees`.
+    pub source: String,
+    pub size: u64,
+    pub file_count: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct DiskUsageReport {
+    pub root: DiskUsageNode,
+    pub worktrees: Vec<WorktreeUsage>,
+    pub include_ignored: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", content = "data")]
+pub enum DiskUsageEvent {
+    Progress {
+        files_scanned: u64,
+        bytes_scanned: u64,
+        /// Directory currently being walked, relative to the project.
+        current: String,
+    },
+}
+
+/// Intermediate tree used while walking; converted to [`DiskUsageNode`] at the end.
+#[derive(Default, Debug)]
+struct Tally {
+    size: u64,
+    files: u64,
+    children: HashMap<String, Tally>,
+}
+
+impl Tally {
+    /// Add a file's bytes to every directory on its path, down to `max_depth`.
+    fn add(&mut self, components: &[String], size: u64, max_depth: usize) {
+        self.size += size;
+        self.files += 1;
+        if max_depth == 0 {
+            return;
+        }
+        if let Some((first, rest)) = components.split_first() {
+            self.children
+                .entry(first.clone())
+                .or_default()
+                .add(rest, size, max_depth - 1);
+        }
+    }
+
+    fn into_node(self, name: String, path: String) -> DiskUsageNode {
+        let mut children: Vec<DiskUsageNode> = self
+            .children
+            .into_iter()
+            .map(|(child, tally)| {
+                let child_path = if path.is_empty() {
+                    child.clone()
+                } else {
+                    format!("{}/{}", path, child)
+                };
+                tally.into_node(child, child_path)
+            })
+            .collect();
+        children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+        let dropped = children.split_off(children.len().min(MAX_CHILDREN));
+        DiskUsageNode {
+            name,
+            path,
+            size: self.size,
+            file_count: self.files,
+            children,
+            other_size: dropped.iter().map(|c| c.size).sum(),
+            other_count: dropped.len() as u64,
+        }
+    }
+}
+
+#[cfg(unix)]
+fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    meta.blocks() * 512
+}
+
+#[cfg(not(unix))]
+fn allocated_size(meta: &std::fs::Metadata) -> u64 {
+    meta.len()
+}
+
+/// Walk `root` and tally allocated bytes per directory. Paths under any of
+/// `exclude` are skipped entirely. Symlinks are not followed.
+fn scan(
+    root: &Path,
+    include_ignored: bool,
+    max_depth: usize,
+    exclude: &[PathBuf],
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64, &Path),
+) -> Result<Tally, String> {
+    let exclude = exclude.to_vec();
+    let mut tally = Tally::default();
+    let mut last_progress = Instant::now();
+
+    let walker = WalkBuilder::new(root)
+        .hidden(false)
+        .ignore(!include_ignored)
+        .git_ignore(!include_ignored)
+        .git_global(!include_ignored)
+        .git_exclude(!include_ignored)
+        .require_git(false)
+        .filter_entry(move |entry| !exclude.iter().any(|ex| entry.path() == ex))
+        .build();
+
+    for entry in walker {
+        if cancel.load(Ordering::Relaxed) {
+            return Err("Disk usage scan cancelled".to_string());
+        }
+        // Unreadable dirs are skipped rather than failing the whole scan.
+        let Ok(entry) = entry else { continue };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        let Ok(rel) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        // Only directory components form the tree; the file itself doesn't.
+        let dirs: Vec<String> = rel
+            .parent()
+            .map(|p| {
+                p.components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        tally.add(&dirs, allocated_size(&meta), max_depth);
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            on_progress(tally.files, tally.size, rel.parent().unwrap_or(rel));
+        }
+    }
+
+    Ok(tally)
+}
+
+/// Size up a project, returning a largest-first tree (top-level dirs first,
+/// `max_depth` levels deep) plus each linked worktree's total. Gitignored
+/// paths are skipped unless `include_ignored` is set. Cancel an in-flight scan
+/// with [`cancel_disk_usage`] using the same `scan_id`.
+#[tauri::command]
+pub async fn analyze_disk_usage(
+    state: tauri::State<'_, DiskUsageState>,
+    path: String,
+    scan_id: String,
+    include_ignored: bool,
+    max_depth: Option<usize>,
+    channel: Channel<DiskUsageEvent>,
+) -> Result<DiskUsageReport, String> {
+    let canonical = canonicalize_path(&path)?;
+    if !canonical.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut scans = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+        scans.insert(scan_id.clone(), cancel.clone());
+    }
+
+    // Linked worktrees (not the main one) are reported on their own. Their
+    // paths are excluded from the project walk in case they live inside it.
+    let worktrees: Vec<_> = crate::git::get_git_worktrees(path.clone())
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|w| w.source != "main")
+        .collect();
+
+    let depth = max_depth.unwrap_or(DEFAULT_MAX_DEPTH);
+    let started = Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        let worktrees: Vec<_> = worktrees
+            .into_iter()
+            .filter_map(|w| {
+                let canonical = Path::new(&w.path).canonicalize().ok()?;
+                Some((w, canonical))
+            })
+            .collect();
+        let exclude: Vec<PathBuf> = worktrees.iter().map(|(_, p)| p.clone()).collect();
+
+        let report_progress = |files: u64, bytes: u64, current: &Path| {
+            let _ = channel.send(DiskUsageEvent::Progress {
+                files_scanned: files,
+                bytes_scanned: bytes,
+                current: current.to_string_lossy().to_string(),
+            });
+        };
+
+        let root = scan(
+            &canonical,
+            include_ignored,
+            depth,
+            &exclude,
+            &cancel,
+            report_progress,
+        )?;
+
+        let mut worktree_usage = Vec::new();
+        for (worktree, path) in worktrees {
+            let tally = scan(&path, include_ignored, 0, &[], &cancel, |_, _, _| {})?;
+            worktree_usage.push(WorktreeUsage {
+                path: worktree.path,
+                branch: worktree.branch,
+                source: worktree.source,
+                size: tally.size,
+                file_count: tally.files,
+            });
+        }
+        worktree_usage.sort_by_key(|w| std::cmp::Reverse(w.size));
+
+        let name = canonical
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok::<_, String>((root.into_node(name, String::new()), worktree_usage))
+    })
+    .await
+    .map_err(|e| format!("Disk usage scan failed: {}", e));
+
+    if let Ok(mut scans) = state.lock() {
+        scans.remove(&scan_id);
+    }
+
+    let (root, worktrees) = result??;
+    info!(
+        "Disk usage scan of {} took {}ms ({} files)",
+        path,
+        started.elapsed().as_millis(),
+        root.file_count
+    );
+    Ok(DiskUsageReport {
+        root,
+        worktrees,
+        include_ignored,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Ask an in-flight scan to stop. A no-op if it already finished.
+#[tauri::command]
+pub fn cancel_disk_usage(
+    state: tauri::State<'_, DiskUsageState>,
+    scan_id: String,
+) -> Result<(), String> {
+    let scans = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(cancel) = scans.get(&scan_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn test_root(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("codezilla_du_{}_{}", name, unique));
+        fs::create_dir_all(&root).unwrap();
+        root.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn tallies_sizes_per_directory_largest_first() {
+        let root = test_root("tally");
+        fs::create_dir_all(root.join("target/debug")).unwrap();
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::write(root.join("target/debug/big.bin"), vec![1u8; 64 * 1024]).unwrap();
+        fs::write(root.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("README.md"), "hi").unwrap();
+
+        let tally = scan(&root, true, 1, &[], &AtomicBool::new(false), |_, _, _| {}).unwrap();
+        let node = tally.into_node("root".to_string(), String::new());
+
+        assert_eq!(node.file_count, 3);
+        assert_eq!(node.children[0].name, "target");
+        assert_eq!(node.children[0].path, "target");
+        // Depth 1: target's own subdirs aren't expanded, but still counted.
+        assert!(node.children[0].children.is_empty());
+        assert!(node.children[0].size >= 64 * 1024);
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn respects_gitignore_and_exclusions_unless_asked() {
+        let root = test_root("ignore");
+        fs::create_dir_all(root.join("node_modules/pkg")).unwrap();
+        fs::create_dir_all(root.join("wt")).unwrap();
+        fs::write(root.join(".gitignore"), "node_modules/\n").unwrap();
+        fs::write(root.join("node_modules/pkg/index.js"), "x").unwrap();
+        fs::write(root.join("wt/file"), "x").unwrap();
+        let cancel = AtomicBool::new(false);
+        let exclude = vec![root.join("wt")];
+
+        let ignored = scan(&root, false, 2, &exclude, &cancel, |_, _, _| {}).unwrap();
+        assert!(!ignored.children.contains_key("node_modules"));
+        assert!(!ignored.children.contains_key("wt"));
+
+        let all = scan(&root, true, 2, &exclude, &cancel, |_, _, _| {}).unwrap();
+        assert!(all.children.contains_key("node_modules"));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn cancelled_scan_stops() {
+        let root = test_root("cancel");
+        fs::write(root.join("a"), "a").unwrap();
+        let err = scan(&root, true, 1, &[], &AtomicBool::new(true), |_, _, _| {}).unwrap_err();
+        assert!(err.contains("cancelled"));
+        let _ = fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,367 @@
+//! Content sniffing for file reads and writes.
+//!
+//! Classifies raw bytes as text (with encoding, BOM and line-ending style), an
+//! image, or some other binary format, so `read_file` can say *why* a file
+//! can't be shown instead of failing with "stream did not contain valid
+//! UTF-8". Legacy encodings are transcoded to UTF-8 on read and back on write,
+//! so an edit saved from the preview lands on disk in the same encoding and
+//! line-ending style it was read in.
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use serde::Serialize;
+
+/// How many leading bytes are inspected for NULs / UTF-16 patterns. Matches
+/// git's own binary heuristic window.
+const SNIFF_WINDOW: usize = 8000;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+/// Result of sniffing a file's leading bytes.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileKind {
+    Text {
+        /// WHATWG encoding label, e.g. "UTF-8", "UTF-16LE", "windows-1252".
+        encoding: String,
+        bom: bool,
+        line_ending: LineEnding,
+    },
+    Image {
+        mime: String,
+    },
+    Binary {
+        /// Best guess from magic bytes; "application/octet-stream" if unknown.
+        mime: String,
+    },
+}
+
+impl std::fmt::Display for FileKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileKind::Text { encoding, .. } => write!(f, "text ({})", encoding),
+            FileKind::Image { mime } => write!(f, "image ({})", mime),
+            FileKind::Binary { mime } => write!(f, "binary ({})", mime),
+        }
+    }
+}
+
+/// Everything needed to write edited text back the way it was read.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextFormat {
+    pub encoding: &'static Encoding,
+    pub bom: bool,
+    pub line_ending: LineEnding,
+}
+
+impl Default for TextFormat {
+    fn default() -> Self {
+        TextFormat {
+            encoding: UTF_8,
+            bom: false,
+            line_ending: LineEnding::Lf,
+        }
+    }
+}
+
+/// Decoded file contents. `text` always uses `\n` line endings; the original
+/// style is kept in `format` and restored by [`encode`].
+pub struct DecodedText {
+    pub text: String,
+    pub format: TextFormat,
+}
+
+/// Magic-byte signatures: (offset, signature, mime). Checked in order, so more
+/// specific signatures must come before shorter prefixes of the same bytes.
+/// Signatures made only of printable ASCII ("BM", "MZ", "%PDF-") are only
+/// trusted when the rest of the file doesn't look like text; see [`decode`].
+const MAGIC: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"BM", "image/bmp"),
+    (0, b"\x00\x00\x01\x00", "image/x-icon"),
+    (0, b"II*\x00", "image/tiff"),
+    (0, b"MM\x00*", "image/tiff"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"PK\x05\x06", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\x00", "application/x-xz"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (257, b"ustar", "application/x-tar"),
+    (0, b"\x7fELF", "application/x-elf"),
+    (0, b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    (0, b"\xce\xfa\xed\xfe", "application/x-mach-binary"),
+    (0, b"\xca\xfe\xba\xbe", "application/x-mach-binary"),
+    (0, b"MZ", "application/vnd.microsoft.portable-executable"),
+    (0, b"\x00asm", "application/wasm"),
+    (0, b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (0, b"wOFF", "font/woff"),
+    (0, b"wOF2", "font/woff2"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"ID3", "audio/mpeg"),
+];
+
+/// MIME type guessed from magic bytes, or None if nothing matched.
+pub fn mime_from_magic(bytes: &[u8]) -> Option<&'static str> {
+    // RIFF and ISO-BMFF containers carry their real type a few bytes in.
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" {
+        return match &bytes[8..12] {
+            b"WEBP" => Some("image/webp"),
+            b"WAVE" => Some("audio/wav"),
+            b"AVI " => Some("video/x-msvideo"),
+            _ => None,
+        };
+    }
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        return match &bytes[8..12] {
+            b"avif" | b"avis" => Some("image/avif"),
+            b"heic" | b"heix" | b"mif1" => Some("image/heic"),
+            b"qt  " => Some("video/quicktime"),
+            _ => Some("video/mp4"),
+        };
+    }
+    MAGIC
+        .iter()
+        .find(|(offset, sig, _)| bytes.get(*offset..*offset + sig.len()) == Some(*sig))
+        .map(|(_, _, mime)| *mime)
+}
+
+/// Guess a BOM-less UTF-16 byte order from the NUL pattern of mostly-ASCII
+/// text: every other byte is zero. `window` must have an even length.
+fn guess_utf16(window: &[u8]) -> Option<&'static Encoding> {
+    if window.len() < 4 {
+        return None;
+    }
+    let pairs = window.len() / 2;
+    let even_zeros = window.iter().step_by(2).filter(|&&b| b == 0).count();
+    let odd_zeros = window
+        .iter()
+        .skip(1)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count();
+    // Require a strong, one-sided signal so real binaries aren't misread.
+    if odd_zeros * 10 >= pairs * 7 && even_zeros * 20 <= pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 10 >= pairs * 7 && odd_zeros * 20 <= pairs {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+fn detect_line_ending(text: &str) -> LineEnding {
+    let crlf = text.matches("\r\n").count();
+    let lf = text.matches('\n').count() - crlf;
+    if crlf > lf {
+        LineEnding::Crlf
+    } else {
+        LineEnding::Lf
+    }
+}
+
+/// Decode `bytes` as text, or return the non-text [`FileKind`] explaining why not.
+pub fn decode(bytes: &[u8]) -> Result<DecodedText, FileKind> {
+    let (encoding, bom_len) = match Encoding::for_bom(bytes) {
+        Some((enc, len)) => (enc, len),
+        None => {
+            let window = &bytes[..bytes.len().min(SNIFF_WINDOW)];
+            let plain_utf8 = !window.contains(&0) && std::str::from_utf8(bytes).is_ok();
+            if let Some(mime) = mime_from_magic(bytes).filter(|_| !plain_utf8) {
+                let kind = if mime.starts_with("image/") {
+                    FileKind::Image {
+                        mime: mime.to_string(),
+                    }
+                } else {
+                    FileKind::Binary {
+                        mime: mime.to_string(),
+                    }
+                };
+                return Err(kind);
+            }
+            if let Some(enc) = guess_utf16(&window[..window.len() & !1]) {
+                (enc, 0)
+            } else if window.contains(&0) {
+                return Err(FileKind::Binary {
+                    mime: "application/octet-stream".to_string(),
+                });
+            } else if plain_utf8 {
+                (UTF_8, 0)
+            } else {
+                // Not UTF-8 and no BOM: assume the Western legacy codepage, a
+                // superset of Latin-1 that maps every byte to a character.
+                (WINDOWS_1252, 0)
+            }
+        }
+    };
+
+    let (decoded, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+    let line_ending = detect_line_ending(&decoded);
+    let text = if decoded.contains("\r\n") {
+        decoded.replace("\r\n", "\n")
+    } else {
+        decoded.into_owned()
+    };
+
+    Ok(DecodedText {
+        text,
+        format: TextFormat {
+            encoding,
+            bom: bom_len > 0,
+            line_ending,
+        },
+    })
+}
+
+/// Classify `bytes` without keeping the decoded text.
+pub fn sniff(bytes: &[u8]) -> FileKind {
+    match decode(bytes) {
+        Ok(decoded) => FileKind::Text {
+            encoding: decoded.format.encoding.name().to_string(),
+            bom: decoded.format.bom,
+            line_ending: decoded.format.line_ending,
+        },
+        Err(kind) => kind,
+    }
+}
+
+/// Encode `text` for disk in `format`: restores CRLF line endings, re-emits the
+/// BOM, and transcodes back to the original encoding. Fails rather than
+/// silently substituting characters the target encoding can't represent.
+pub fn encode(text: &str, format: &TextFormat) -> Result<Vec<u8>, String> {
+    let text = match format.line_ending {
+        // Normalise first so content that already has CRLF isn't doubled up.
+        LineEnding::Crlf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        LineEnding::Lf => text.to_string(),
+    };
+
+    let mut out: Vec<u8> = Vec::with_capacity(text.len() + 3);
+    let enc = format.encoding;
+    if enc == UTF_16LE || enc == UTF_16BE {
+        // encoding_rs only decodes UTF-16 (per WHATWG), so encode by hand.
+        let le = enc == UTF_16LE;
+        if format.bom {
+            out.extend_from_slice(if le { &[0xff, 0xfe] } else { &[0xfe, 0xff] });
+        }
+        for unit in text.encode_utf16() {
+            out.extend_from_slice(&if le {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            });
+        }
+        return Ok(out);
+    }
+
+    if format.bom && enc == UTF_8 {
+        out.extend_from_slice(b"\xef\xbb\xbf");
+    }
+    let (encoded, _, unmappable) = enc.encode(&text);
+    if unmappable {
+        return Err(format!(
+            "Content contains characters that cannot be saved as {}",
+            enc.name()
+        ));
+    }
+    out.extend_from_slice(&encoded);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_plain_utf8_with_crlf() {
+        let decoded = decode(b"one\r\ntwo\r\n").unwrap();
+        assert_eq!(decoded.text, "one\ntwo\n");
+        assert_eq!(decoded.format.encoding, UTF_8);
+        assert_eq!(decoded.format.line_ending, LineEnding::Crlf);
+        assert_eq!(
+            encode(&decoded.text, &decoded.format).unwrap(),
+            b"one\r\ntwo\r\n"
+        );
+    }
+
+    #[test]
+    fn round_trips_utf16_with_bom() {
+        let mut bytes = vec![0xff, 0xfe];
+        for unit in "héllo\n".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.text, "héllo\n");
+        assert_eq!(decoded.format.encoding, UTF_16LE);
+        assert!(decoded.format.bom);
+        assert_eq!(encode(&decoded.text, &decoded.format).unwrap(), bytes);
+    }
+
+    #[test]
+    fn detects_bomless_utf16() {
+        let bytes: Vec<u8> = "plain ascii text"
+            .encode_utf16()
+            .flat_map(|u| u.to_be_bytes())
+            .collect();
+        assert_eq!(decode(&bytes).unwrap().format.encoding, UTF_16BE);
+    }
+
+    #[test]
+    fn falls_back_to_windows_1252_and_round_trips() {
+        let bytes = b"caf\xe9 cr\xe8me\n";
+        let decoded = decode(bytes).unwrap();
+        assert_eq!(decoded.text, "café crème\n");
+        assert_eq!(decoded.format.encoding, WINDOWS_1252);
+        assert_eq!(encode(&decoded.text, &decoded.format).unwrap(), bytes);
+    }
+
+    #[test]
+    fn refuses_unmappable_characters_in_legacy_encoding() {
+        let format = TextFormat {
+            encoding: WINDOWS_1252,
+            ..TextFormat::default()
+        };
+        let err = encode("snowman ☃", &format).unwrap_err();
+        assert!(err.contains("windows-1252"));
+    }
+
+    #[test]
+    fn classifies_images_and_binaries() {
+        assert_eq!(
+            sniff(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"),
+            FileKind::Image {
+                mime: "image/png".to_string()
+            }
+        );
+        assert_eq!(
+            sniff(b"\x00asm\x01\x00\x00\x00"),
+            FileKind::Binary {
+                mime: "application/wasm".to_string()
+            }
+        );
+        assert_eq!(
+            sniff(b"abc\x00\x01\x02def\x00"),
+            FileKind::Binary {
+                mime: "application/octet-stream".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn printable_magic_alone_does_not_make_text_binary() {
+        assert!(matches!(sniff(b"BM notes\n"), FileKind::Text { .. }));
+        assert!(matches!(
+            sniff(b"MZ\x90\x00\x03\x00"),
+            FileKind::Binary { .. }
+        ));
+    }
+}
@@ -0,0 +1,1108 @@
+//! Hexdump and header summaries for files the text preview can't show.
+//!
+//! Agents leave behind wasm modules, sqlite databases and compiled artifacts
+//! that `read_file` refuses as binary. [`read_file_hex`] pages through them as
+//! a classic offset / hex / ASCII dump and, on the first page, adds a parsed
+//! header for the formats worth recognising: ELF, Mach-O and PE executables,
+//! zip and tar(.gz/.zst) archives, sqlite databases, and PNG/JPEG images (dimensions and
+//! EXIF). Parsers read only the bytes they need, never the whole file.
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::archive::{self, ArchiveEntry, ArchiveKind};
+use super::encoding::mime_from_magic;
+use super::{canonicalize_path, validate_within_root};
+
+const BYTES_PER_ROW: usize = 16;
+const DEFAULT_PAGE_LEN: u64 = 4096;
+const MAX_PAGE_LEN: u64 = 64 * 1024;
+/// Archive listings and sqlite schemas are capped so a pathological file
+/// can't produce a multi-megabyte IPC payload.
+const MAX_LISTED_ENTRIES: usize = 1000;
+/// Enough for every fixed-offset header parsed here.
+const HEAD_LEN: u64 = 4096;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HexRow {
+    pub offset: u64,
+    /// Space-separated lowercase hex bytes, e.g. "7f 45 4c 46".
+    pub hex: String,
+    /// Printable ASCII, with `.` standing in for everything else.
+    pub ascii: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SqliteObject {
+    /// "table" | "index" | "view" | "trigger"
+    pub kind: String,
+    pub name: String,
+    pub table: String,
+    pub sql: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MachOSlice {
+    pub cpu: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Parsed header summary, tagged by `format` for the frontend.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum FormatHeader {
+    Elf {
+        class: String,
+        endian: String,
+        os_abi: String,
+        file_type: String,
+        machine: String,
+        entry: u64,
+    },
+    MachO {
+        cpu: String,
+        file_type: String,
+        load_commands: u32,
+        /// Per-architecture slices of a universal binary; empty when thin.
+        slices: Vec<MachOSlice>,
+    },
+    Pe {
+        machine: String,
+        pe32_plus: bool,
+        sections: u16,
+        timestamp: u32,
+        subsystem: String,
+        dll: bool,
+    },
+    Archive {
+        kind: ArchiveKind,
+        entries: Vec<ArchiveEntry>,
+        truncated: bool,
+    },
+    Sqlite {
+        page_size: u32,
+        page_count: u32,
+        text_encoding: String,
+        user_version: u32,
+        schema: Vec<SqliteObject>,
+    },
+    Image {
+        mime: String,
+        width: u32,
+        height: u32,
+        /// (tag name, value) pairs from the EXIF block, if any.
+        exif: Vec<(String, String)>,
+    },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HexPage {
+    pub file_size: u64,
+    pub offset: u64,
+    pub rows: Vec<HexRow>,
+    /// Best guess from magic bytes, if recognised.
+    pub mime: Option<String>,
+    /// Only parsed for the first page (offset 0).
+    pub header: Option<FormatHeader>,
+    /// Set when header parsing was attempted but the file is malformed.
+    pub header_error: Option<String>,
+}
+
+/// Render `bytes` (which start at file offset `base`) as hexdump rows.
+pub fn hex_rows(bytes: &[u8], base: u64) -> Vec<HexRow> {
+    bytes
+        .chunks(BYTES_PER_ROW)
+        .enumerate()
+        .map(|(i, chunk)| HexRow {
+            offset: base + (i * BYTES_PER_ROW) as u64,
+            hex: chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" "),
+            ascii: chunk
+                .iter()
+                .map(|&b| {
+                    if (0x20..0x7f).contains(&b) {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Little helpers for fixed-offset reads. All return None when out of bounds so
+// a truncated file yields a parse error instead of a panic.
+
+fn u16_at(b: &[u8], off: usize, le: bool) -> Option<u16> {
+    let s: [u8; 2] = b.get(off..off + 2)?.try_into().ok()?;
+    Some(if le {
+        u16::from_le_bytes(s)
+    } else {
+        u16::from_be_bytes(s)
+    })
+}
+
+fn u32_at(b: &[u8], off: usize, le: bool) -> Option<u32> {
+    let s: [u8; 4] = b.get(off..off + 4)?.try_into().ok()?;
+    Some(if le {
+        u32::from_le_bytes(s)
+    } else {
+        u32::from_be_bytes(s)
+    })
+}
+
+fn u64_at(b: &[u8], off: usize, le: bool) -> Option<u64> {
+    let s: [u8; 8] = b.get(off..off + 8)?.try_into().ok()?;
+    Some(if le {
+        u64::from_le_bytes(s)
+    } else {
+        u64::from_be_bytes(s)
+    })
+}
+
+fn truncated() -> String {
+    "Header is truncated".to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Executables
+
+fn elf_machine(m: u16) -> String {
+    match m {
+        0x03 => "x86".to_string(),
+        0x08 => "mips".to_string(),
+        0x14 => "powerpc".to_string(),
+        0x15 => "powerpc64".to_string(),
+        0x28 => "arm".to_string(),
+        0x3e => "x86_64".to_string(),
+        0xb7 => "aarch64".to_string(),
+        0xf3 => "riscv".to_string(),
+        0xf7 => "bpf".to_string(),
+        0x102 => "loongarch".to_string(),
+        other => format!("0x{:x}", other),
+    }
+}
+
+fn parse_elf(head: &[u8]) -> Result<FormatHeader, String> {
+    let class = *head.get(4).ok_or_else(truncated)?;
+    let data = *head.get(5).ok_or_else(truncated)?;
+    let le = data != 2;
+    let e_type = u16_at(head, 16, le).ok_or_else(truncated)?;
+    let e_machine = u16_at(head, 18, le).ok_or_else(truncated)?;
+    let entry = if class == 2 {
+        u64_at(head, 24, le).ok_or_else(truncated)?
+    } else {
+        u32_at(head, 24, le).ok_or_else(truncated)? as u64
+    };
+    Ok(FormatHeader::Elf {
+        class: if class == 2 { "ELF64" } else { "ELF32" }.to_string(),
+        endian: if le { "little" } else { "big" }.to_string(),
+        os_abi: match head.get(7) {
+            Some(0) => "System V".to_string(),
+            Some(3) => "Linux".to_string(),
+            Some(9) => "FreeBSD".to_string(),
+            Some(other) => format!("0x{:x}", other),
+            None => return Err(truncated()),
+        },
+        file_type: match e_type {
+            1 => "relocatable".to_string(),
+            2 => "executable".to_string(),
+            3 => "shared object".to_string(),
+            4 => "core".to_string(),
+            other => format!("0x{:x}", other),
+        },
+        machine: elf_machine(e_machine),
+        entry,
+    })
+}
+
+fn macho_cpu(cpu: u32) -> String {
+    match cpu {
+        7 => "x86".to_string(),
+        0x0100_0007 => "x86_64".to_string(),
+        12 => "arm".to_string(),
+        0x0100_000c => "arm64".to_string(),
+        0x0200_000c => "arm64_32".to_string(),
+        18 => "powerpc".to_string(),
+        other => format!("0x{:x}", other),
+    }
+}
+
+fn parse_macho(head: &[u8]) -> Result<FormatHeader, String> {
+    let magic = u32_at(head, 0, false).ok_or_else(truncated)?;
+    if magic == 0xcafe_babe {
+        let count = u32_at(head, 4, false).ok_or_else(truncated)?;
+        let mut slices = Vec::new();
+        for i in 0..count as usize {
+            let base = 8 + i * 20;
+            slices.push(MachOSlice {
+                cpu: macho_cpu(u32_at(head, base, false).ok_or_else(truncated)?),
+                offset: u32_at(head, base + 8, false).ok_or_else(truncated)? as u64,
+                size: u32_at(head, base + 12, false).ok_or_else(truncated)? as u64,
+            });
+        }
+        return Ok(FormatHeader::MachO {
+            cpu: "universal".to_string(),
+            file_type: "fat".to_string(),
+            load_commands: 0,
+            slices,
+        });
+    }
+    // Thin binaries store their header in native (little-endian) order.
+    let cpu = u32_at(head, 4, true).ok_or_else(truncated)?;
+    let file_type = u32_at(head, 12, true).ok_or_else(truncated)?;
+    let load_commands = u32_at(head, 16, true).ok_or_else(truncated)?;
+    Ok(FormatHeader::MachO {
+        cpu: macho_cpu(cpu),
+        file_type: match file_type {
+            1 => "object".to_string(),
+            2 => "executable".to_string(),
+            6 => "dylib".to_string(),
+            8 => "bundle".to_string(),
+            0xa => "dsym".to_string(),
+            other => format!("0x{:x}", other),
+        },
+        load_commands,
+        slices: Vec::new(),
+    })
+}
+
+fn parse_pe(head: &[u8]) -> Result<FormatHeader, String> {
+    let pe = u32_at(head, 0x3c, true).ok_or_else(truncated)? as usize;
+    if head.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return Err("MZ stub without a PE header (DOS executable)".to_string());
+    }
+    let coff = pe + 4;
+    let machine = u16_at(head, coff, true).ok_or_else(truncated)?;
+    let sections = u16_at(head, coff + 2, true).ok_or_else(truncated)?;
+    let timestamp = u32_at(head, coff + 4, true).ok_or_else(truncated)?;
+    let characteristics = u16_at(head, coff + 18, true).ok_or_else(truncated)?;
+    let optional = coff + 20;
+    let magic = u16_at(head, optional, true).ok_or_else(truncated)?;
+    let subsystem = u16_at(head, optional + 68, true).ok_or_else(truncated)?;
+    Ok(FormatHeader::Pe {
+        machine: match machine {
+            0x14c => "x86".to_string(),
+            0x8664 => "x86_64".to_string(),
+            0x1c0 | 0x1c4 => "arm".to_string(),
+            0xaa64 => "arm64".to_string(),
+            other => format!("0x{:x}", other),
+        },
+        pe32_plus: magic == 0x20b,
+        sections,
+        timestamp,
+        subsystem: match subsystem {
+            1 => "native".to_string(),
+            2 => "windows gui".to_string(),
+            3 => "windows console".to_string(),
+            10..=13 => "efi".to_string(),
+            other => format!("0x{:x}", other),
+        },
+        dll: characteristics & 0x2000 != 0,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Archives
+
+fn list_archive(path: &Path, kind: ArchiveKind) -> Result<FormatHeader, String> {
+    let (entries, truncated) = archive::list_entries(path, kind, MAX_LISTED_ENTRIES)?;
+    Ok(FormatHeader::Archive {
+        kind,
+        entries,
+        truncated,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// SQLite: walk the `sqlite_schema` b-tree rooted at page 1.
+
+struct SqliteFile {
+    file: File,
+    page_size: usize,
+    usable: usize,
+    /// Pages actually in the file, which bounds any honest payload.
+    page_count: usize,
+    utf16: Option<bool>, // Some(little_endian) for UTF-16 databases
+}
+
+impl SqliteFile {
+    fn page(&mut self, number: u32) -> Result<Vec<u8>, String> {
+        if number == 0 {
+            return Err("Invalid sqlite page number".to_string());
+        }
+        let mut buf = vec![0u8; self.page_size];
+        self.file
+            .seek(SeekFrom::Start((number as u64 - 1) * self.page_size as u64))
+            .and_then(|_| self.file.read_exact(&mut buf))
+            .map_err(|e| format!("Failed to read sqlite page {}: {}", number, e))?;
+        Ok(buf)
+    }
+
+    /// Assemble a cell's full payload, following overflow pages if needed.
+    /// A corrupt length or overflow chain errors out rather than reading
+    /// more than the file holds.
+    fn payload(&mut self, page: &[u8], start: usize, len: u64) -> Result<Vec<u8>, String> {
+        if len > (self.page_count * self.usable) as u64 {
+            return Err("Corrupt sqlite cell: payload is larger than the database".to_string());
+        }
+        let len = len as usize;
+        let u = self.usable;
+        let max_local = u - 35;
+        let local = if len <= max_local {
+            len
+        } else {
+            let min_local = (u - 12) * 32 / 255 - 23;
+            let k = min_local + (len - min_local) % (u - 4);
+            if k <= max_local {
+                k
+            } else {
+                min_local
+            }
+        };
+        let mut out = page
+            .get(start..start + local)
+            .ok_or("Corrupt sqlite cell")?
+            .to_vec();
+        if local < len {
+            let mut next = u32_at(page, start + local, false).ok_or("Corrupt sqlite cell")?;
+            let mut followed = 0;
+            while out.len() < len && next != 0 {
+                followed += 1;
+                if followed > self.page_count {
+                    return Err("Corrupt sqlite cell: overflow pages form a cycle".to_string());
+                }
+                let overflow = self.page(next)?;
+                next = u32_at(&overflow, 0, false).ok_or("Corrupt overflow page")?;
+                let take = (len - out.len()).min(u - 4);
+                out.extend_from_slice(&overflow[4..4 + take]);
+            }
+        }
+        Ok(out)
+    }
+
+    fn text(&self, bytes: &[u8]) -> String {
+        match self.utf16 {
+            Some(le) => {
+                let units: Vec<u16> = bytes
+                    .chunks_exact(2)
+                    .map(|c| {
+                        if le {
+                            u16::from_le_bytes([c[0], c[1]])
+                        } else {
+                            u16::from_be_bytes([c[0], c[1]])
+                        }
+                    })
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+            None => String::from_utf8_lossy(bytes).to_string(),
+        }
+    }
+}
+
+/// SQLite varint: big-endian base-128, up to 9 bytes. Returns (value, length).
+fn varint(b: &[u8], off: usize) -> Option<(u64, usize)> {
+    let mut value: u64 = 0;
+    for i in 0..9 {
+        let byte = *b.get(off + i)?;
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Decode the text columns of a schema record (type, name, tbl_name, rootpage, sql).
+fn schema_row(db: &SqliteFile, record: &[u8]) -> Option<SqliteObject> {
+    let (header_len, mut pos) = varint(record, 0)?;
+    let mut types = Vec::new();
+    while pos < header_len as usize {
+        let (t, n) = varint(record, pos)?;
+        types.push(t);
+        pos += n;
+    }
+    let mut body = header_len as usize;
+    let mut cols: Vec<Option<String>> = Vec::new();
+    for t in types {
+        let size = match t {
+            0 | 8 | 9 => 0,
+            1..=4 => t as usize,
+            5 => 6,
+            6 | 7 => 8,
+            n if n >= 12 => ((n - 12) / 2) as usize,
+            _ => return None,
+        };
+        let value = record.get(body..body + size)?;
+        cols.push(if t >= 13 && t % 2 == 1 {
+            Some(db.text(value))
+        } else {
+            None
+        });
+        body += size;
+    }
+    Some(SqliteObject {
+        kind: cols.first()?.clone()?,
+        name: cols.get(1)?.clone()?,
+        table: cols.get(2)?.clone().unwrap_or_default(),
+        sql: cols.get(4).cloned().flatten(),
+    })
+}
+
+fn parse_sqlite(path: &Path, head: &[u8]) -> Result<FormatHeader, String> {
+    let raw_page_size = u16_at(head, 16, false).ok_or_else(truncated)?;
+    let page_size: u32 = if raw_page_size == 1 {
+        65536
+    } else {
+        raw_page_size as u32
+    };
+    let reserved = *head.get(20).ok_or_else(truncated)? as usize;
+    let page_count = u32_at(head, 28, false).ok_or_else(truncated)?;
+    let encoding = u32_at(head, 56, false).ok_or_else(truncated)?;
+    let user_version = u32_at(head, 60, false).ok_or_else(truncated)?;
+    if page_size < 512 || !page_size.is_power_of_two() || reserved >= page_size as usize - 480 {
+        return Err("Invalid sqlite page size".to_string());
+    }
+
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let file_len = file
+        .metadata()
+        .map_err(|e| format!("Cannot read metadata: {}", e))?
+        .len();
+    let mut db = SqliteFile {
+        file,
+        page_size: page_size as usize,
+        usable: page_size as usize - reserved,
+        page_count: (file_len / page_size as u64) as usize,
+        utf16: match encoding {
+            2 => Some(true),
+            3 => Some(false),
+            _ => None,
+        },
+    };
+
+    let mut schema = Vec::new();
+    let mut stack = vec![1u32];
+    let mut visited = 0usize;
+    while let Some(number) = stack.pop() {
+        // Guard against cycles in a corrupt file.
+        visited += 1;
+        if visited > 10_000 || schema.len() >= MAX_LISTED_ENTRIES {
+            break;
+        }
+        let page = db.page(number)?;
+        let hdr = if number == 1 { 100 } else { 0 };
+        let kind = *page.get(hdr).ok_or("Corrupt sqlite page")?;
+        let cells = u16_at(&page, hdr + 3, false).ok_or("Corrupt sqlite page")? as usize;
+        match kind {
+            // Interior table page: recurse into every child, right-most last.
+            0x05 => {
+                let right = u32_at(&page, hdr + 8, false).ok_or("Corrupt sqlite page")?;
+                stack.push(right);
+                for i in (0..cells).rev() {
+                    let ptr =
+                        u16_at(&page, hdr + 12 + i * 2, false).ok_or("Corrupt sqlite page")?;
+                    stack.push(u32_at(&page, ptr as usize, false).ok_or("Corrupt sqlite cell")?);
+                }
+            }
+            // Leaf table page: each cell is (payload len, rowid, record).
+            0x0d => {
+                for i in 0..cells {
+                    let ptr = u16_at(&page, hdr + 8 + i * 2, false).ok_or("Corrupt sqlite page")?
+                        as usize;
+                    let (len, a) = varint(&page, ptr).ok_or("Corrupt sqlite cell")?;
+                    let (_, b) = varint(&page, ptr + a).ok_or("Corrupt sqlite cell")?;
+                    let record = db.payload(&page, ptr + a + b, len)?;
+                    if let Some(obj) = schema_row(&db, &record) {
+                        schema.push(obj);
+                    }
+                }
+            }
+            other => return Err(format!("Unexpected sqlite page type 0x{:x}", other)),
+        }
+    }
+
+    Ok(FormatHeader::Sqlite {
+        page_size,
+        page_count,
+        text_encoding: match encoding {
+            2 => "UTF-16le",
+            3 => "UTF-16be",
+            _ => "UTF-8",
+        }
+        .to_string(),
+        user_version,
+        schema,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Images
+
+/// Named EXIF tags worth surfacing. Anything else is skipped.
+fn exif_tag_name(tag: u16) -> Option<&'static str> {
+    Some(match tag {
+        0x010f => "Make",
+        0x0110 => "Model",
+        0x0112 => "Orientation",
+        0x0131 => "Software",
+        0x0132 => "DateTime",
+        0x829a => "ExposureTime",
+        0x829d => "FNumber",
+        0x8827 => "ISO",
+        0x9003 => "DateTimeOriginal",
+        0x920a => "FocalLength",
+        0xa002 => "PixelXDimension",
+        0xa003 => "PixelYDimension",
+        _ => return None,
+    })
+}
+
+/// Parse a TIFF-structured EXIF block (starting at the "II"/"MM" byte order
+/// mark) into (tag, value) pairs from IFD0 and the Exif sub-IFD.
+fn parse_exif(tiff: &[u8]) -> Vec<(String, String)> {
+    let le = match tiff.get(0..2) {
+        Some(b"II") => true,
+        Some(b"MM") => false,
+        _ => return Vec::new(),
+    };
+    let mut out = Vec::new();
+    let mut ifds = match u32_at(tiff, 4, le) {
+        Some(off) => vec![off as usize],
+        None => return out,
+    };
+    let mut seen = 0;
+    while let Some(ifd) = ifds.pop() {
+        seen += 1;
+        if seen > 4 {
+            break;
+        }
+        let Some(count) = u16_at(tiff, ifd, le) else {
+            continue;
+        };
+        for i in 0..count as usize {
+            let e = ifd + 2 + i * 12;
+            let (Some(tag), Some(kind), Some(n)) = (
+                u16_at(tiff, e, le),
+                u16_at(tiff, e + 2, le),
+                u32_at(tiff, e + 4, le),
+            ) else {
+                break;
+            };
+            if tag == 0x8769 {
+                if let Some(sub) = u32_at(tiff, e + 8, le) {
+                    ifds.push(sub as usize);
+                }
+                continue;
+            }
+            let Some(name) = exif_tag_name(tag) else {
+                continue;
+            };
+            let value = match kind {
+                // ASCII: inline when <= 4 bytes, otherwise at an offset.
+                2 => {
+                    let n = n as usize;
+                    let start = if n <= 4 {
+                        e + 8
+                    } else {
+                        u32_at(tiff, e + 8, le).unwrap_or(0) as usize
+                    };
+                    tiff.get(start..start + n).map(|s| {
+                        String::from_utf8_lossy(s)
+                            .trim_end_matches('\0')
+                            .trim()
+                            .to_string()
+                    })
+                }
+                3 => u16_at(tiff, e + 8, le).map(|v| v.to_string()),
+                4 => u32_at(tiff, e + 8, le).map(|v| v.to_string()),
+                // RATIONAL: numerator/denominator at an offset.
+                5 => u32_at(tiff, e + 8, le).and_then(|off| {
+                    let num = u32_at(tiff, off as usize, le)?;
+                    let den = u32_at(tiff, off as usize + 4, le)?;
+                    Some(if den == 1 {
+                        num.to_string()
+                    } else {
+                        format!("{}/{}", num, den)
+                    })
+                }),
+                _ => None,
+            };
+            if let Some(value) = value {
+                out.push((name.to_string(), value));
+            }
+        }
+    }
+    out
+}
+
+fn parse_png(path: &Path, head: &[u8]) -> Result<FormatHeader, String> {
+    if head.get(12..16) != Some(b"IHDR") {
+        return Err("PNG is missing its IHDR chunk".to_string());
+    }
+    let width = u32_at(head, 16, false).ok_or_else(truncated)?;
+    let height = u32_at(head, 20, false).ok_or_else(truncated)?;
+
+    // EXIF lives in an optional eXIf chunk before the image data.
+    let mut exif = Vec::new();
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut pos: u64 = 8;
+    let mut chunk_header = [0u8; 8];
+    while file.seek(SeekFrom::Start(pos)).is_ok() && file.read_exact(&mut chunk_header).is_ok() {
+        let len = u32::from_be_bytes([
+            chunk_header[0],
+            chunk_header[1],
+            chunk_header[2],
+            chunk_header[3],
+        ]);
+        match &chunk_header[4..8] {
+            b"eXIf" => {
+                let mut data = vec![0u8; (len as usize).min(1 << 20)];
+                if file.read_exact(&mut data).is_ok() {
+                    exif = parse_exif(&data);
+                }
+                break;
+            }
+            b"IDAT" | b"IEND" => break,
+            _ => pos += 12 + len as u64,
+        }
+    }
+
+    Ok(FormatHeader::Image {
+        mime: "image/png".to_string(),
+        width,
+        height,
+        exif,
+    })
+}
+
+fn parse_jpeg(path: &Path) -> Result<FormatHeader, String> {
+    let mut file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut exif = Vec::new();
+    let mut pos: u64 = 2;
+    let mut marker = [0u8; 4];
+    loop {
+        file.seek(SeekFrom::Start(pos))
+            .and_then(|_| file.read_exact(&mut marker))
+            .map_err(|_| "JPEG ended before its frame header".to_string())?;
+        if marker[0] != 0xff {
+            return Err("Corrupt JPEG marker".to_string());
+        }
+        let len = u16::from_be_bytes([marker[2], marker[3]]) as usize;
+        match marker[1] {
+            // APP1 carries EXIF as "Exif\0\0" followed by a TIFF structure.
+            0xe1 => {
+                let mut data = vec![0u8; len.saturating_sub(2)];
+                if file.read_exact(&mut data).is_ok() && data.starts_with(b"Exif\0\0") {
+                    exif = parse_exif(&data[6..]);
+                }
+            }
+            // SOFn frame headers (excluding DHT/JPG/DAC) hold the dimensions.
+            0xc0..=0xcf if !matches!(marker[1], 0xc4 | 0xc8 | 0xcc) => {
+                let mut frame = [0u8; 5];
+                file.read_exact(&mut frame)
+                    .map_err(|_| "JPEG frame header is truncated".to_string())?;
+                return Ok(FormatHeader::Image {
+                    mime: "image/jpeg".to_string(),
+                    width: u16::from_be_bytes([frame[3], frame[4]]) as u32,
+                    height: u16::from_be_bytes([frame[1], frame[2]]) as u32,
+                    exif,
+                });
+            }
+            0xd9 | 0xda => return Err("JPEG has no frame header".to_string()),
+            _ => {}
+        }
+        pos += 2 + len as u64;
+    }
+}
+
+/// Parse a header summary for recognised formats. Ok(None) means the format
+/// simply isn't one we summarise.
+fn parse_header(
+    path: &Path,
+    head: &[u8],
+    mime: Option<&str>,
+) -> Result<Option<FormatHeader>, String> {
+    let header = match mime {
+        Some("application/x-elf") => parse_elf(head)?,
+        // 0xcafebabe is shared with Java class files, whose "arch count" slot
+        // holds a class-file version (>= 45) rather than a small slice count.
+        Some("application/x-mach-binary")
+            if head.starts_with(b"\xca\xfe\xba\xbe")
+                && u32_at(head, 4, false).unwrap_or(0) >= 45 =>
+        {
+            return Ok(None)
+        }
+        Some("application/x-mach-binary") => parse_macho(head)?,
+        Some("application/vnd.microsoft.portable-executable") => parse_pe(head)?,
+        Some("application/zip") => list_archive(path, ArchiveKind::Zip)?,
+        Some("application/x-tar") => list_archive(path, ArchiveKind::Tar)?,
+        // Compressed streams are only summarised when they wrap a tarball.
+        Some("application/gzip") | Some("application/zstd") => match archive::detect_kind(path) {
+            Some(kind @ (ArchiveKind::TarGz | ArchiveKind::TarZst)) => list_archive(path, kind)?,
+            _ => return Ok(None),
+        },
+        Some("application/vnd.sqlite3") => parse_sqlite(path, head)?,
+        Some("image/png") => parse_png(path, head)?,
+        Some("image/jpeg") => parse_jpeg(path)?,
+        _ => return Ok(None),
+    };
+    Ok(Some(header))
+}
+
+/// Paginated hexdump of any file, plus a parsed header summary on the first
+/// page. `offset` and `length` default to the start of the file and 4 KiB;
+/// `length` is capped at 64 KiB per call.
+#[tauri::command]
+pub fn read_file_hex(
+    path: String,
+    project_root: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<HexPage, String> {
+    let file_path = canonicalize_path(&path)?;
+    let canonical_root = canonicalize_path(&project_root)?;
+    validate_within_root(&file_path, &canonical_root)?;
+
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+
+    let mut file = File::open(&file_path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let file_size = file
+        .metadata()
+        .map_err(|e| format!("Cannot read metadata: {}", e))?
+        .len();
+
+    // Align to a row boundary so offsets in the dump stay tidy.
+    let offset = offset.unwrap_or(0).min(file_size) / BYTES_PER_ROW as u64 * BYTES_PER_ROW as u64;
+    let length = length.unwrap_or(DEFAULT_PAGE_LEN).min(MAX_PAGE_LEN);
+
+    let mut page = Vec::new();
+    file.seek(SeekFrom::Start(offset))
+        .and_then(|_| (&mut file).take(length).read_to_end(&mut page))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+
+    let mut head = Vec::new();
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| (&mut file).take(HEAD_LEN).read_to_end(&mut head))
+        .map_err(|e| format!("Failed to read file: {}", e))?;
+    let mime = mime_from_magic(&head);
+
+    let (header, header_error) = if offset == 0 {
+        match parse_header(&file_path, &head, mime) {
+            Ok(header) => (header, None),
+            Err(e) => (None, Some(e)),
+        }
+    } else {
+        (None, None)
+    };
+
+    Ok(HexPage {
+        file_size,
+        offset,
+        rows: hex_rows(&page, offset),
+        mime: mime.map(str::to_string),
+        header,
+        header_error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn fixture(name: &str, bytes: &[u8]) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let path = std::env::temp_dir().join(format!("codezilla_inspect_{}_{}", unique, name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// A two-page, 512-byte-page database whose schema page holds one cell
+    /// with `payload_len` and `record`, followed by overflow page 2.
+    fn sqlite_db(payload_len: &[u8], record: &[u8], overflow: &[u8]) -> Vec<u8> {
+        let mut db = vec![0u8; 1024];
+        db[..16].copy_from_slice(b"SQLite format 3\0");
+        db[16..18].copy_from_slice(&512u16.to_be_bytes());
+        db[28..32].copy_from_slice(&2u32.to_be_bytes());
+        db[56..60].copy_from_slice(&1u32.to_be_bytes());
+        db[60..64].copy_from_slice(&7u32.to_be_bytes());
+        // Leaf table page header with one cell at offset 200.
+        db[100] = 0x0d;
+        db[103..105].copy_from_slice(&1u16.to_be_bytes());
+        db[108..110].copy_from_slice(&200u16.to_be_bytes());
+        let mut cell = payload_len.to_vec();
+        cell.push(1); // rowid
+        cell.extend_from_slice(record);
+        db[200..200 + cell.len()].copy_from_slice(&cell);
+        db[512..512 + overflow.len()].copy_from_slice(overflow);
+        db
+    }
+
+    #[test]
+    fn parses_sqlite_schema() {
+        // (type, name, tbl_name, rootpage, sql) as a record: header, then body.
+        let sql = b"CREATE TABLE t(x)";
+        let mut record = vec![6, 13 + 2 * 5, 13 + 2, 13 + 2, 1, 13 + 2 * sql.len() as u8];
+        record.extend_from_slice(b"tablett\x02");
+        record.extend_from_slice(sql);
+        let path = fixture("schema.db", &sqlite_db(&[record.len() as u8], &record, &[]));
+        let head = std::fs::read(&path).unwrap();
+        match parse_sqlite(&path, &head).unwrap() {
+            FormatHeader::Sqlite {
+                page_size,
+                page_count,
+                user_version,
+                schema,
+                ..
+            } => {
+                assert_eq!((page_size, page_count, user_version), (512, 2, 7));
+                assert_eq!(schema.len(), 1);
+                assert_eq!(schema[0].kind, "table");
+                assert_eq!(schema[0].name, "t");
+                assert_eq!(schema[0].sql.as_deref(), Some("CREATE TABLE t(x)"));
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn corrupt_sqlite_overflow_errors_instead_of_looping() {
+        // A ~16 MB payload whose overflow page points back at itself.
+        let len = [0x87, 0xff, 0xff, 0x7f];
+        let mut local = vec![0u8; 300];
+        local[39..43].copy_from_slice(&2u32.to_be_bytes());
+        let path = fixture("cycle.db", &sqlite_db(&len, &local, &2u32.to_be_bytes()));
+        let head = std::fs::read(&path).unwrap();
+        let err = parse_sqlite(&path, &head).unwrap_err();
+        assert!(err.starts_with("Corrupt sqlite cell"), "{}", err);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn parses_png_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&640u32.to_be_bytes());
+        png.extend_from_slice(&480u32.to_be_bytes());
+        png.extend_from_slice(&[8, 6, 0, 0, 0, 0, 0, 0, 0]);
+        png.extend_from_slice(b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+        let path = fixture("image.png", &png);
+        match parse_png(&path, &png).unwrap() {
+            FormatHeader::Image {
+                mime,
+                width,
+                height,
+                exif,
+            } => {
+                assert_eq!(mime, "image/png");
+                assert_eq!((width, height), (640, 480));
+                assert!(exif.is_empty());
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn parses_jpeg_frame_and_exif() {
+        let mut tiff = b"II*\x00\x08\x00\x00\x00".to_vec();
+        tiff.extend_from_slice(&1u16.to_le_bytes());
+        tiff.extend_from_slice(&0x010fu16.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&4u32.to_le_bytes());
+        tiff.extend_from_slice(b"Foo\x00");
+        let mut jpeg = b"\xff\xd8\xff\xe1".to_vec();
+        jpeg.extend_from_slice(&(2 + 6 + tiff.len() as u16).to_be_bytes());
+        jpeg.extend_from_slice(b"Exif\x00\x00");
+        jpeg.extend_from_slice(&tiff);
+        // SOF0: length, precision, height, width, components.
+        jpeg.extend_from_slice(b"\xff\xc0\x00\x11\x08");
+        jpeg.extend_from_slice(&300u16.to_be_bytes());
+        jpeg.extend_from_slice(&400u16.to_be_bytes());
+        jpeg.extend_from_slice(&[0; 12]);
+        let path = fixture("photo.jpg", &jpeg);
+        match parse_jpeg(&path).unwrap() {
+            FormatHeader::Image {
+                width,
+                height,
+                exif,
+                ..
+            } => {
+                assert_eq!((width, height), (400, 300));
+                assert_eq!(exif, vec![("Make".to_string(), "Foo".to_string())]);
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn parses_pe32_plus_dll() {
+        let mut head = vec![0u8; 0x40 + 24 + 70];
+        head[..2].copy_from_slice(b"MZ");
+        head[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        head[0x40..0x44].copy_from_slice(b"PE\0\0");
+        let coff = 0x44;
+        head[coff..coff + 2].copy_from_slice(&0x8664u16.to_le_bytes());
+        head[coff + 2..coff + 4].copy_from_slice(&5u16.to_le_bytes());
+        head[coff + 4..coff + 8].copy_from_slice(&1_700_000_000u32.to_le_bytes());
+        head[coff + 18..coff + 20].copy_from_slice(&0x2022u16.to_le_bytes());
+        let optional = coff + 20;
+        head[optional..optional + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        head[optional + 68..optional + 70].copy_from_slice(&3u16.to_le_bytes());
+        match parse_pe(&head).unwrap() {
+            FormatHeader::Pe {
+                machine,
+                pe32_plus,
+                sections,
+                timestamp,
+                subsystem,
+                dll,
+            } => {
+                assert_eq!(machine, "x86_64");
+                assert!(pe32_plus && dll);
+                assert_eq!((sections, timestamp), (5, 1_700_000_000));
+                assert_eq!(subsystem, "windows console");
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_thin_and_universal_macho() {
+        let mut thin = b"\xcf\xfa\xed\xfe".to_vec();
+        thin.extend_from_slice(&0x0100_000cu32.to_le_bytes());
+        thin.extend_from_slice(&0u32.to_le_bytes());
+        thin.extend_from_slice(&2u32.to_le_bytes());
+        thin.extend_from_slice(&17u32.to_le_bytes());
+        match parse_macho(&thin).unwrap() {
+            FormatHeader::MachO {
+                cpu,
+                file_type,
+                load_commands,
+                slices,
+            } => {
+                assert_eq!((cpu.as_str(), file_type.as_str()), ("arm64", "executable"));
+                assert_eq!(load_commands, 17);
+                assert!(slices.is_empty());
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+
+        let mut fat = b"\xca\xfe\xba\xbe".to_vec();
+        fat.extend_from_slice(&2u32.to_be_bytes());
+        for (cpu, offset) in [(0x0100_0007u32, 0x1000u32), (0x0100_000c, 0x8000)] {
+            for field in [cpu, 0, offset, 0x4000, 12] {
+                fat.extend_from_slice(&field.to_be_bytes());
+            }
+        }
+        match parse_macho(&fat).unwrap() {
+            FormatHeader::MachO { cpu, slices, .. } => {
+                assert_eq!(cpu, "universal");
+                let slices: Vec<_> = slices
+                    .iter()
+                    .map(|s| (s.cpu.as_str(), s.offset, s.size))
+                    .collect();
+                assert_eq!(
+                    slices,
+                    [("x86_64", 0x1000, 0x4000), ("arm64", 0x8000, 0x4000)]
+                );
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hex_rows_render_offsets_and_ascii() {
+        let rows = hex_rows(b"\x7fELF hello, world!\x00", 0x20);
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].offset, 0x20);
+        assert_eq!(
+            rows[0].hex,
+            "7f 45 4c 46 20 68 65 6c 6c 6f 2c 20 77 6f 72 6c"
+        );
+        assert_eq!(rows[0].ascii, ".ELF hello, worl");
+        assert_eq!(rows[1].offset, 0x30);
+        assert_eq!(rows[1].ascii, "d!.");
+    }
+
+    #[test]
+    fn parses_elf64_header() {
+        let mut head = vec![0u8; 64];
+        head[..4].copy_from_slice(b"\x7fELF");
+        head[4] = 2; // 64-bit
+        head[5] = 1; // little-endian
+        head[16..18].copy_from_slice(&3u16.to_le_bytes());
+        head[18..20].copy_from_slice(&0x3eu16.to_le_bytes());
+        head[24..32].copy_from_slice(&0x1040u64.to_le_bytes());
+        match parse_elf(&head).unwrap() {
+            FormatHeader::Elf {
+                class,
+                file_type,
+                machine,
+                entry,
+                ..
+            } => {
+                assert_eq!(class, "ELF64");
+                assert_eq!(file_type, "shared object");
+                assert_eq!(machine, "x86_64");
+                assert_eq!(entry, 0x1040);
+            }
+            other => panic!("unexpected header: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn truncated_headers_error_instead_of_panicking() {
+        assert!(parse_elf(b"\x7fELF\x02\x01").is_err());
+        assert!(parse_pe(b"MZ").is_err());
+    }
+
+    #[test]
+    fn parses_exif_ascii_and_short_tags() {
+        // Little-endian TIFF with one IFD holding Make="Foo" and Orientation=6.
+        let mut tiff = b"II*\x00\x08\x00\x00\x00".to_vec();
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&0x010fu16.to_le_bytes());
+        tiff.extend_from_slice(&2u16.to_le_bytes());
+        tiff.extend_from_slice(&4u32.to_le_bytes());
+        tiff.extend_from_slice(b"Foo\x00");
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes());
+        tiff.extend_from_slice(&3u16.to_le_bytes());
+        tiff.extend_from_slice(&1u32.to_le_bytes());
+        tiff.extend_from_slice(&[6, 0, 0, 0]);
+        assert_eq!(
+            parse_exif(&tiff),
+            vec![
+                ("Make".to_string(), "Foo".to_string()),
+                ("Orientation".to_string(), "6".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn sqlite_varints_decode() {
+        assert_eq!(varint(&[0x05], 0), Some((5, 1)));
+        assert_eq!(varint(&[0x81, 0x00], 0), Some((128, 2)));
+        assert_eq!(varint(&[0x81], 0), None);
+    }
+}
@@ -1,9 +1,14 @@
+pub mod archive;
+pub mod disk_usage;
+pub mod encoding;
+pub mod inspect;
 pub mod watcher;
 
 use ignore::WalkBuilder;
 use log::error;
 use serde::Serialize;
 use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
 
 /// macOS system files that should never appear in the file explorer.
 const HIDDEN_NAMES: &[&str] = &[
@@ -37,15 +42,151 @@ pub fn validate_within_root(path: &std::path::Path, root: &std::path::Path) -> R
     Ok(())
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Clone, Default)]
 pub struct FileEntry {
     pub name: String,
     pub path: String,
+    /// For a symlink, whether its target is a dir when the link is followed
+    /// — by default, when it stays inside the project root — and false
+    /// otherwise (the link itself is never a dir).
     pub is_dir: bool,
+    /// A zip/tar file the tree can expand via `read_archive_directory`.
+    pub is_archive: bool,
+    pub is_symlink: bool,
+    /// Raw link contents as stored on disk (may be relative).
+    pub link_target: Option<String>,
+    /// Symlink whose target doesn't exist.
+    pub broken: bool,
+    /// Symlink resolving (or, if broken, pointing) outside the project root.
+    pub points_outside_root: bool,
+    pub size: u64,
+    pub mtime_ms: u64,
+    /// Any execute bit set (always false off unix).
+    pub executable: bool,
+}
+
+/// Lexically resolve `..`/`.` in `path` without touching the filesystem, for
+/// link targets that don't exist.
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                out.pop();
+            }
+            Component::CurDir => {}
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+/// Where the symlink at `link` points: canonical if it resolves, otherwise the
+/// lexically-normalised target. The flag is true when the target is missing.
+fn resolve_link(link: &Path) -> Option<(PathBuf, bool)> {
+    let target = std::fs::read_link(link).ok()?;
+    match link.canonicalize() {
+        Ok(resolved) => Some((resolved, false)),
+        Err(_) => {
+            let joined = link.parent().unwrap_or(Path::new("/")).join(&target);
+            Some((normalize_lexically(&joined), true))
+        }
+    }
 }
 
+/// The first symlink on `path` (the path itself or one of its ancestors below
+/// `raw_root`) that resolves outside `canonical_root`, if any. Used to give
+/// writes through escaping links a clear refusal rather than a generic
+/// "outside project root".
+fn find_escaping_link(path: &Path, raw_root: &Path, canonical_root: &Path) -> Option<PathBuf> {
+    for ancestor in path.ancestors() {
+        if ancestor == raw_root || !ancestor.starts_with(raw_root) {
+            break;
+        }
+        let is_link = std::fs::symlink_metadata(ancestor).is_ok_and(|m| m.file_type().is_symlink());
+        if !is_link {
+            continue;
+        }
+        if let Some((resolved, _)) = resolve_link(ancestor) {
+            if !resolved.starts_with(canonical_root) {
+                return Some(ancestor.to_path_buf());
+            }
+        }
+    }
+    None
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.is_file() && meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Build a [`FileEntry`] for `p` from its own (lstat) metadata. A live
+/// symlink that is followed takes its type, size and mtime from its target;
+/// `follow_links` of None follows only links that stay inside the root.
+fn file_entry(p: &Path, canonical_root: &Path, follow_links: Option<bool>) -> FileEntry {
+    let name = p
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let own = std::fs::symlink_metadata(p).ok();
+    let is_symlink = own.as_ref().is_some_and(|m| m.file_type().is_symlink());
+
+    let mut entry = FileEntry {
+        path: p.to_string_lossy().to_string(),
+        is_symlink,
+        ..Default::default()
+    };
+    if is_symlink {
+        entry.link_target = std::fs::read_link(p)
+            .ok()
+            .map(|t| t.to_string_lossy().to_string());
+        if let Some((resolved, broken)) = resolve_link(p) {
+            entry.broken = broken;
+            entry.points_outside_root = !resolved.starts_with(canonical_root);
+        }
+    }
+
+    let follow = follow_links.unwrap_or(!entry.points_outside_root);
+    let meta = if is_symlink && follow && !entry.broken {
+        std::fs::metadata(p).ok()
+    } else {
+        own
+    };
+    if let Some(meta) = meta {
+        entry.is_dir = meta.is_dir();
+        entry.size = meta.len();
+        entry.executable = is_executable(&meta);
+        entry.mtime_ms = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+    }
+    entry.is_archive = !entry.is_dir && archive::has_archive_extension(&name);
+    entry.name = name;
+    entry
+}
+
+/// List one directory level. By default a symlink to a directory inside the
+/// project (a linked package, say) is followed and listed as a directory the
+/// tree can expand, while one pointing outside it isn't followed and is a
+/// leaf; only escaping links are left unfollowed, since following an in-root
+/// link can't reach anything the tree couldn't list anyway. `follow_links`
+/// forces either way.
 #[tauri::command]
-pub fn read_directory(path: String, project_root: String) -> Result<Vec<FileEntry>, String> {
+pub fn read_directory(
+    path: String,
+    project_root: String,
+    follow_links: Option<bool>,
+) -> Result<Vec<FileEntry>, String> {
     let canonical = canonicalize_path(&path)?;
     let canonical_root = canonicalize_path(&project_root)?;
     validate_within_root(&canonical, &canonical_root)?;
@@ -53,7 +194,6 @@ pub fn read_directory(path: String, project_root: String) -> Result<Vec<FileEntr
     if !root.is_dir() {
         return Err(format!("Not a directory: {}", path));
     }
-
     let mut entries: Vec<FileEntry> = WalkBuilder::new(root)
         .max_depth(Some(1))
         .hidden(false)
@@ -73,17 +213,7 @@ pub fn read_directory(path: String, project_root: String) -> Result<Vec<FileEntr
         .build()
         .filter_map(|result| result.ok())
         .filter(|entry| entry.path() != root) // skip the root itself
-        .map(|entry| {
-            let p = entry.path();
-            FileEntry {
-                name: p
-                    .file_name()
-                    .map(|n| n.to_string_lossy().to_string())
-                    .unwrap_or_default(),
-                path: p.to_string_lossy().to_string(),
-                is_dir: p.is_dir(),
-            }
-        })
+        .map(|entry| file_entry(entry.path(), &canonical_root, follow_links))
         .collect();
 
     // Sort: directories first, then alphabetical case-insensitive
@@ -183,6 +313,9 @@ pub fn path_exists(path: String) -> bool {
 
 const MAX_FILE_SIZE: u64 = 512 * 1024;
 
+/// Read a text file, transcoding legacy encodings (UTF-16, windows-1252) to
+/// UTF-8 and normalising CRLF to LF. Binary files and images are refused with
+/// the sniffed MIME type so the UI can fall back to a different preview.
 #[tauri::command]
 pub fn read_file(path: String, project_root: String) -> Result<String, String> {
     let file_path = canonicalize_path(&path)?;
@@ -205,28 +338,86 @@ pub fn read_file(path: String, project_root: String) -> Result<String, String> {
         ));
     }
 
-    std::fs::read_to_string(&file_path).map_err(|e| {
+    let bytes = std::fs::read(&file_path).map_err(|e| {
         error!("Failed to read file {}: {}", file_path.display(), e);
         format!("Failed to read file: {}", e)
-    })
+    })?;
+
+    match encoding::decode(&bytes) {
+        Ok(decoded) => Ok(decoded.text),
+        Err(kind) => Err(format!("File is not text: {}", kind)),
+    }
 }
 
+/// Write text back to an existing file in the encoding, BOM and line-ending
+/// style it currently has on disk, so editing a CRLF or UTF-16 file from the
+/// preview doesn't silently convert it.
 #[tauri::command]
 pub fn write_file(path: String, project_root: String, content: String) -> Result<(), String> {
-    let file_path = canonicalize_path(&path)?;
     let canonical_root = canonicalize_path(&project_root)?;
+    if let Some(link) = find_escaping_link(Path::new(&path), Path::new(&project_root), &canonical_root) {
+        return Err(format!(
+            "Refusing to write through symlink '{}' that points outside project root '{}'",
+            link.display(),
+            canonical_root.display()
+        ));
+    }
+    let file_path = canonicalize_path(&path)?;
     validate_within_root(&file_path, &canonical_root)?;
 
     if !file_path.is_file() {
         return Err(format!("Not a file: {}", path));
     }
 
-    std::fs::write(&file_path, content).map_err(|e| {
+    let existing = std::fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let format = match encoding::decode(&existing) {
+        Ok(decoded) => decoded.format,
+        Err(_) => return Err(format!("Refusing to overwrite binary file: {}", path)),
+    };
+    let bytes = encoding::encode(&content, &format)?;
+
+    std::fs::write(&file_path, bytes).map_err(|e| {
         error!("Failed to write file {}: {}", file_path.display(), e);
         format!("Failed to write file: {}", e)
     })
 }
 
+/// Classify a file as text (with encoding, BOM and line endings), image or
+/// binary by sniffing its leading bytes.
+#[tauri::command]
+pub fn sniff_file(path: String, project_root: String) -> Result<encoding::FileKind, String> {
+    let file_path = canonicalize_path(&path)?;
+    let canonical_root = canonicalize_path(&project_root)?;
+    validate_within_root(&file_path, &canonical_root)?;
+
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+
+    let metadata = file_path
+        .metadata()
+        .map_err(|e| format!("Cannot read metadata: {}", e))?;
+    let bytes = if metadata.len() > MAX_FILE_SIZE {
+        // Large files are only classified from their head; UTF-8 validity of a
+        // truncated multi-byte tail is tolerated by trimming to a char boundary.
+        use std::io::Read;
+        let mut head = Vec::with_capacity(MAX_FILE_SIZE as usize);
+        std::fs::File::open(&file_path)
+            .and_then(|f| f.take(MAX_FILE_SIZE).read_to_end(&mut head))
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if let Err(e) = std::str::from_utf8(&head) {
+            if e.error_len().is_none() {
+                head.truncate(e.valid_up_to());
+            }
+        }
+        head
+    } else {
+        std::fs::read(&file_path).map_err(|e| format!("Failed to read file: {}", e))?
+    };
+
+    Ok(encoding::sniff(&bytes))
+}
+
 const MAX_IMAGE_SIZE: u64 = 50 * 1024 * 1024; // 50 MB
 
 #[tauri::command]
@@ -318,7 +509,7 @@ pub fn open_in_default_app(path: String, project_root: String) -> Result<(), Str
 
 #[cfg(test)]
 mod tests {
-    use super::write_file;
+    use super::{read_directory, read_file, write_file};
     use std::fs;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -370,6 +561,110 @@ mod tests {
         let _ = fs::remove_dir_all(outside);
     }
 
+    #[test]
+    fn write_file_preserves_encoding_and_line_endings() {
+        let root = test_root("encoding");
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("legacy.txt");
+        fs::write(&file, b"caf\xe9\r\n").unwrap();
+        let path = file.to_string_lossy().to_string();
+        let root_str = root.to_string_lossy().to_string();
+
+        let content = read_file(path.clone(), root_str.clone()).unwrap();
+        assert_eq!(content, "caf\u{e9}\n");
+
+        write_file(path, root_str, "caf\u{e9} cr\u{e8}me\n".to_string()).unwrap();
+
+        assert_eq!(fs::read(&file).unwrap(), b"caf\xe9 cr\xe8me\r\n");
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[test]
+    fn read_file_reports_binary_mime() {
+        let root = test_root("binary");
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("module.wasm");
+        fs::write(&file, b"\x00asm\x01\x00\x00\x00").unwrap();
+
+        let err = read_file(
+            file.to_string_lossy().to_string(),
+            root.to_string_lossy().to_string(),
+        )
+        .unwrap_err();
+
+        assert!(err.contains("application/wasm"));
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_directory_follows_only_links_inside_root() {
+        let root = test_root("links");
+        let outside = test_root("links_outside");
+        fs::create_dir_all(root.join("src")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        std::os::unix::fs::symlink("src", root.join("inner")).unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+        std::os::unix::fs::symlink("missing.txt", root.join("dangling")).unwrap();
+
+        let entries = read_directory(
+            root.to_string_lossy().to_string(),
+            root.to_string_lossy().to_string(),
+            None,
+        )
+        .unwrap();
+        let find = |name: &str| entries.iter().find(|e| e.name == name).unwrap();
+
+        let inner = find("inner");
+        assert!(inner.is_symlink && inner.is_dir && !inner.broken && !inner.points_outside_root);
+        assert_eq!(inner.link_target.as_deref(), Some("src"));
+        let escape = find("escape");
+        assert!(escape.points_outside_root && !escape.is_dir);
+        let dangling = find("dangling");
+        assert!(dangling.broken && !dangling.points_outside_root && !dangling.is_dir);
+
+        let followed = read_directory(
+            root.to_string_lossy().to_string(),
+            root.to_string_lossy().to_string(),
+            Some(true),
+        )
+        .unwrap();
+        assert!(followed.iter().any(|e| e.name == "escape" && e.is_dir));
+        let described = read_directory(
+            root.to_string_lossy().to_string(),
+            root.to_string_lossy().to_string(),
+            Some(false),
+        )
+        .unwrap();
+        assert!(described.iter().any(|e| e.name == "inner" && !e.is_dir));
+
+        let _ = fs::remove_dir_all(root);
+        let _ = fs::remove_dir_all(outside);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_file_refuses_symlink_escaping_root() {
+        let root = test_root("write_link");
+        let outside = test_root("write_link_outside");
+        fs::create_dir_all(&root).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret.txt"), "before").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("escape")).unwrap();
+
+        let err = write_file(
+            root.join("escape/secret.txt").to_string_lossy().to_string(),
+            root.to_string_lossy().to_string(),
+            "after".to_string(),
+        )
+        .unwrap_err();
+
+        assert!(err.contains("symlink"), "{err}");
+        assert_eq!(fs::read_to_string(outside.join("secret.txt")).unwrap(), "before");
+        let _ = fs::remove_dir_all(root);
+        let _ = fs::remove_dir_all(outside);
+    }
+
     #[test]
     fn write_file_rejects_directories() {
         let root = test_root("directory");
@@ -1,10 +1,11 @@
+use crate::git::state::{is_git_state_path, GitStateHandle};
 use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tauri::{AppHandle, Emitter, Manager};
 
 pub struct FileWatcher {
     _watcher: RecommendedWatcher,
@@ -25,6 +26,23 @@ fn is_excluded(path: &std::path::Path) -> bool {
     })
 }
 
+/// Sort one notify event's paths into the parent dirs to report as
+/// `fs-change` and the paths to hand the git state service.
+fn collect_paths(event: &Event, changed_dirs: &mut HashSet<PathBuf>, git_paths: &mut HashSet<PathBuf>) {
+    for path in &event.paths {
+        if is_excluded(path) {
+            if is_git_state_path(path) {
+                git_paths.insert(path.clone());
+            }
+            continue;
+        }
+        git_paths.insert(path.clone());
+        if let Some(parent) = path.parent() {
+            changed_dirs.insert(parent.to_path_buf());
+        }
+    }
+}
+
 pub type WatcherState = Arc<Mutex<Option<FileWatcher>>>;
 
 impl FileWatcher {
@@ -67,16 +85,11 @@ impl FileWatcher {
                     break;
                 }
 
-                // Collect affected parent dirs
+                // Collect affected parent dirs, plus every path the git state
+                // service cares about (including index/HEAD/ref moves in .git)
                 let mut changed_dirs: HashSet<PathBuf> = HashSet::new();
-                for path in &event.paths {
-                    if is_excluded(path) {
-                        continue;
-                    }
-                    if let Some(parent) = path.parent() {
-                        changed_dirs.insert(parent.to_path_buf());
-                    }
-                }
+                let mut git_paths: HashSet<PathBuf> = HashSet::new();
+                collect_paths(&event, &mut changed_dirs, &mut git_paths);
 
                 // Drain additional events within the debounce window
                 let deadline = std::time::Instant::now() + Duration::from_millis(300);
@@ -86,20 +99,17 @@ impl FileWatcher {
                         break;
                     }
                     match event_rx.recv_timeout(remaining) {
-                        Ok(ev) => {
-                            for path in &ev.paths {
-                                if is_excluded(path) {
-                                    continue;
-                                }
-                                if let Some(parent) = path.parent() {
-                                    changed_dirs.insert(parent.to_path_buf());
-                                }
-                            }
-                        }
+                        Ok(ev) => collect_paths(&ev, &mut changed_dirs, &mut git_paths),
                         Err(_) => break,
                     }
                 }
 
+                if !git_paths.is_empty() {
+                    if let Some(git_state) = app_handle.try_state::<GitStateHandle>() {
+                        git_state.paths_changed(&git_paths.into_iter().collect::<Vec<_>>());
+                    }
+                }
+
                 // Emit to frontend (skip if everything in the batch was excluded)
                 if changed_dirs.is_empty() {
                     continue;
@@ -135,15 +145,28 @@ pub fn start_watching(
 
     // Stop existing watcher by dropping it
     *guard = None;
+    let git_state = app_handle.try_state::<GitStateHandle>().map(|s| s.inner().clone());
+    if let Some(git_state) = &git_state {
+        git_state.set_watch_root(None);
+    }
 
     let watcher = FileWatcher::start(&path, app_handle)?;
     *guard = Some(watcher);
+    if let Some(git_state) = git_state {
+        git_state.set_watch_root(Some(canonical));
+    }
     Ok(())
 }
 
 #[tauri::command]
-pub fn stop_watching(state: tauri::State<'_, WatcherState>) -> Result<(), String> {
+pub fn stop_watching(
+    app_handle: AppHandle,
+    state: tauri::State<'_, WatcherState>,
+) -> Result<(), String> {
     let mut guard = state.lock().map_err(|e| format!("Lock error: {}", e))?;
     *guard = None;
+    if let Some(git_state) = app_handle.try_state::<GitStateHandle>() {
+        git_state.set_watch_root(None);
+    }
     Ok(())
 }
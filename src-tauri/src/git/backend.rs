@@ -0,0 +1,340 @@
+//! Read-only git queries behind one trait, so the Tauri commands don't care
+//! whether they're answered in-process (libgit2) or by the `git` CLI.
+//!
+//! The default backend tries libgit2 first and falls back to the CLI when it
+//! errors (repo extensions libgit2 doesn't support, a prunable worktree, ...).
+//! `CODEZILLA_GIT_BACKEND=cli` forces the CLI for everything.
+
+use super::cli::CliBackend;
+use super::libgit::LibgitBackend;
+use super::libgit;
+use super::types::{CommitInfo, CommitSummary, FileDiffStat, GitStatus, LogQuery, WorktreeInfo};
+use log::warn;
+use std::path::Path;
+use std::sync::OnceLock;
+
+pub(crate) trait GitBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Porcelain v2 status of the worktree containing `repo`, with paths
+    /// relative to the worktree root. Ok(None) when it isn't inside one.
+    fn status(&self, repo: &Path) -> Result<Option<GitStatus>, String>;
+
+    /// Per-file line counts for tracked changes, HEAD → worktree, with paths
+    /// relative to the worktree root. Errors when there's no HEAD yet.
+    fn numstat(&self, repo: &Path, path: Option<&str>) -> Result<Vec<FileDiffStat>, String>;
+
+    /// Unified diff HEAD → worktree for one file; an untracked file is shown
+    /// as entirely added.
+    fn diff(&self, repo: &Path, path: &str) -> Result<String, String>;
+
+    /// The main worktree first, then linked worktrees by path. Empty when
+    /// `repo` isn't in a git repo.
+    fn worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>, String>;
+
+    /// Metadata and per-file stats of one commit.
+    fn commit_info(&self, repo: &Path, rev: &str) -> Result<CommitInfo, String>;
+
+    /// One commit's patch against its first parent.
+    fn show(&self, repo: &Path, rev: &str) -> Result<String, String>;
+
+    /// Commits in `--date-order` (children before parents, otherwise newest
+    /// first), after `query.skip`, at most `max` of them. Empty before the
+    /// first commit.
+    fn log(&self, repo: &Path, query: &LogQuery, max: usize) -> Result<Vec<CommitSummary>, String>;
+}
+
+/// libgit2 with the CLI as fallback.
+struct InProcessFirst {
+    primary: LibgitBackend,
+    fallback: CliBackend,
+}
+
+impl InProcessFirst {
+    fn or_cli<T>(
+        &self,
+        what: &str,
+        primary: Result<T, String>,
+        fallback: impl FnOnce(&CliBackend) -> Result<T, String>,
+    ) -> Result<T, String> {
+        primary.or_else(|e| {
+            warn!("libgit2 {} failed ({}), retrying with git CLI", what, e);
+            fallback(&self.fallback)
+        })
+    }
+}
+
+impl GitBackend for InProcessFirst {
+    fn name(&self) -> &'static str {
+        self.primary.name()
+    }
+
+    fn status(&self, repo: &Path) -> Result<Option<GitStatus>, String> {
+        self.or_cli("status", self.primary.status(repo), |cli| cli.status(repo))
+    }
+
+    fn numstat(&self, repo: &Path, path: Option<&str>) -> Result<Vec<FileDiffStat>, String> {
+        self.or_cli("numstat", self.primary.numstat(repo, path), |cli| {
+            cli.numstat(repo, path)
+        })
+    }
+
+    fn diff(&self, repo: &Path, path: &str) -> Result<String, String> {
+        self.or_cli("diff", self.primary.diff(repo, path), |cli| cli.diff(repo, path))
+    }
+
+    fn worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>, String> {
+        self.or_cli("worktree list", self.primary.worktrees(repo), |cli| {
+            cli.worktrees(repo)
+        })
+    }
+
+    fn commit_info(&self, repo: &Path, rev: &str) -> Result<CommitInfo, String> {
+        self.or_cli("log", self.primary.commit_info(repo, rev), |cli| {
+            cli.commit_info(repo, rev)
+        })
+    }
+
+    fn show(&self, repo: &Path, rev: &str) -> Result<String, String> {
+        self.or_cli("show", self.primary.show(repo, rev), |cli| cli.show(repo, rev))
+    }
+
+    fn log(&self, repo: &Path, query: &LogQuery, max: usize) -> Result<Vec<CommitSummary>, String> {
+        // Path-limited history and symmetric ranges need git's own history
+        // simplification; don't bother trying in-process.
+        if !libgit::can_walk(query) {
+            return self.fallback.log(repo, query, max);
+        }
+        self.or_cli("log", self.primary.log(repo, query, max), |cli| {
+            cli.log(repo, query, max)
+        })
+    }
+}
+
+/// The process-wide backend, chosen once from `CODEZILLA_GIT_BACKEND`.
+pub(crate) fn backend() -> &'static dyn GitBackend {
+    static BACKEND: OnceLock<Box<dyn GitBackend>> = OnceLock::new();
+    BACKEND
+        .get_or_init(|| match std::env::var("CODEZILLA_GIT_BACKEND").as_deref() {
+            Ok("cli") => Box::new(CliBackend),
+            _ => Box::new(InProcessFirst {
+                primary: LibgitBackend,
+                fallback: CliBackend,
+            }),
+        })
+        .as_ref()
+}
+
+/// Parity: both backends must answer the same on the same fixture repo.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+    use std::path::{Path, PathBuf};
+
+    /// A repo with one of most kinds of change: staged + unstaged edits, a
+    /// staged rename, a deletion, an untracked file with an awkward name, a
+    /// subdirectory, and a linked worktree on another branch.
+    fn fixture() -> (PathBuf, PathBuf) {
+        let repo = test_repo("parity");
+        fs::create_dir_all(repo.join("src")).unwrap();
+        commit_file(&repo, "src/lib.rs", "fn a() {}\nfn b() {}\n", "init");
+        commit_file(&repo, "README.md", "# readme\n", "Add readme\n\nWith a body\nover two lines.");
+        commit_file(&repo, "old name.txt", "rename me\n", "Add file to rename");
+        commit_file(&repo, "gone.txt", "bye\n", "Add file to delete");
+
+        fs::write(repo.join("src/lib.rs"), "fn a() {}\nfn b2() {}\nfn c() {}\n").unwrap();
+        git(&repo, &["add", "src/lib.rs"]);
+        fs::write(repo.join("src/lib.rs"), "fn a() {}\nfn b2() {}\nfn c() {}\nfn d() {}\n").unwrap();
+        git(&repo, &["mv", "old name.txt", "new \"name\".txt"]);
+        fs::remove_file(repo.join("gone.txt")).unwrap();
+        fs::write(repo.join("un tracked.md"), "one\ntwo\n").unwrap();
+        fs::create_dir_all(repo.join("src/nested")).unwrap();
+        fs::write(repo.join("src/nested/new.rs"), "// new\n").unwrap();
+
+        let worktree = repo.with_file_name(format!(
+            "{}-wt",
+            repo.file_name().unwrap().to_string_lossy()
+        ));
+        git(&repo, &["worktree", "add", "-q", "-b", "feature", worktree.to_str().unwrap()]);
+        (repo, worktree)
+    }
+
+    fn sorted<T: Clone, K: Ord>(items: &[T], key: impl Fn(&T) -> K) -> Vec<T> {
+        let mut items = items.to_vec();
+        items.sort_by_key(key);
+        items
+    }
+
+    /// Only the +/- lines: index abbreviations and hunk function context
+    /// legitimately differ between implementations.
+    fn changed_lines(patch: &str) -> Vec<&str> {
+        patch
+            .lines()
+            .filter(|l| {
+                (l.starts_with('+') || l.starts_with('-'))
+                    && !l.starts_with("+++")
+                    && !l.starts_with("---")
+            })
+            .collect()
+    }
+
+    /// Commit with a fixed timestamp so date ordering is deterministic.
+    fn commit_at(repo: &Path, file: &str, content: &str, message: &str, time: u32) {
+        fs::write(repo.join(file), content).unwrap();
+        git(repo, &["add", "--", file]);
+        let date = format!("{} +0000", 1_700_000_000 + time * 60);
+        let output = std::process::Command::new("git")
+            .args(["commit", "-q", "-m", message])
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .current_dir(repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn backends_agree_on_log() {
+        let repo = test_repo("parity_log");
+        commit_at(&repo, "a.txt", "1\n", "Initial commit", 0);
+        git(&repo, &["checkout", "-q", "-b", "feature"]);
+        commit_at(&repo, "b.txt", "1\n", "Feature: add b", 1);
+        commit_at(&repo, "b.txt", "2\n", "Feature: tweak b", 3);
+        git(&repo, &["checkout", "-q", "main"]);
+        commit_at(&repo, "a.txt", "2\n", "Main: bump a", 2);
+        git(&repo, &["config", "user.name", "Other Person"]);
+        git(&repo, &["merge", "-q", "--no-ff", "-m", "Merge feature", "feature"]);
+        commit_at(&repo, "a.txt", "3\n", "Main: after merge", 4);
+
+        let cli = CliBackend;
+        let lib = LibgitBackend;
+        let queries = [
+            LogQuery::default(),
+            LogQuery {
+                skip: 1,
+                ..Default::default()
+            },
+            LogQuery {
+                range: Some("main~2..feature".into()),
+                ..Default::default()
+            },
+            LogQuery {
+                range: Some("feature".into()),
+                ..Default::default()
+            },
+            LogQuery {
+                author: Some("OTHER".into()),
+                ..Default::default()
+            },
+            LogQuery {
+                grep: Some("feature:".into()),
+                skip: 1,
+                ..Default::default()
+            },
+        ];
+        for query in &queries {
+            let a = cli.log(&repo, query, 3).unwrap();
+            let b = lib.log(&repo, query, 3).unwrap();
+            assert!(!a.is_empty(), "{:?}", query);
+            assert_eq!(a, b, "{:?}", query);
+        }
+
+        let all = lib.log(&repo, &LogQuery::default(), 100).unwrap();
+        let merge = all.iter().find(|c| c.subject == "Merge feature").unwrap();
+        assert_eq!(merge.parents.len(), 2);
+
+        let by_path = backend()
+            .log(
+                &repo,
+                &LogQuery {
+                    path: Some("b.txt".into()),
+                    ..Default::default()
+                },
+                100,
+            )
+            .unwrap();
+        assert_eq!(by_path.len(), 2);
+
+        let empty = test_repo("parity_log_empty");
+        assert!(cli.log(&empty, &LogQuery::default(), 10).unwrap().is_empty());
+        assert!(lib.log(&empty, &LogQuery::default(), 10).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&empty);
+        let _ = fs::remove_dir_all(&repo);
+    }
+
+    #[test]
+    fn backends_agree_on_fixture_repo() {
+        let (repo, worktree) = fixture();
+        let cli = CliBackend;
+        let lib = LibgitBackend;
+
+        for dir in [repo.clone(), repo.join("src")] {
+            let a = cli.status(&dir).unwrap().unwrap();
+            let b = lib.status(&dir).unwrap().unwrap();
+            assert_eq!(a.branch, b.branch, "branch in {:?}", dir);
+            let key = |e: &crate::git::types::GitStatusEntry| e.path.clone();
+            let (a, b) = (sorted(&a.entries, key), sorted(&b.entries, key));
+            assert_eq!(
+                format!("{:?}", a),
+                format!("{:?}", b),
+                "status entries in {:?}",
+                dir
+            );
+        }
+
+        let key = |s: &FileDiffStat| s.path.clone();
+        assert_eq!(
+            format!("{:?}", sorted(&cli.numstat(&repo, None).unwrap(), key)),
+            format!("{:?}", sorted(&lib.numstat(&repo, None).unwrap(), key)),
+        );
+        assert_eq!(
+            format!("{:?}", cli.numstat(&repo.join("src"), Some("lib.rs")).unwrap()),
+            format!("{:?}", lib.numstat(&repo.join("src"), Some("lib.rs")).unwrap()),
+        );
+
+        for file in ["src/lib.rs", "gone.txt", "un tracked.md"] {
+            let a = cli.diff(&repo, file).unwrap();
+            let b = lib.diff(&repo, file).unwrap();
+            assert!(!a.is_empty(), "{}", file);
+            assert_eq!(changed_lines(&a), changed_lines(&b), "diff of {}", file);
+        }
+
+        assert_eq!(
+            format!("{:?}", cli.worktrees(&repo).unwrap()),
+            format!("{:?}", lib.worktrees(&repo).unwrap())
+        );
+        assert_eq!(
+            format!("{:?}", cli.worktrees(&worktree).unwrap()),
+            format!("{:?}", lib.worktrees(&worktree).unwrap())
+        );
+
+        for rev in ["HEAD", "HEAD~2", "HEAD~3"] {
+            let a = cli.commit_info(&repo, rev).unwrap();
+            let b = lib.commit_info(&repo, rev).unwrap();
+            assert_eq!(format!("{:?}", a), format!("{:?}", b), "commit {}", rev);
+            assert_eq!(
+                changed_lines(&cli.show(&repo, rev).unwrap()),
+                changed_lines(&lib.show(&repo, rev).unwrap()),
+                "show {}",
+                rev
+            );
+        }
+        assert!(lib.commit_info(&repo, "nope").is_err());
+
+        let plain = repo.with_file_name(format!(
+            "{}-plain",
+            repo.file_name().unwrap().to_string_lossy()
+        ));
+        fs::create_dir_all(&plain).unwrap();
+        assert!(cli.status(&plain).unwrap().is_none());
+        assert!(lib.status(&plain).unwrap().is_none());
+        assert!(lib.worktrees(&plain).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&plain);
+        let _ = fs::remove_dir_all(&worktree);
+        let _ = fs::remove_dir_all(&repo);
+    }
+}
@@ -0,0 +1,405 @@
+//! Who last touched each line of a file, and how a range of lines got to be
+//! the way it is.
+//!
+//! Blame reads `git blame --incremental` as git produces it, batching ranges
+//! over a `Channel` so a large file fills in progressively rather than after
+//! the whole history walk.
+
+use super::diff::parse_diff;
+use super::types::{BlameEvent, BlameRange, CommitFileStat, CommitInfo};
+use super::{
+    git_failure, query_backend, repo_dir, run_git, validate_rev, wait_until_deadline,
+    BACKEND_TIMEOUT, LOG_DEFAULT_LIMIT, LOG_MAX_LIMIT,
+};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use std::time::Duration;
+use tauri::ipc::Channel;
+
+/// Ranges sent per [`BlameEvent::Ranges`] batch.
+const BLAME_BATCH: usize = 200;
+
+/// Blaming a file with a long history walks all of it; ranges stream in
+/// meanwhile, so a slow blame is still useful.
+const BLAME_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// The all-zero id `git blame` gives lines that aren't committed yet.
+const UNCOMMITTED: &str = "0000000000000000000000000000000000000000";
+
+/// `author-time` plus `author-tz` (`+0130`) as ISO 8601 in that zone, the
+/// shape `%aI` gives everywhere else.
+fn iso_date(unix: i64, tz: &str) -> String {
+    let sign = if tz.starts_with('-') { -1 } else { 1 };
+    let digits = tz.trim_start_matches(['+', '-']);
+    let hours = digits
+        .get(..2)
+        .and_then(|h| h.parse::<i64>().ok())
+        .unwrap_or(0);
+    let minutes = digits
+        .get(2..4)
+        .and_then(|m| m.parse::<i64>().ok())
+        .unwrap_or(0);
+    let local = unix + sign * (hours * 3600 + minutes * 60);
+
+    // Days since 1970-01-01 to a civil date (Howard Hinnant's algorithm).
+    let days = local.div_euclid(86_400);
+    let secs = local.rem_euclid(86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        if sign < 0 { '-' } else { '+' },
+        hours,
+        minutes
+    )
+}
+
+/// Commit details `--incremental` prints only the first time it sees a commit.
+#[derive(Default, Clone)]
+struct CommitMeta {
+    author: String,
+    author_email: String,
+    time: i64,
+    tz: String,
+    summary: String,
+}
+
+/// Line-at-a-time parser for `git blame --incremental`: a
+/// `<sha> <orig> <final> <count>` header, then `key value` lines ending with
+/// `filename`.
+#[derive(Default)]
+struct IncrementalParser {
+    commits: HashMap<String, CommitMeta>,
+    /// (sha, orig_start, final_start, count) of the group being read.
+    group: Option<(String, u32, u32, u32)>,
+}
+
+impl IncrementalParser {
+    fn feed(&mut self, line: &str) -> Option<BlameRange> {
+        let Some((sha, orig, start, count)) = self.group.clone() else {
+            let mut fields = line.split(' ');
+            let sha = fields.next()?.to_string();
+            let mut number = || fields.next()?.parse::<u32>().ok();
+            self.group = Some((sha, number()?, number()?, number()?));
+            return None;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        let meta = self.commits.entry(sha.clone()).or_default();
+        match key {
+            "author" => meta.author = value.to_string(),
+            "author-mail" => {
+                meta.author_email = value
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            }
+            "author-time" => meta.time = value.parse().unwrap_or(0),
+            "author-tz" => meta.tz = value.to_string(),
+            "summary" => meta.summary = value.to_string(),
+            "filename" => {
+                self.group = None;
+                let meta = meta.clone();
+                return Some(BlameRange {
+                    start_line: start,
+                    line_count: count,
+                    orig_start_line: orig,
+                    orig_path: value.to_string(),
+                    uncommitted: sha == UNCOMMITTED,
+                    commit: sha,
+                    author: meta.author,
+                    author_email: meta.author_email,
+                    date: iso_date(meta.time, &meta.tz),
+                    summary: meta.summary,
+                });
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Read `git blame --incremental` output, emitting ranges in batches as
+/// they complete. Returns every range and how many were emitted.
+fn read_ranges(
+    stdout: impl Read,
+    emit: &(dyn Fn(BlameEvent) + Send + Sync),
+) -> Result<(Vec<BlameRange>, usize), String> {
+    let mut parser = IncrementalParser::default();
+    let mut ranges = Vec::new();
+    let mut sent = 0;
+    for line in BufReader::new(stdout).lines() {
+        let line = line.map_err(|e| format!("Failed to read git blame: {}", e))?;
+        if let Some(range) = parser.feed(&line) {
+            ranges.push(range);
+            if ranges.len() - sent >= BLAME_BATCH {
+                emit(BlameEvent::Ranges(ranges[sent..].to_vec()));
+                sent = ranges.len();
+            }
+        }
+    }
+    Ok((ranges, sent))
+}
+
+/// Blame `file_path` (relative to `repo`) as of `rev`, or the working tree
+/// when None, emitting ranges in batches as git finds them. Returns every
+/// range, ordered by line. Run inside [`query_backend`], git is killed when
+/// the query's deadline passes.
+fn blame(
+    repo: &Path,
+    file_path: &str,
+    rev: Option<&str>,
+    emit: &(dyn Fn(BlameEvent) + Send + Sync),
+) -> Result<Vec<BlameRange>, String> {
+    let mut args = vec!["blame", "--incremental"];
+    args.extend(rev);
+    args.extend(["--", file_path]);
+    let mut command = Command::new("git");
+    command
+        .args(&args)
+        .current_dir(repo)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    std::thread::scope(|scope| {
+        // stderr gets its own thread so a noisy git can't fill the pipe and
+        // stall while stdout is still being read.
+        let errors = scope.spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_end(&mut buf);
+            }
+            buf
+        });
+        let reader = scope.spawn(move || match stdout {
+            Some(stdout) => read_ranges(stdout, emit),
+            None => Ok((Vec::new(), 0)),
+        });
+        let status = wait_until_deadline(&mut child);
+        let read = reader
+            .join()
+            .unwrap_or_else(|_| Err("Failed to read git blame".to_string()));
+        let stderr = errors.join().unwrap_or_default();
+
+        let status = status?;
+        if !status.success() {
+            let output = Output {
+                status,
+                stdout: Vec::new(),
+                stderr,
+            };
+            return Err(git_failure("blame the file", &output));
+        }
+        let (mut ranges, sent) = read?;
+        if sent < ranges.len() {
+            emit(BlameEvent::Ranges(ranges[sent..].to_vec()));
+        }
+        ranges.sort_by_key(|r| r.start_line);
+        Ok(ranges)
+    })
+}
+
+/// Blame a file line by line: uncommitted lines come back with
+/// `uncommitted` set. Pass `rev` to blame an older version.
+#[tauri::command]
+pub async fn get_git_blame(
+    repo_path: String,
+    file_path: String,
+    rev: Option<String>,
+    channel: Channel<BlameEvent>,
+) -> Result<Vec<BlameRange>, String> {
+    if let Some(rev) = &rev {
+        validate_rev(rev)?;
+    }
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BLAME_TIMEOUT, move |_, repo| {
+        blame(repo, &file_path, rev.as_deref(), &|event| {
+            let _ = channel.send(event);
+        })
+    })
+    .await
+}
+
+/// Parse `git log -L` output formatted as
+/// `\x1e%H\x1f%an\x1f%aI\x1f%s\x1f%b\x1f` followed by each commit's patch for
+/// the traced lines.
+fn parse_line_history(stdout: &str) -> Vec<CommitInfo> {
+    stdout
+        .split('\x1e')
+        .filter_map(|record| {
+            let fields: Vec<&str> = record.splitn(6, '\x1f').collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let file_stats = parse_diff(fields[5])
+                .into_iter()
+                .map(|file| CommitFileStat {
+                    file: file.new_path.or(file.old_path).unwrap_or_default(),
+                    additions: file.added,
+                    deletions: file.removed,
+                })
+                .collect();
+            Some(CommitInfo::new(
+                fields[0].to_string(),
+                fields[1].to_string(),
+                fields[2].to_string(),
+                fields[3].to_string(),
+                fields[4].trim_end().to_string(),
+                file_stats,
+            ))
+        })
+        .collect()
+}
+
+/// The commits that changed lines `start_line..=end_line` of `file_path`,
+/// newest first (`git log -L`). Each entry's file stats count only the
+/// changes to the traced lines.
+#[tauri::command]
+pub async fn get_line_history(
+    repo_path: String,
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+    rev: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<CommitInfo>, String> {
+    if start_line == 0 || end_line < start_line {
+        return Err(format!("Invalid line range: {}-{}", start_line, end_line));
+    }
+    if let Some(rev) = &rev {
+        validate_rev(rev)?;
+    }
+    let limit = limit.unwrap_or(LOG_DEFAULT_LIMIT).clamp(1, LOG_MAX_LIMIT);
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let args = [
+            "-c".to_string(),
+            "core.quotePath=false".to_string(),
+            "log".to_string(),
+            format!("-L{},{}:{}", start_line, end_line, file_path),
+            format!("--max-count={}", limit),
+            "--format=%x1e%H%x1f%an%x1f%aI%x1f%s%x1f%b%x1f".to_string(),
+            "--no-color".to_string(),
+            "--no-ext-diff".to_string(),
+            "--src-prefix=a/".to_string(),
+            "--dst-prefix=b/".to_string(),
+            "--end-of-options".to_string(),
+            rev.unwrap_or_else(|| "HEAD".to_string()),
+        ];
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("trace the lines", &output));
+        }
+        Ok(parse_line_history(&String::from_utf8_lossy(&output.stdout)))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+    use std::sync::Mutex;
+
+    #[test]
+    fn formats_author_time_in_its_zone() {
+        assert_eq!(iso_date(0, "+0000"), "1970-01-01T00:00:00+00:00");
+        assert_eq!(
+            iso_date(1_700_000_000, "+0530"),
+            "2023-11-15T03:43:20+05:30"
+        );
+        assert_eq!(
+            iso_date(1_700_000_000, "-0800"),
+            "2023-11-14T14:13:20-08:00"
+        );
+    }
+
+    #[tokio::test]
+    async fn blames_lines_and_traces_their_history() {
+        let repo = test_repo("blame");
+        commit_file(&repo, "f.txt", "a\nb\nc\n", "one");
+        git(&repo, &["config", "user.name", "Other"]);
+        commit_file(&repo, "f.txt", "a\nB\nc\n", "two");
+        fs::write(repo.join("f.txt"), "a\nB\nc\nd\n").unwrap();
+
+        let batches = Mutex::new(0);
+        let ranges = blame(&repo, "f.txt", None, &|BlameEvent::Ranges(r)| {
+            assert!(!r.is_empty());
+            *batches.lock().unwrap() += 1;
+        })
+        .unwrap();
+        assert_eq!(*batches.lock().unwrap(), 1);
+        let line = |n: u32| {
+            ranges
+                .iter()
+                .find(|r| r.start_line <= n && n < r.start_line + r.line_count)
+                .unwrap()
+        };
+        assert_eq!(
+            (line(1).author.as_str(), line(1).summary.as_str()),
+            ("Test", "one")
+        );
+        assert_eq!(line(1).author_email, "test@example.com");
+        assert_eq!(
+            (line(2).author.as_str(), line(2).summary.as_str()),
+            ("Other", "two")
+        );
+        assert!(line(4).uncommitted);
+        assert!(!line(3).uncommitted);
+
+        let history = get_line_history(
+            repo.to_string_lossy().to_string(),
+            "f.txt".to_string(),
+            2,
+            2,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        let subjects: Vec<_> = history.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["two", "one"]);
+        assert_eq!(history[0].file_stats[0].file, "f.txt");
+        assert_eq!((history[0].additions, history[0].deletions), (1, 1));
+
+        let err = get_line_history(
+            repo.to_string_lossy().to_string(),
+            "f.txt".into(),
+            3,
+            2,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Invalid line range"), "{}", err);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,287 @@
+//! Local and remote branch management.
+
+use super::backend::GitBackend;
+use super::types::{BranchInfo, FileState};
+use super::{git_failure, query_backend, repo_dir, run_git, validate_rev, BACKEND_TIMEOUT};
+use std::path::Path;
+
+/// Reject names git wouldn't accept for a new branch (and anything that
+/// could be read as an option).
+pub(crate) fn validate_branch_name(repo: &Path, name: &str) -> Result<(), String> {
+    if name.is_empty() || name.starts_with('-') {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    let output = run_git(repo, ["check-ref-format", "--branch", name])?;
+    if !output.status.success() {
+        return Err(format!("Invalid branch name: {}", name));
+    }
+    Ok(())
+}
+
+/// Parse `%(upstream:track,nobracket)`: "ahead 1, behind 2", "gone" or "".
+fn parse_track(track: &str) -> (u32, u32, bool) {
+    let mut ahead = 0;
+    let mut behind = 0;
+    for part in track.split(", ") {
+        if let Some(n) = part.strip_prefix("ahead ") {
+            ahead = n.parse().unwrap_or(0);
+        } else if let Some(n) = part.strip_prefix("behind ") {
+            behind = n.parse().unwrap_or(0);
+        }
+    }
+    (ahead, behind, track == "gone")
+}
+
+const BRANCH_FORMAT: &str = "%(refname)%00%(refname:short)%00%(HEAD)%00%(objectname)\
+%00%(upstream:short)%00%(upstream:track,nobracket)%00%(committerdate:iso-strict)%00%(subject)";
+
+fn parse_branches(stdout: &str) -> Vec<BranchInfo> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let f: Vec<&str> = line.splitn(8, '\0').collect();
+            if f.len() < 8 || f[0].ends_with("/HEAD") {
+                // `refs/remotes/origin/HEAD` is a pointer, not a branch.
+                return None;
+            }
+            let (ahead, behind, upstream_gone) = parse_track(f[5]);
+            Some(BranchInfo {
+                name: f[1].to_string(),
+                is_remote: f[0].starts_with("refs/remotes/"),
+                is_current: f[2] == "*",
+                head: f[3].to_string(),
+                upstream: Some(f[4]).filter(|u| !u.is_empty()).map(str::to_string),
+                upstream_gone,
+                ahead,
+                behind,
+                last_commit_date: f[6].to_string(),
+                subject: f[7].to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Local branches (current first, then by name) followed by remote-tracking
+/// branches, with upstream tracking counts for local ones.
+#[tauri::command]
+pub async fn list_branches(repo_path: String) -> Result<Vec<BranchInfo>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |_, repo| {
+        let output = run_git(
+            repo,
+            [
+                "for-each-ref",
+                &format!("--format={}", BRANCH_FORMAT),
+                "refs/heads",
+                "refs/remotes",
+            ],
+        )?;
+        if !output.status.success() {
+            return Err(git_failure("list branches", &output));
+        }
+        let mut branches = parse_branches(&String::from_utf8_lossy(&output.stdout));
+        branches.sort_by_key(|b| (b.is_remote, !b.is_current, b.name.clone()));
+        Ok(branches)
+    })
+    .await
+}
+
+/// Create `name` at `start_point` (HEAD by default), optionally switching to it.
+#[tauri::command]
+pub async fn create_branch(
+    repo_path: String,
+    name: String,
+    start_point: Option<String>,
+    checkout: Option<bool>,
+) -> Result<(), String> {
+    if let Some(start) = &start_point {
+        validate_rev(start)?;
+    }
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        validate_branch_name(repo, &name)?;
+        let mut args = if checkout.unwrap_or(false) {
+            vec!["switch", "--create", &name]
+        } else {
+            vec!["branch", &name]
+        };
+        args.extend(start_point.as_deref());
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("create branch", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Tracked files with uncommitted changes (untracked files don't block a
+/// switch unless git itself would overwrite them).
+pub(crate) fn dirty_tracked_paths(
+    git: &dyn GitBackend,
+    repo: &Path,
+) -> Result<Vec<String>, String> {
+    let status = git.status(repo)?.unwrap_or_default();
+    Ok(status
+        .entries
+        .into_iter()
+        .filter(|e| e.worktree != FileState::Untracked && e.worktree != FileState::Ignored)
+        .map(|e| e.path)
+        .collect())
+}
+
+/// Describe a dirty tree for an error message, naming a few files.
+pub(crate) fn dirty_error(action: &str, dirty: &[String]) -> String {
+    let shown: Vec<&str> = dirty.iter().take(5).map(String::as_str).collect();
+    let more = dirty.len().saturating_sub(shown.len());
+    format!(
+        "Cannot {}: {} file(s) have uncommitted changes ({}{})",
+        action,
+        dirty.len(),
+        shown.join(", "),
+        if more > 0 {
+            format!(", and {} more", more)
+        } else {
+            String::new()
+        }
+    )
+}
+
+/// Switch to branch `name`. A remote branch name (`origin/x`) checks out a
+/// local tracking branch `x`. Refuses while tracked files have uncommitted
+/// changes unless `allow_dirty`, in which case git carries them over (or
+/// refuses itself if they'd be overwritten).
+#[tauri::command]
+pub async fn switch_branch(
+    repo_path: String,
+    name: String,
+    allow_dirty: Option<bool>,
+) -> Result<(), String> {
+    validate_rev(&name)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        if !allow_dirty.unwrap_or(false) {
+            let dirty = dirty_tracked_paths(git, repo)?;
+            if !dirty.is_empty() {
+                return Err(dirty_error(&format!("switch to {}", name), &dirty));
+            }
+        }
+        let output = run_git(repo, ["switch", &name])?;
+        if output.status.success() {
+            return Ok(());
+        }
+        // `git switch origin/x` wants --detach; track it as a local branch
+        // instead.
+        if let Some((_, local)) = name.split_once('/') {
+            let remote_ref = format!("refs/remotes/{}", name);
+            let is_remote = run_git(repo, ["show-ref", "--verify", "--quiet", &remote_ref])?
+                .status
+                .success();
+            if is_remote {
+                let output = run_git(repo, ["switch", "--create", local, "--track", &name])?;
+                if !output.status.success() {
+                    return Err(git_failure("switch branch", &output));
+                }
+                return Ok(());
+            }
+        }
+        Err(git_failure("switch branch", &output))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_branch(
+    repo_path: String,
+    old_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    validate_rev(&old_name)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        validate_branch_name(repo, &new_name)?;
+        let output = run_git(repo, ["branch", "--move", &old_name, &new_name])?;
+        if !output.status.success() {
+            return Err(git_failure("rename branch", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+pub(crate) fn delete(repo: &Path, name: &str, force: bool) -> Result<(), String> {
+    validate_rev(name)?;
+    let flag = if force { "-D" } else { "-d" };
+    let output = run_git(repo, ["branch", flag, name])?;
+    if !output.status.success() {
+        return Err(git_failure("delete branch", &output));
+    }
+    Ok(())
+}
+
+/// Delete a local branch. Without `force`, git refuses if it isn't merged.
+#[tauri::command]
+pub async fn delete_branch(
+    repo_path: String,
+    name: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        delete(repo, &name, force.unwrap_or(false))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    #[tokio::test]
+    async fn branch_lifecycle() {
+        let repo = test_repo("branches");
+        let root = repo.to_string_lossy().to_string();
+        commit_file(&repo, "a.txt", "1\n", "init");
+
+        create_branch(root.clone(), "agent/feature".into(), None, Some(true))
+            .await
+            .unwrap();
+        commit_file(&repo, "a.txt", "2\n", "feature work");
+        git(&repo, &["branch", "--set-upstream-to=main"]);
+
+        let branches = list_branches(root.clone()).await.unwrap();
+        let feature = &branches[0];
+        assert_eq!(feature.name, "agent/feature");
+        assert!(feature.is_current);
+        assert_eq!(feature.upstream.as_deref(), Some("main"));
+        assert_eq!((feature.ahead, feature.behind), (1, 0));
+        assert_eq!(feature.subject, "feature work");
+
+        fs::write(repo.join("a.txt"), "dirty\n").unwrap();
+        let err = switch_branch(root.clone(), "main".into(), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("a.txt"), "{err}");
+        git(&repo, &["checkout", "--", "a.txt"]);
+        switch_branch(root.clone(), "main".into(), None)
+            .await
+            .unwrap();
+
+        rename_branch(root.clone(), "agent/feature".into(), "agent/renamed".into())
+            .await
+            .unwrap();
+        assert!(delete_branch(root.clone(), "agent/renamed".into(), None)
+            .await
+            .is_err());
+        delete_branch(root.clone(), "agent/renamed".into(), Some(true))
+            .await
+            .unwrap();
+        assert!(create_branch(root.clone(), "bad..name".into(), None, None)
+            .await
+            .is_err());
+        assert_eq!(list_branches(root).await.unwrap().len(), 1);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
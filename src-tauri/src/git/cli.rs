@@ -0,0 +1,387 @@
+//! [`GitBackend`] that shells out to the `git` CLI and parses its porcelain
+//! output. It is the fallback when the in-process backend can't handle a repo,
+//! and the reference the parity tests hold the in-process backend to.
+
+use super::backend::GitBackend;
+use super::types::{
+    CommitFileStat, CommitInfo, CommitSummary, FileDiffStat, GitStatus, LogQuery, SubmoduleState,
+    WorktreeInfo,
+};
+use super::{classify_worktree, git_failure, has_head, run_git, status_entry};
+use std::path::Path;
+
+pub(crate) struct CliBackend;
+
+/// `N...` for a plain file, `S<c><m><u>` for a submodule.
+fn parse_submodule_state(field: &str) -> Option<SubmoduleState> {
+    let flags = field.strip_prefix('S')?.as_bytes();
+    if flags.len() < 3 {
+        return None;
+    }
+    Some(SubmoduleState {
+        commit_changed: flags[0] == b'C',
+        modified: flags[1] == b'M',
+        untracked: flags[2] == b'U',
+    })
+}
+
+/// Parse `git status --porcelain=v2 --branch -z`. Records are NUL-terminated
+/// and paths are never quoted, so names with spaces, quotes or newlines come
+/// through intact; a rename/copy record is followed by its origin path as a
+/// separate field.
+pub(crate) fn parse_status_v2(stdout: &str) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut records = stdout.split('\0');
+
+    while let Some(record) = records.next() {
+        if let Some(header) = record.strip_prefix("# ") {
+            let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+            let branch = &mut status.branch;
+            match key {
+                "branch.oid" if value != "(initial)" => branch.oid = Some(value.to_string()),
+                "branch.head" if value != "(detached)" => branch.head = Some(value.to_string()),
+                "branch.upstream" => branch.upstream = Some(value.to_string()),
+                "branch.ab" => {
+                    for count in value.split(' ') {
+                        if let Some(n) = count.strip_prefix('+') {
+                            branch.ahead = n.parse().unwrap_or(0);
+                        } else if let Some(n) = count.strip_prefix('-') {
+                            branch.behind = n.parse().unwrap_or(0);
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        let (kind, rest) = match record.split_once(' ') {
+            Some(split) => split,
+            None => continue,
+        };
+        // Number of space-separated fields before the path, per record type.
+        let fields_before_path = match kind {
+            "1" => 7,
+            "2" => 8,
+            "u" => 9,
+            "?" | "!" => {
+                let xy = kind.repeat(2);
+                let xy = [xy.as_bytes()[0], xy.as_bytes()[1]];
+                status
+                    .entries
+                    .extend(status_entry(xy, rest.to_string(), None, None));
+                continue;
+            }
+            _ => continue,
+        };
+        let fields: Vec<&str> = rest.splitn(fields_before_path + 1, ' ').collect();
+        if fields.len() <= fields_before_path {
+            continue;
+        }
+        let xy = fields[0].as_bytes();
+        if xy.len() < 2 {
+            continue;
+        }
+        let orig_path = if kind == "2" {
+            records.next().map(|p| p.to_string())
+        } else {
+            None
+        };
+        status.entries.extend(status_entry(
+            [xy[0], xy[1]],
+            fields[fields_before_path].to_string(),
+            orig_path,
+            parse_submodule_state(fields[1]),
+        ));
+    }
+
+    status
+}
+
+/// Parse `git worktree list --porcelain`.
+fn parse_worktree_list(stdout: &str) -> Vec<WorktreeInfo> {
+    // Records are separated by blank lines. Each starts with `worktree <path>`,
+    // then `HEAD <sha>`, then either `branch refs/heads/<name>` or `detached`
+    // (and `bare` for a bare main repo, which has no HEAD/branch), then
+    // optional `locked [<reason>]` and `prunable <reason>` lines.
+    let mut worktrees: Vec<WorktreeInfo> = Vec::new();
+    for record in stdout.split("\n\n") {
+        let mut lines = record.lines();
+        let Some(path) = lines.next().and_then(|l| l.strip_prefix("worktree ")) else {
+            continue;
+        };
+        let mut info = WorktreeInfo {
+            source: classify_worktree(path, worktrees.is_empty()),
+            path: path.to_string(),
+            branch: None,
+            detached: false,
+            head: String::new(),
+            locked: false,
+            lock_reason: None,
+            prunable: false,
+            dirty: false,
+        };
+        for line in lines {
+            if let Some(h) = line.strip_prefix("HEAD ") {
+                info.head = h.to_string();
+            } else if let Some(b) = line.strip_prefix("branch ") {
+                info.branch = Some(b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
+            } else if line == "detached" {
+                info.detached = true;
+            } else if line == "locked" {
+                info.locked = true;
+            } else if let Some(reason) = line.strip_prefix("locked ") {
+                info.locked = true;
+                info.lock_reason = Some(reason.to_string());
+            } else if line.starts_with("prunable") {
+                info.prunable = true;
+            }
+        }
+        worktrees.push(info);
+    }
+    worktrees
+}
+
+/// Parse `--numstat -z --no-renames` records (`added\tremoved\tpath\0`, path
+/// unquoted). Binary files (`-\t-`) count as 0/0.
+pub(crate) fn parse_numstat(stdout: &str) -> Vec<FileDiffStat> {
+    stdout
+        .split('\0')
+        .filter_map(|record| {
+            let parts: Vec<&str> = record.trim_start_matches('\n').splitn(3, '\t').collect();
+            if parts.len() < 3 {
+                return None;
+            }
+            Some(FileDiffStat {
+                path: parts[2].to_string(),
+                added: parts[0].parse::<u32>().unwrap_or(0),
+                removed: parts[1].parse::<u32>().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+impl GitBackend for CliBackend {
+    fn name(&self) -> &'static str {
+        "cli"
+    }
+
+    fn status(&self, repo: &Path) -> Result<Option<GitStatus>, String> {
+        let output = run_git(repo, ["status", "--porcelain=v2", "--branch", "-z", "-uall"])?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        Ok(Some(parse_status_v2(&String::from_utf8_lossy(&output.stdout))))
+    }
+
+    fn numstat(&self, repo: &Path, path: Option<&str>) -> Result<Vec<FileDiffStat>, String> {
+        // Renames would be reported as `old => new`, which isn't a path, and
+        // without -z odd names come back quoted.
+        let mut args = vec!["diff", "--numstat", "-z", "--no-renames", "HEAD"];
+        if let Some(path) = path {
+            args.extend(["--", path]);
+        }
+        let output = run_git(repo, &args)?;
+        // A non-zero exit is a genuine git error (index.lock contention, no
+        // HEAD yet); `git diff` without --exit-code exits 0 with or without
+        // changes.
+        if !output.status.success() {
+            return Err(git_failure("diff", &output));
+        }
+        Ok(parse_numstat(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn diff(&self, repo: &Path, path: &str) -> Result<String, String> {
+        // Try normal diff first (tracked files)
+        let output = run_git(repo, ["diff", "HEAD", "--", path])?;
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        if !stdout.is_empty() {
+            return Ok(stdout);
+        }
+
+        // If empty, check if file is untracked and show as new file diff
+        let status_output = run_git(repo, ["status", "--porcelain", "--", path])?;
+        let status_str = String::from_utf8_lossy(&status_output.stdout);
+        if status_str.starts_with("??") {
+            let untracked = run_git(repo, ["diff", "--no-index", "/dev/null", path])?;
+            // git diff --no-index exits with 1 when there are differences, that's expected
+            return Ok(String::from_utf8_lossy(&untracked.stdout).to_string());
+        }
+
+        Ok(String::new())
+    }
+
+    fn worktrees(&self, repo: &Path) -> Result<Vec<WorktreeInfo>, String> {
+        let output = run_git(repo, ["worktree", "list", "--porcelain"])?;
+        if !output.status.success() {
+            // Not a git repo (or no worktree support) — surface nothing.
+            return Ok(vec![]);
+        }
+        Ok(parse_worktree_list(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    fn commit_info(&self, repo: &Path, rev: &str) -> Result<CommitInfo, String> {
+        let output = run_git(repo, ["log", "-1", "--format=%H%n%an%n%aI%n%s%n%b", rev, "--"])?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Not a valid commit: {}", stderr.trim()));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines: Vec<&str> = stdout.splitn(5, '\n').collect();
+        if lines.len() < 4 {
+            return Err("Unexpected git log output".to_string());
+        }
+
+        let hash = lines[0].to_string();
+        let author = lines[1].to_string();
+        let date = lines[2].to_string();
+        let subject = lines[3].to_string();
+        let body = if lines.len() >= 5 {
+            lines[4].trim_end().to_string()
+        } else {
+            String::new()
+        };
+
+        // Get per-file stats via --numstat
+        let numstat_output = run_git(repo, ["show", "--numstat", "-z", "--no-renames", "--format=", rev, "--"])?;
+        let mut file_stats: Vec<CommitFileStat> = Vec::new();
+        if numstat_output.status.success() {
+            let numstat = parse_numstat(&String::from_utf8_lossy(&numstat_output.stdout));
+            file_stats = numstat
+                .into_iter()
+                .map(|s| CommitFileStat {
+                    file: s.path,
+                    additions: s.added,
+                    deletions: s.removed,
+                })
+                .collect();
+        }
+
+        Ok(CommitInfo::new(hash, author, date, subject, body, file_stats))
+    }
+
+    fn show(&self, repo: &Path, rev: &str) -> Result<String, String> {
+        let output = run_git(repo, ["show", "--format=", rev, "--"])?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Not a valid commit: {}", stderr.trim()));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    fn log(&self, repo: &Path, query: &LogQuery, max: usize) -> Result<Vec<CommitSummary>, String> {
+        if query.range.is_none() && !has_head(repo) {
+            return Ok(Vec::new());
+        }
+        let mut args = vec![
+            "log".to_string(),
+            "--date-order".to_string(),
+            "--format=%H%x1f%P%x1f%an%x1f%aI%x1f%s".to_string(),
+            format!("--skip={}", query.skip),
+            format!("--max-count={}", max),
+        ];
+        if query.author.is_some() || query.grep.is_some() {
+            args.extend(["--fixed-strings".into(), "--regexp-ignore-case".into()]);
+        }
+        if let Some(author) = &query.author {
+            args.push(format!("--author={}", author));
+        }
+        if let Some(grep) = &query.grep {
+            args.push(format!("--grep={}", grep));
+        }
+        args.push("--end-of-options".into());
+        args.push(query.range.clone().unwrap_or_else(|| "HEAD".into()));
+        args.push("--".into());
+        args.extend(query.path.clone());
+
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("read history", &output));
+        }
+        Ok(parse_log(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Parse `git log --format=%H%x1f%P%x1f%an%x1f%aI%x1f%s`.
+fn parse_log(stdout: &str) -> Vec<CommitSummary> {
+    stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(5, '\x1f').collect();
+            if fields.len() < 5 {
+                return None;
+            }
+            Some(CommitSummary {
+                hash: fields[0].to_string(),
+                parents: fields[1].split_whitespace().map(str::to_string).collect(),
+                author: fields[2].to_string(),
+                date: fields[3].to_string(),
+                subject: fields[4].to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::types::{BranchStatus, FileState, GitFileStatus};
+
+    #[test]
+    fn parses_porcelain_v2_records() {
+        let out = "# branch.oid 1234\0# branch.head main\0# branch.upstream origin/main\0\
+                   # branch.ab +2 -1\0\
+                   1 MM N... 100644 100644 100644 aaaa bbbb src/a file.rs\0\
+                   2 R. N... 100644 100644 100644 aaaa aaaa R100 new\nname.rs\0old name.rs\0\
+                   1 .M SC.. 160000 160000 160000 cccc cccc vendor/lib\0\
+                   u UU N... 100644 100644 100644 100644 a b c conflict.rs\0\
+                   ? say \"hi\".txt\0";
+        let status = parse_status_v2(out);
+
+        assert_eq!(
+            status.branch,
+            BranchStatus {
+                oid: Some("1234".into()),
+                head: Some("main".into()),
+                upstream: Some("origin/main".into()),
+                ahead: 2,
+                behind: 1,
+            }
+        );
+        let e = &status.entries;
+        assert_eq!(e.len(), 5);
+        assert_eq!(e[0].path, "src/a file.rs");
+        assert_eq!((e[0].index, e[0].worktree), (FileState::Modified, FileState::Modified));
+        assert_eq!(e[1].path, "new\nname.rs");
+        assert_eq!(e[1].orig_path.as_deref(), Some("old name.rs"));
+        assert_eq!((e[1].index, e[1].worktree), (FileState::Renamed, FileState::Unmodified));
+        assert_eq!(
+            e[2].submodule,
+            Some(SubmoduleState {
+                commit_changed: true,
+                modified: false,
+                untracked: false,
+            })
+        );
+        assert_eq!(e[3].status, GitFileStatus::Conflicted);
+        assert_eq!(e[4].path, "say \"hi\".txt");
+        assert_eq!(e[4].status, GitFileStatus::Untracked);
+    }
+
+    #[test]
+    fn parses_worktree_lock_and_prune_flags() {
+        let out = "worktree /repo\nHEAD aaaa\nbranch refs/heads/main\n\n\
+                   worktree /wt/a\nHEAD bbbb\ndetached\nlocked\n\n\
+                   worktree /wt/b\nHEAD cccc\nbranch refs/heads/b\nlocked on usb\n\n\
+                   worktree /wt/c\nHEAD dddd\nbranch refs/heads/c\n\
+                   prunable gitdir file points to non-existent location\n";
+        let w = parse_worktree_list(out);
+        assert_eq!(w.len(), 4);
+        assert_eq!(w[0].source, "main");
+        assert!(w[1].detached && w[1].locked && w[1].lock_reason.is_none());
+        assert_eq!(w[2].lock_reason.as_deref(), Some("on usb"));
+        assert_eq!(w[3].branch.as_deref(), Some("c"));
+        assert!(w[3].prunable && !w[3].locked);
+    }
+}
@@ -0,0 +1,280 @@
+//! Creating commits from the staged index.
+
+use super::backend::GitBackend;
+use super::hooks::{hooks_dir, is_executable_file};
+use super::types::{CommitOptions, CommitOutcome, GitFileStatus};
+use super::{git_failure, query_backend, repo_dir, run_git, run_git_with_stdin};
+use std::path::Path;
+use std::time::Duration;
+
+/// Hooks `git commit` runs (and `--no-verify` skips, apart from
+/// prepare-commit-msg), in the order it runs them.
+const COMMIT_HOOKS: [&str; 3] = ["pre-commit", "prepare-commit-msg", "commit-msg"];
+
+/// Commit hooks run linters and test suites, so a commit gets longer than a
+/// plain query.
+const COMMIT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Paths git reports as unmerged.
+fn conflicted_paths(git: &dyn GitBackend, repo: &Path) -> Result<Vec<String>, String> {
+    let status = git.status(repo)?.unwrap_or_default();
+    Ok(status
+        .entries
+        .into_iter()
+        .filter(|e| e.status == GitFileStatus::Conflicted)
+        .map(|e| e.path)
+        .collect())
+}
+
+/// Whether the index differs from HEAD (or, before the first commit, has
+/// anything in it).
+fn has_staged_changes(repo: &Path) -> Result<bool, String> {
+    let output = run_git(repo, ["diff", "--cached", "--quiet", "--"])?;
+    match output.status.code() {
+        Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        _ => Err(git_failure("check for staged changes", &output)),
+    }
+}
+
+/// Commit-time hooks that are installed and executable.
+fn installed_commit_hooks(repo: &Path) -> Vec<String> {
+    let Ok(hooks_dir) = hooks_dir(repo) else {
+        return Vec::new();
+    };
+    COMMIT_HOOKS
+        .iter()
+        .filter(|hook| is_executable_file(&hooks_dir.join(hook)))
+        .map(|hook| hook.to_string())
+        .collect()
+}
+
+fn commit(
+    git: &dyn GitBackend,
+    repo: &Path,
+    options: &CommitOptions,
+) -> Result<CommitOutcome, String> {
+    let conflicts = conflicted_paths(git, repo)?;
+    if !conflicts.is_empty() {
+        return Err(format!(
+            "Cannot commit with unresolved conflicts in: {}",
+            conflicts.join(", ")
+        ));
+    }
+
+    let message = options.message.trim();
+    if message.is_empty() && !options.amend {
+        return Err("Commit message is empty".to_string());
+    }
+    if !options.allow_empty && !options.amend && !has_staged_changes(repo)? {
+        return Err("Nothing staged to commit".to_string());
+    }
+
+    let mut args: Vec<String> = vec!["commit".into()];
+    if message.is_empty() {
+        // Amending with no new message keeps the existing one.
+        args.push("--no-edit".into());
+    } else {
+        args.extend(["--file".into(), "-".into()]);
+    }
+    if options.amend {
+        args.push("--amend".into());
+    }
+    if options.signoff {
+        args.push("--signoff".into());
+    }
+    if options.allow_empty {
+        args.push("--allow-empty".into());
+    }
+    if options.no_verify {
+        args.push("--no-verify".into());
+    }
+    if let Some(author) = options
+        .author
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+    {
+        if !(author.contains('<') && author.ends_with('>')) {
+            return Err("Author must look like \"Name <email>\"".to_string());
+        }
+        args.push(format!("--author={}", author));
+    }
+
+    let output = run_git_with_stdin(repo, &args, message.as_bytes())?;
+    // Hooks' stdout is redirected to stderr by git; git's own summary line
+    // goes to stdout.
+    let hook_output = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+    if !output.status.success() {
+        let hooks = if options.no_verify {
+            Vec::new()
+        } else {
+            installed_commit_hooks(repo)
+        };
+        // Whatever git itself has to say ("nothing to commit", the commit
+        // summary) goes to stdout; a hook stopping the commit leaves it empty.
+        let git_spoke = !output.stdout.iter().all(u8::is_ascii_whitespace);
+        let git_refused = hook_output.lines().any(|l| l.starts_with("fatal:"));
+        if !hooks.is_empty() && !git_spoke && !git_refused {
+            return Ok(CommitOutcome::HookRejected {
+                hooks,
+                output: hook_output,
+            });
+        }
+        return Err(git_failure("commit", &output));
+    }
+
+    Ok(CommitOutcome::Committed {
+        commit: git.commit_info(repo, "HEAD")?,
+        hook_output,
+    })
+}
+
+/// Commit the staged changes (or amend HEAD). The message goes to git on
+/// stdin so it survives any characters. Unless amending or `allow_empty`,
+/// an empty index is refused up front. A failure that git itself says
+/// nothing about while commit hooks are installed is reported as a hook
+/// rejection with the hooks' output, rather than as an error.
+#[tauri::command]
+pub async fn git_commit(
+    repo_path: String,
+    options: CommitOptions,
+) -> Result<CommitOutcome, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, COMMIT_TIMEOUT, move |git, repo| {
+        commit(git, repo, &options)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    fn options(message: &str) -> CommitOptions {
+        CommitOptions {
+            message: message.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn commits_staged_changes_with_signoff_and_author() {
+        let repo = test_repo("commit");
+        commit_file(&repo, "a.txt", "one\n", "init");
+        fs::write(repo.join("a.txt"), "two\n").unwrap();
+        git(&repo, &["add", "a.txt"]);
+
+        let outcome = git_commit(
+            repo.to_string_lossy().to_string(),
+            CommitOptions {
+                signoff: true,
+                author: Some("Someone Else <else@example.com>".to_string()),
+                ..options("Change a\n\nLonger body")
+            },
+        )
+        .await
+        .unwrap();
+
+        match outcome {
+            CommitOutcome::Committed { commit, .. } => {
+                assert_eq!(commit.subject, "Change a");
+                assert_eq!(commit.author, "Someone Else");
+                assert!(commit
+                    .body
+                    .contains("Signed-off-by: Test <test@example.com>"));
+                assert_eq!(commit.files_changed, 1);
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn failing_pre_commit_hook_is_reported() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = test_repo("commit_hook");
+        commit_file(&repo, "a.txt", "one\n", "init");
+        let hook = repo.join(".git/hooks/pre-commit");
+        fs::write(&hook, "#!/bin/sh\necho 'lint failed' >&2\nexit 1\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let root = repo.to_string_lossy().to_string();
+        let outcome = git_commit(
+            root.clone(),
+            CommitOptions {
+                allow_empty: true,
+                ..options("Empty")
+            },
+        )
+        .await
+        .unwrap();
+        match outcome {
+            CommitOutcome::HookRejected { hooks, output } => {
+                assert_eq!(hooks, vec!["pre-commit".to_string()]);
+                assert!(output.contains("lint failed"));
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+
+        // --no-verify bypasses it.
+        let outcome = git_commit(
+            root,
+            CommitOptions {
+                allow_empty: true,
+                no_verify: true,
+                ..options("Empty")
+            },
+        )
+        .await
+        .unwrap();
+        assert!(matches!(outcome, CommitOutcome::Committed { .. }));
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn nothing_staged_is_not_a_hook_rejection() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let repo = test_repo("commit_nothing");
+        commit_file(&repo, "a.txt", "one\n", "init");
+        let hook = repo.join(".git/hooks/commit-msg");
+        fs::write(&hook, "#!/bin/sh\nexit 0\n").unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::write(repo.join("a.txt"), "unstaged\n").unwrap();
+
+        let err = git_commit(repo.to_string_lossy().to_string(), options("Nothing"))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Nothing staged to commit");
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn refuses_to_commit_with_conflicts() {
+        let repo = test_repo("commit_conflict");
+        commit_file(&repo, "a.txt", "base\n", "init");
+        git(&repo, &["checkout", "-q", "-b", "other"]);
+        commit_file(&repo, "a.txt", "theirs\n", "theirs");
+        git(&repo, &["checkout", "-q", "main"]);
+        commit_file(&repo, "a.txt", "ours\n", "ours");
+        let merge = std::process::Command::new("git")
+            .args(["merge", "other"])
+            .current_dir(&repo)
+            .output()
+            .unwrap();
+        assert!(!merge.status.success());
+
+        let err = git_commit(repo.to_string_lossy().to_string(), options("Merge"))
+            .await
+            .unwrap_err();
+        assert!(err.contains("a.txt"), "{err}");
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,349 @@
+//! Comparing any two states of a repo: refs, the merge base of two refs, the
+//! index, or the working tree of one of its worktrees.
+//!
+//! Each endpoint is reduced to a tree (or commit) id first, so every pairing
+//! is a plain `git diff <a> <b>`. A working tree becomes a tree by staging it
+//! into a scratch copy of its index, leaving the real index untouched.
+
+use super::backend::backend;
+use super::cli::parse_numstat;
+use super::diff::{diff_args, run_diff};
+use super::types::{ChangedFile, DiffChange, DiffEndpoint, DiffFile, DiffOptions, FileDiffStat};
+use super::worktrees::same_path;
+use super::{
+    git_failure, query_backend, repo_dir, run_git, run_git_with_env, validate_rev, BACKEND_TIMEOUT,
+};
+use std::path::Path;
+
+fn stdout_line(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+/// The tree git writes for the files on disk in `worktree`: its index with
+/// every change (untracked files included) staged on top.
+pub(crate) fn working_tree_id(worktree: &Path) -> Result<String, String> {
+    let index = run_git(worktree, ["rev-parse", "--git-path", "index"])?;
+    if !index.status.success() {
+        return Err(git_failure("locate the index", &index));
+    }
+    let index = worktree.join(stdout_line(&index));
+    let scratch = std::env::temp_dir().join(format!("codezilla-index-{}", uuid::Uuid::new_v4()));
+    if index.is_file() {
+        std::fs::copy(&index, &scratch).map_err(|e| format!("Failed to copy the index: {}", e))?;
+    }
+
+    let env = [("GIT_INDEX_FILE", scratch.as_os_str())];
+    let result = run_git_with_env(worktree, ["add", "--all"], &env).and_then(|added| {
+        if !added.status.success() {
+            return Err(git_failure("snapshot the working tree", &added));
+        }
+        let tree = run_git_with_env(worktree, ["write-tree"], &env)?;
+        if !tree.status.success() {
+            return Err(git_failure("snapshot the working tree", &tree));
+        }
+        Ok(stdout_line(&tree))
+    });
+    let _ = std::fs::remove_file(&scratch);
+    result
+}
+
+/// Reduce `endpoint` to an id `git diff` accepts.
+fn resolve(repo: &Path, endpoint: &DiffEndpoint) -> Result<String, String> {
+    match endpoint {
+        DiffEndpoint::Ref { rev } => {
+            validate_rev(rev)?;
+            let output = run_git(
+                repo,
+                [
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("{}^{{tree}}", rev),
+                ],
+            )?;
+            if !output.status.success() {
+                return Err(format!("Unknown revision: {}", rev));
+            }
+            Ok(stdout_line(&output))
+        }
+        DiffEndpoint::MergeBase { a, b } => {
+            validate_rev(a)?;
+            validate_rev(b)?;
+            let output = run_git(repo, ["merge-base", a.as_str(), b.as_str()])?;
+            if !output.status.success() {
+                return Err(format!("{} and {} have no common ancestor", a, b));
+            }
+            Ok(stdout_line(&output))
+        }
+        DiffEndpoint::Index => {
+            let output = run_git(repo, ["write-tree"])?;
+            if !output.status.success() {
+                return Err(git_failure("read the index", &output));
+            }
+            Ok(stdout_line(&output))
+        }
+        DiffEndpoint::WorkingTree { path } => {
+            // Snapshotting writes blobs into the worktree's object store, so
+            // it has to share this repo's for the diff to see them.
+            let worktrees = backend().worktrees(repo)?;
+            let worktree = worktrees
+                .iter()
+                .find(|w| same_path(&w.path, Path::new(path)))
+                .ok_or_else(|| format!("Not a worktree of this repository: {}", path))?;
+            working_tree_id(Path::new(&worktree.path))
+        }
+    }
+}
+
+/// `git diff <flags> <base> <target> -- [path]`, with both endpoints resolved.
+fn diff_between(
+    repo: &Path,
+    mut args: Vec<String>,
+    base: &DiffEndpoint,
+    target: &DiffEndpoint,
+    file_path: Option<String>,
+) -> Result<Vec<String>, String> {
+    args.extend([
+        resolve(repo, base)?,
+        resolve(repo, target)?,
+        "--".to_string(),
+    ]);
+    args.extend(file_path);
+    Ok(args)
+}
+
+/// Parse `--name-status -z` records: a status letter (with a similarity
+/// score for renames and copies) followed by one path, or two for those.
+fn parse_name_status(stdout: &str) -> Vec<ChangedFile> {
+    let mut fields = stdout.split('\0');
+    let mut files = Vec::new();
+    while let Some(status) = fields.next() {
+        let Some(kind) = status.chars().next() else {
+            continue;
+        };
+        let similarity = status[1..].parse::<u32>().ok();
+        let (change, two_paths) = match kind {
+            'A' => (DiffChange::Added, false),
+            'D' => (DiffChange::Deleted, false),
+            'R' => (DiffChange::Renamed, true),
+            'C' => (DiffChange::Copied, true),
+            _ => (DiffChange::Modified, false),
+        };
+        let Some(first) = fields.next() else {
+            break;
+        };
+        let (old_path, path) = if two_paths {
+            let Some(second) = fields.next() else {
+                break;
+            };
+            (Some(first.to_string()), second.to_string())
+        } else {
+            (None, first.to_string())
+        };
+        files.push(ChangedFile {
+            path,
+            old_path,
+            change,
+            similarity: if two_paths { similarity } else { None },
+        });
+    }
+    files
+}
+
+/// Structured diff from `base` to `target`, optionally limited to one path
+/// (relative to `repo_path`).
+#[tauri::command]
+pub async fn get_diff_between(
+    repo_path: String,
+    base: DiffEndpoint,
+    target: DiffEndpoint,
+    file_path: Option<String>,
+    options: DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let args = diff_between(repo, diff_args(&options), &base, &target, file_path)?;
+        run_diff(repo, &args, &options)
+    })
+    .await
+}
+
+/// Per-file added/removed line counts from `base` to `target`.
+#[tauri::command]
+pub async fn get_numstat_between(
+    repo_path: String,
+    base: DiffEndpoint,
+    target: DiffEndpoint,
+) -> Result<Vec<FileDiffStat>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let flags = ["diff", "--numstat", "-z", "--no-renames"].map(String::from);
+        let args = diff_between(repo, flags.to_vec(), &base, &target, None)?;
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("diff", &output));
+        }
+        Ok(parse_numstat(&String::from_utf8_lossy(&output.stdout)))
+    })
+    .await
+}
+
+/// The paths that differ from `base` to `target` and how.
+#[tauri::command]
+pub async fn get_changed_files_between(
+    repo_path: String,
+    base: DiffEndpoint,
+    target: DiffEndpoint,
+    detect_renames: Option<bool>,
+) -> Result<Vec<ChangedFile>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let renames = if detect_renames.unwrap_or(true) {
+            "--find-renames"
+        } else {
+            "--no-renames"
+        };
+        let flags = [
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--name-status",
+            "-z",
+            renames,
+        ];
+        let args = diff_between(repo, flags.map(String::from).to_vec(), &base, &target, None)?;
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("diff", &output));
+        }
+        Ok(parse_name_status(&String::from_utf8_lossy(&output.stdout)))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    fn rev(rev: &str) -> DiffEndpoint {
+        DiffEndpoint::Ref {
+            rev: rev.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_name_status_records() {
+        let files = parse_name_status("M\0a.txt\0R087\0old.rs\0new.rs\0A\0b c.txt\0");
+        assert_eq!(files.len(), 3);
+        assert_eq!(files[0].change, DiffChange::Modified);
+        assert_eq!(files[1].old_path.as_deref(), Some("old.rs"));
+        assert_eq!(
+            (files[1].path.as_str(), files[1].similarity),
+            ("new.rs", Some(87))
+        );
+        assert_eq!(
+            (files[2].path.as_str(), files[2].change),
+            ("b c.txt", DiffChange::Added)
+        );
+    }
+
+    #[tokio::test]
+    async fn compares_refs_merge_bases_and_worktrees() {
+        let repo = test_repo("compare");
+        commit_file(&repo, "shared.txt", "one\n", "init");
+        git(&repo, &["branch", "feature"]);
+        commit_file(&repo, "main.txt", "main\n", "on main");
+
+        let linked = repo.with_file_name(format!(
+            "{}-feature",
+            repo.file_name().unwrap().to_string_lossy()
+        ));
+        git(
+            &repo,
+            &[
+                "worktree",
+                "add",
+                "-q",
+                &linked.to_string_lossy(),
+                "feature",
+            ],
+        );
+        fs::write(linked.join("shared.txt"), "one\ntwo\n").unwrap();
+        fs::write(linked.join("new.txt"), "untracked\n").unwrap();
+        let repo_path = repo.to_string_lossy().to_string();
+
+        // Merge base of main and feature → feature's working tree: only the
+        // worktree's own edits, not main's later commit.
+        let base = DiffEndpoint::MergeBase {
+            a: "main".into(),
+            b: "feature".into(),
+        };
+        let target = DiffEndpoint::WorkingTree {
+            path: linked.to_string_lossy().to_string(),
+        };
+        let changed =
+            get_changed_files_between(repo_path.clone(), base.clone(), target.clone(), None)
+                .await
+                .unwrap();
+        let mut paths: Vec<_> = changed.iter().map(|f| f.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["new.txt", "shared.txt"]);
+
+        let stats = get_numstat_between(repo_path.clone(), base.clone(), target.clone())
+            .await
+            .unwrap();
+        let shared = stats.iter().find(|s| s.path == "shared.txt").unwrap();
+        assert_eq!((shared.added, shared.removed), (1, 0));
+
+        let files = get_diff_between(
+            repo_path.clone(),
+            base,
+            target,
+            Some("shared.txt".into()),
+            DiffOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].added, 1);
+        // The linked worktree's real index is left alone.
+        assert_eq!(git(&linked, &["diff", "--cached", "--name-only"]), "");
+
+        // Ref → index.
+        fs::write(repo.join("main.txt"), "staged\n").unwrap();
+        git(&repo, &["add", "main.txt"]);
+        let changed =
+            get_changed_files_between(repo_path.clone(), rev("HEAD"), DiffEndpoint::Index, None)
+                .await
+                .unwrap();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].path, "main.txt");
+
+        let err = get_diff_between(
+            repo_path.clone(),
+            rev("--output=/tmp/x"),
+            DiffEndpoint::Index,
+            None,
+            DiffOptions::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Invalid revision"), "{}", err);
+
+        let err = get_numstat_between(
+            repo_path,
+            rev("HEAD"),
+            DiffEndpoint::WorkingTree {
+                path: std::env::temp_dir().to_string_lossy().to_string(),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("Not a worktree"), "{}", err);
+
+        let _ = fs::remove_dir_all(&linked);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,499 @@
+//! Resolving merge conflicts, and continuing or aborting the merge, rebase
+//! or cherry-pick that produced them.
+
+use super::types::{
+    ConflictFile, ConflictRegion, ConflictResolution, GitOperation, GitOperationKind, RegionChoice,
+};
+use super::{git_failure, query_backend, repo_dir, run_git, BACKEND_TIMEOUT};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Continuing a rebase replays every commit left, running hooks for each.
+const CONTINUE_TIMEOUT: Duration = Duration::from_secs(600);
+
+enum Chunk<'a> {
+    Text(&'a str),
+    Conflict(ConflictRegion),
+}
+
+/// The marker at the start of `line` (`<<<<<<<`, `|||||||`, `=======`,
+/// `>>>>>>>`), as its first char, plus the label after it.
+fn marker(line: &str) -> Option<(char, &str)> {
+    let first = line.chars().next()?;
+    if !"<|=>".contains(first) || !line.starts_with(&first.to_string().repeat(7)) {
+        return None;
+    }
+    let rest = &line[7..];
+    if !(rest.is_empty() || rest.starts_with([' ', '\r', '\n'])) {
+        return None;
+    }
+    Some((first, rest.trim()))
+}
+
+/// Split a conflicted file into plain text and conflict regions. An
+/// unterminated region is left as plain text.
+fn split_conflicts(text: &str) -> Vec<Chunk<'_>> {
+    let mut chunks = Vec::new();
+    let mut plain_start = 0;
+    let mut offset = 0;
+    // The region being read, the byte offset of its `<<<<<<<` line, and
+    // which side the following lines belong to.
+    let mut open: Option<(ConflictRegion, usize, char)> = None;
+
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let line_no = index as u32 + 1;
+        let start = offset;
+        offset += line.len();
+        let mark = marker(line);
+        let Some((region, _, side)) = open.as_mut() else {
+            if let Some(('<', label)) = mark {
+                if start > plain_start {
+                    chunks.push(Chunk::Text(&text[plain_start..start]));
+                }
+                let region = ConflictRegion {
+                    start_line: line_no,
+                    end_line: line_no,
+                    ours_label: label.to_string(),
+                    theirs_label: String::new(),
+                    ours: String::new(),
+                    base: None,
+                    theirs: String::new(),
+                };
+                open = Some((region, start, '<'));
+            }
+            continue;
+        };
+        match mark {
+            Some(('|', _)) if *side == '<' => {
+                region.base = Some(String::new());
+                *side = '|';
+            }
+            Some(('=', _)) if *side != '=' => *side = '=',
+            Some(('>', label)) if *side == '=' => {
+                region.end_line = line_no;
+                region.theirs_label = label.to_string();
+                let (region, _, _) = open.take().unwrap();
+                chunks.push(Chunk::Conflict(region));
+                plain_start = offset;
+            }
+            _ => match side {
+                '<' => region.ours.push_str(line),
+                '|' => region.base.get_or_insert_with(String::new).push_str(line),
+                _ => region.theirs.push_str(line),
+            },
+        }
+    }
+    if let Some((_, start, _)) = open {
+        plain_start = start;
+    }
+    if plain_start < text.len() {
+        chunks.push(Chunk::Text(&text[plain_start..]));
+    }
+    chunks
+}
+
+fn parse_regions(text: &str) -> Vec<ConflictRegion> {
+    split_conflicts(text)
+        .into_iter()
+        .filter_map(|chunk| match chunk {
+            Chunk::Conflict(region) => Some(region),
+            Chunk::Text(_) => None,
+        })
+        .collect()
+}
+
+/// Rebuild `text` with each conflict region replaced by its choice.
+fn apply_choices(text: &str, choices: &[RegionChoice]) -> Result<String, String> {
+    let chunks = split_conflicts(text);
+    let regions = chunks
+        .iter()
+        .filter(|c| matches!(c, Chunk::Conflict(_)))
+        .count();
+    if regions != choices.len() {
+        return Err(format!(
+            "The file has {} conflict region(s) but {} choice(s) were given",
+            regions,
+            choices.len()
+        ));
+    }
+    let mut choices = choices.iter();
+    let mut out = String::with_capacity(text.len());
+    for chunk in chunks {
+        match chunk {
+            Chunk::Text(t) => out.push_str(t),
+            Chunk::Conflict(region) => match choices.next().unwrap() {
+                RegionChoice::Ours => out.push_str(&region.ours),
+                RegionChoice::Theirs => out.push_str(&region.theirs),
+                RegionChoice::Base => match &region.base {
+                    Some(base) => out.push_str(base),
+                    None => {
+                        return Err(format!(
+                            "The region at line {} has no base version",
+                            region.start_line
+                        ))
+                    }
+                },
+                RegionChoice::Both => {
+                    out.push_str(&region.ours);
+                    out.push_str(&region.theirs);
+                }
+                RegionChoice::Text { text } => out.push_str(text),
+            },
+        }
+    }
+    Ok(out)
+}
+
+/// `file_path` as a path inside the repo, refusing anything that could
+/// step outside it.
+fn repo_relative(file_path: &str) -> Result<&Path, String> {
+    let path = Path::new(file_path);
+    if file_path.is_empty()
+        || !path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(format!("Invalid path: {}", file_path));
+    }
+    Ok(path)
+}
+
+fn is_conflicted(repo: &Path, file_path: &str) -> Result<bool, String> {
+    let output = run_git(repo, ["ls-files", "--unmerged", "--", file_path])?;
+    if !output.status.success() {
+        return Err(git_failure("read the index", &output));
+    }
+    Ok(!output.stdout.is_empty())
+}
+
+/// The blob at index `stage` (1 base, 2 ours, 3 theirs), None if absent.
+fn stage_blob(repo: &Path, file_path: &str, stage: u8) -> Result<Option<Vec<u8>>, String> {
+    let spec = format!(":{}:./{}", stage, file_path);
+    let output = run_git(repo, ["cat-file", "blob", &spec])?;
+    Ok(output.status.success().then_some(output.stdout))
+}
+
+fn read_conflict(repo: &Path, file_path: String) -> Result<ConflictFile, String> {
+    let relative = repo_relative(&file_path)?;
+    if !is_conflicted(repo, &file_path)? {
+        return Err(format!("{} has no conflicts", file_path));
+    }
+    let stages = [1, 2, 3]
+        .into_iter()
+        .map(|stage| stage_blob(repo, &file_path, stage))
+        .collect::<Result<Vec<_>, _>>()?;
+    let working = match std::fs::read(repo.join(relative)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", file_path, e)),
+    };
+
+    let is_text = |bytes: &[u8]| !bytes.contains(&0) && std::str::from_utf8(bytes).is_ok();
+    let binary = !is_text(&working) || stages.iter().flatten().any(|b| !is_text(b));
+    let [base, ours, theirs]: [Option<String>; 3] = stages
+        .into_iter()
+        .map(|blob| {
+            blob.filter(|_| !binary)
+                .map(|b| String::from_utf8_lossy(&b).into_owned())
+        })
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap();
+    let regions = if binary {
+        Vec::new()
+    } else {
+        parse_regions(&String::from_utf8_lossy(&working))
+    };
+
+    Ok(ConflictFile {
+        path: file_path,
+        base,
+        ours,
+        theirs,
+        binary,
+        regions,
+    })
+}
+
+/// Base/ours/theirs contents and the conflict regions of the working copy of
+/// a conflicted file (`file_path` relative to `repo_path`).
+#[tauri::command]
+pub async fn get_conflict(repo_path: String, file_path: String) -> Result<ConflictFile, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        read_conflict(repo, file_path)
+    })
+    .await
+}
+
+fn resolve(repo: &Path, file_path: &str, resolution: ConflictResolution) -> Result<(), String> {
+    let path = repo.join(repo_relative(file_path)?);
+    if !is_conflicted(repo, file_path)? {
+        return Err(format!("{} has no conflicts", file_path));
+    }
+
+    let content = match resolution {
+        ConflictResolution::Delete => {
+            let output = run_git(repo, ["rm", "--quiet", "--force", "--", file_path])?;
+            if !output.status.success() {
+                return Err(git_failure("resolve conflict", &output));
+            }
+            return Ok(());
+        }
+        ConflictResolution::Content { content } => content,
+        ConflictResolution::Regions { choices } => {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", file_path, e))?;
+            apply_choices(&text, &choices)?
+        }
+    };
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write {}: {}", file_path, e))?;
+    let output = run_git(repo, ["add", "--", file_path])?;
+    if !output.status.success() {
+        return Err(git_failure("mark conflict resolved", &output));
+    }
+    Ok(())
+}
+
+/// Write a resolution for a conflicted file and mark it resolved.
+#[tauri::command]
+pub async fn resolve_conflict(
+    repo_path: String,
+    file_path: String,
+    resolution: ConflictResolution,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        resolve(repo, &file_path, resolution)
+    })
+    .await
+}
+
+fn git_dir(repo: &Path) -> Result<PathBuf, String> {
+    let output = run_git(repo, ["rev-parse", "--absolute-git-dir"])?;
+    if !output.status.success() {
+        return Err("Not a git repository".to_string());
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+fn read_number(path: PathBuf) -> Option<u32> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The stopped operation in the worktree at `repo`, from the state files
+/// git leaves in its (per-worktree) git dir.
+fn current_operation(repo: &Path) -> Result<Option<GitOperation>, String> {
+    let dir = git_dir(repo)?;
+    let rebase_merge = dir.join("rebase-merge");
+    let rebase_apply = dir.join("rebase-apply");
+    let (kind, step) = if rebase_merge.is_dir() {
+        let step =
+            read_number(rebase_merge.join("msgnum")).zip(read_number(rebase_merge.join("end")));
+        (GitOperationKind::Rebase, step)
+    } else if rebase_apply.is_dir() && !rebase_apply.join("applying").exists() {
+        // `applying` marks a `git am` session rather than a rebase.
+        let step =
+            read_number(rebase_apply.join("next")).zip(read_number(rebase_apply.join("last")));
+        (GitOperationKind::Rebase, step)
+    } else if dir.join("MERGE_HEAD").is_file() {
+        (GitOperationKind::Merge, None)
+    } else if dir.join("CHERRY_PICK_HEAD").is_file() {
+        (GitOperationKind::CherryPick, None)
+    } else {
+        return Ok(None);
+    };
+
+    let output = run_git(repo, ["diff", "--name-only", "--diff-filter=U", "-z"])?;
+    if !output.status.success() {
+        return Err(git_failure("list conflicts", &output));
+    }
+    let conflicted = String::from_utf8_lossy(&output.stdout)
+        .split('\0')
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect();
+    Ok(Some(GitOperation {
+        kind,
+        conflicted,
+        step,
+    }))
+}
+
+fn verb(kind: GitOperationKind) -> &'static str {
+    match kind {
+        GitOperationKind::Merge => "merge",
+        GitOperationKind::Rebase => "rebase",
+        GitOperationKind::CherryPick => "cherry-pick",
+    }
+}
+
+/// The merge, rebase or cherry-pick in progress in this worktree, if any.
+#[tauri::command]
+pub async fn get_git_operation(repo_path: String) -> Result<Option<GitOperation>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |_, repo| current_operation(repo)).await
+}
+
+#[tauri::command]
+pub async fn abort_git_operation(repo_path: String) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |_, repo| {
+        let op = current_operation(repo)?
+            .ok_or_else(|| "No merge, rebase or cherry-pick in progress".to_string())?;
+        let output = run_git(repo, [verb(op.kind), "--abort"])?;
+        if !output.status.success() {
+            return Err(git_failure(&format!("abort {}", verb(op.kind)), &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Continue once every conflict is resolved, keeping git's prepared commit
+/// messages. Returns the operation if it stopped again (a rebase reaching
+/// its next conflicting commit), None once it's finished.
+#[tauri::command]
+pub async fn continue_git_operation(repo_path: String) -> Result<Option<GitOperation>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, CONTINUE_TIMEOUT, |_, repo| {
+        let op = current_operation(repo)?
+            .ok_or_else(|| "No merge, rebase or cherry-pick in progress".to_string())?;
+        if !op.conflicted.is_empty() {
+            return Err(format!(
+                "Resolve the conflicts in {} first",
+                op.conflicted.join(", ")
+            ));
+        }
+        // `core.editor=true` accepts the message git would open an editor for.
+        let output = run_git(
+            repo,
+            ["-c", "core.editor=true", verb(op.kind), "--continue"],
+        )?;
+        let after = current_operation(repo)?;
+        if !output.status.success() && after.as_ref().is_none_or(|op| op.conflicted.is_empty()) {
+            return Err(git_failure(&format!("continue {}", verb(op.kind)), &output));
+        }
+        Ok(after)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    #[test]
+    fn parses_and_resolves_regions() {
+        let text =
+            "top\n<<<<<<< HEAD\nours 1\n||||||| base\nbase 1\n=======\ntheirs 1\n>>>>>>> feature\n\
+                    middle\n<<<<<<< HEAD\nours 2\n=======\ntheirs 2\n>>>>>>> feature\nend\n";
+        let regions = parse_regions(text);
+        assert_eq!(regions.len(), 2);
+        assert_eq!((regions[0].start_line, regions[0].end_line), (2, 8));
+        assert_eq!(regions[0].base.as_deref(), Some("base 1\n"));
+        assert_eq!(regions[0].theirs_label, "feature");
+        assert_eq!(regions[1].ours, "ours 2\n");
+        assert!(regions[1].base.is_none());
+
+        let resolved = apply_choices(
+            text,
+            &[
+                RegionChoice::Both,
+                RegionChoice::Text {
+                    text: "mine\n".into(),
+                },
+            ],
+        )
+        .unwrap();
+        assert_eq!(resolved, "top\nours 1\ntheirs 1\nmiddle\nmine\nend\n");
+        assert!(apply_choices(text, &[RegionChoice::Ours]).is_err());
+        assert!(apply_choices(text, &[RegionChoice::Ours, RegionChoice::Base]).is_err());
+    }
+
+    #[test]
+    fn unterminated_region_is_plain_text() {
+        let text = "a\n<<<<<<< HEAD\nb\n";
+        assert!(parse_regions(text).is_empty());
+        assert_eq!(apply_choices(text, &[]).unwrap(), text);
+    }
+
+    fn conflicted_repo(name: &str) -> PathBuf {
+        let repo = test_repo(name);
+        commit_file(&repo, "a.txt", "base\n", "init");
+        git(&repo, &["checkout", "-q", "-b", "other"]);
+        commit_file(&repo, "a.txt", "theirs\n", "theirs");
+        git(&repo, &["checkout", "-q", "main"]);
+        commit_file(&repo, "a.txt", "ours\n", "ours");
+        repo
+    }
+
+    #[tokio::test]
+    async fn resolves_merge_and_continues() {
+        let repo = conflicted_repo("conflict_merge");
+        let root = repo.to_string_lossy().to_string();
+        let merge = std::process::Command::new("git")
+            .args(["-c", "merge.conflictStyle=diff3", "merge", "other"])
+            .current_dir(&repo)
+            .output()
+            .unwrap();
+        assert!(!merge.status.success());
+
+        let op = get_git_operation(root.clone()).await.unwrap().unwrap();
+        assert_eq!(op.kind, GitOperationKind::Merge);
+        assert_eq!(op.conflicted, vec!["a.txt".to_string()]);
+        assert!(continue_git_operation(root.clone()).await.is_err());
+
+        let file = get_conflict(root.clone(), "a.txt".into()).await.unwrap();
+        assert_eq!(file.base.as_deref(), Some("base\n"));
+        assert_eq!(file.ours.as_deref(), Some("ours\n"));
+        assert_eq!(file.theirs.as_deref(), Some("theirs\n"));
+        assert_eq!(file.regions.len(), 1);
+        assert_eq!(file.regions[0].base.as_deref(), Some("base\n"));
+
+        resolve_conflict(
+            root.clone(),
+            "a.txt".into(),
+            ConflictResolution::Regions {
+                choices: vec![RegionChoice::Theirs],
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "theirs\n");
+        assert!(continue_git_operation(root.clone())
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            git(&repo, &["rev-list", "--count", "--merges", "HEAD"]).trim(),
+            "1"
+        );
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn aborts_rebase() {
+        let repo = conflicted_repo("conflict_rebase");
+        let root = repo.to_string_lossy().to_string();
+        let head = git(&repo, &["rev-parse", "HEAD"]);
+        let rebase = std::process::Command::new("git")
+            .args(["rebase", "other"])
+            .current_dir(&repo)
+            .output()
+            .unwrap();
+        assert!(!rebase.status.success());
+
+        let op = get_git_operation(root.clone()).await.unwrap().unwrap();
+        assert_eq!(op.kind, GitOperationKind::Rebase);
+        assert_eq!(op.step, Some((1, 1)));
+        abort_git_operation(root.clone()).await.unwrap();
+        assert!(get_git_operation(root.clone()).await.unwrap().is_none());
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), head);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
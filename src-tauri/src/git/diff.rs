@@ -0,0 +1,613 @@
+//! Structured diffs: files, hunks and numbered lines parsed from `git diff`,
+//! with options for context, whitespace, renames and word-level highlights.
+//!
+//! These come from the CLI rather than the [`GitBackend`](super::backend)
+//! so every option means exactly what it means to git.
+
+use super::types::{
+    DiffChange, DiffFile, DiffHunk, DiffLine, DiffLineKind, DiffOptions, LineSegment,
+    WhitespaceMode,
+};
+use super::{
+    git_failure, has_head, query_backend, repo_dir, run_git, untracked_in, validate_rev_range,
+    BACKEND_TIMEOUT,
+};
+use std::path::Path;
+
+/// The tree with no entries, for diffing a root commit or an unborn HEAD.
+pub(crate) const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// `git diff` flags shared by every structured diff.
+pub(crate) fn diff_args(options: &DiffOptions) -> Vec<String> {
+    // Fixed prefixes and no external tools, whatever the user's config says.
+    let mut args: Vec<String> = [
+        "-c",
+        "core.quotePath=false",
+        "diff",
+        "--no-color",
+        "--no-ext-diff",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+    ]
+    .map(String::from)
+    .to_vec();
+    if let Some(n) = options.context_lines {
+        args.push(format!("--unified={}", n));
+    }
+    match options.whitespace {
+        WhitespaceMode::Show => {}
+        WhitespaceMode::IgnoreAtEol => args.push("--ignore-space-at-eol".into()),
+        WhitespaceMode::IgnoreChange => args.push("--ignore-space-change".into()),
+        WhitespaceMode::IgnoreAll => args.push("--ignore-all-space".into()),
+    }
+    args.push(if options.detect_renames {
+        "--find-renames".into()
+    } else {
+        "--no-renames".into()
+    });
+    args
+}
+
+/// Undo git's C-style quoting of a path (`"a\tb"`); unquoted paths pass
+/// through.
+fn unquote(path: &str) -> String {
+    let Some(inner) = path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) else {
+        return path.to_string();
+    };
+    let mut bytes = Vec::with_capacity(inner.len());
+    let mut chars = inner.bytes().peekable();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+        match chars.next() {
+            Some(b'n') => bytes.push(b'\n'),
+            Some(b't') => bytes.push(b'\t'),
+            Some(b'r') => bytes.push(b'\r'),
+            Some(b'a') => bytes.push(7),
+            Some(b'b') => bytes.push(8),
+            Some(b'f') => bytes.push(12),
+            Some(b'v') => bytes.push(11),
+            Some(d @ b'0'..=b'7') => {
+                let mut value = u32::from(d - b'0');
+                for _ in 0..2 {
+                    match chars.peek() {
+                        Some(&o @ b'0'..=b'7') => {
+                            value = value * 8 + u32::from(o - b'0');
+                            chars.next();
+                        }
+                        _ => break,
+                    }
+                }
+                bytes.push(value as u8);
+            }
+            Some(other) => bytes.push(other),
+            None => {}
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// A `---`/`+++` path: None for `/dev/null`, otherwise without its prefix.
+fn side_path(raw: &str, prefix: &str) -> Option<String> {
+    // A trailing tab separates the name from a timestamp in some diffs.
+    let raw = unquote(raw.trim_end_matches('\t'));
+    if raw == "/dev/null" {
+        return None;
+    }
+    Some(raw.strip_prefix(prefix).unwrap_or(&raw).to_string())
+}
+
+/// Both paths from `diff --git a/<old> b/<new>`. Only reliable when they're
+/// quoted or identical, which covers everything without rename/copy lines.
+fn header_paths(rest: &str) -> (String, String) {
+    if let Some(quoted) = rest.strip_prefix('"') {
+        if let Some(end) = quoted.find("\" ").map(|i| i + 2) {
+            let (old, new) = rest.split_at(end);
+            return (
+                side_path(old, "a/").unwrap_or_default(),
+                side_path(new.trim_start(), "b/").unwrap_or_default(),
+            );
+        }
+    }
+    let half = rest.len().saturating_sub(1) / 2;
+    if rest.is_char_boundary(half) {
+        let (old, new) = rest.split_at(half);
+        if let (Some(old), Some(new)) = (old.strip_prefix("a/"), new.strip_prefix(" b/")) {
+            return (old.to_string(), unquote(new));
+        }
+    }
+    let (old, new) = rest.rsplit_once(" b/").unwrap_or((rest, rest));
+    (old.trim_start_matches("a/").to_string(), new.to_string())
+}
+
+/// Parse `@@ -a[,b] +c[,d] @@ header`.
+fn parse_hunk_header(line: &str) -> Option<DiffHunk> {
+    let rest = line.strip_prefix("@@ -")?;
+    let (ranges, header) = rest.split_once(" @@")?;
+    let (old, new) = ranges.split_once(" +")?;
+    let range = |r: &str| -> Option<(u32, u32)> {
+        match r.split_once(',') {
+            Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_lines) = range(old)?;
+    let (new_start, new_lines) = range(new)?;
+    Some(DiffHunk {
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        header: header.trim().to_string(),
+        lines: Vec::new(),
+    })
+}
+
+/// Parse `git diff` output into files, hunks and numbered lines.
+pub(crate) fn parse_diff(text: &str) -> Vec<DiffFile> {
+    let mut files: Vec<DiffFile> = Vec::new();
+    // Line counters for the hunk being read.
+    let (mut old_no, mut new_no) = (0, 0);
+
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            let (old, new) = header_paths(rest);
+            files.push(DiffFile {
+                old_path: Some(old),
+                new_path: Some(new),
+                change: DiffChange::Modified,
+                similarity: None,
+                old_mode: None,
+                new_mode: None,
+                binary: false,
+                added: 0,
+                removed: 0,
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+
+        if let Some(hunk) = file.hunks.last_mut() {
+            let (kind, content) = match line.as_bytes().first() {
+                Some(b'+') => (DiffLineKind::Added, &line[1..]),
+                Some(b'-') => (DiffLineKind::Removed, &line[1..]),
+                Some(b' ') => (DiffLineKind::Context, &line[1..]),
+                // An empty context line can lose its leading space.
+                None => (DiffLineKind::Context, ""),
+                Some(b'\\') => {
+                    if let Some(last) = hunk.lines.last_mut() {
+                        last.no_newline = true;
+                    }
+                    continue;
+                }
+                _ => {
+                    if let Some(next) = parse_hunk_header(line) {
+                        old_no = next.old_start;
+                        new_no = next.new_start;
+                        file.hunks.push(next);
+                    }
+                    continue;
+                }
+            };
+            let (old_line, new_line) = match kind {
+                DiffLineKind::Added => {
+                    file.added += 1;
+                    new_no += 1;
+                    (None, Some(new_no - 1))
+                }
+                DiffLineKind::Removed => {
+                    file.removed += 1;
+                    old_no += 1;
+                    (Some(old_no - 1), None)
+                }
+                DiffLineKind::Context => {
+                    old_no += 1;
+                    new_no += 1;
+                    (Some(old_no - 1), Some(new_no - 1))
+                }
+            };
+            hunk.lines.push(DiffLine {
+                kind,
+                old_line,
+                new_line,
+                content: content.to_string(),
+                no_newline: false,
+                segments: None,
+            });
+            continue;
+        }
+
+        if let Some(hunk) = parse_hunk_header(line) {
+            old_no = hunk.old_start;
+            new_no = hunk.new_start;
+            file.hunks.push(hunk);
+        } else if let Some(mode) = line.strip_prefix("new file mode ") {
+            file.change = DiffChange::Added;
+            file.old_path = None;
+            file.new_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("deleted file mode ") {
+            file.change = DiffChange::Deleted;
+            file.new_path = None;
+            file.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("old mode ") {
+            file.old_mode = Some(mode.to_string());
+        } else if let Some(mode) = line.strip_prefix("new mode ") {
+            file.new_mode = Some(mode.to_string());
+        } else if let Some(pct) = line.strip_prefix("similarity index ") {
+            file.similarity = pct.trim_end_matches('%').parse().ok();
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            file.change = DiffChange::Renamed;
+            file.old_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            file.new_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("copy from ") {
+            file.change = DiffChange::Copied;
+            file.old_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("copy to ") {
+            file.new_path = Some(unquote(path));
+        } else if let Some(path) = line.strip_prefix("--- ") {
+            if file.change != DiffChange::Added {
+                file.old_path = side_path(path, "a/");
+            }
+        } else if let Some(path) = line.strip_prefix("+++ ") {
+            if file.change != DiffChange::Deleted {
+                file.new_path = side_path(path, "b/");
+            }
+        } else if line.starts_with("Binary files ") {
+            file.binary = true;
+        }
+    }
+    files
+}
+
+/// Runs of word characters, runs of whitespace, and single punctuation
+/// characters.
+fn tokenize(line: &str) -> Vec<&str> {
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            0
+        } else if c.is_whitespace() {
+            1
+        } else {
+            2
+        }
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut prev: Option<u8> = None;
+    for (i, c) in line.char_indices() {
+        let k = class(c);
+        if i > start && (prev != Some(k) || k == 2) {
+            tokens.push(&line[start..i]);
+            start = i;
+        }
+        prev = Some(k);
+    }
+    if start < line.len() {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Segments for `a` and `b` around their common leading and trailing words,
+/// or None when they share nothing (the whole line is the change).
+fn word_segments(a: &str, b: &str) -> Option<(Vec<LineSegment>, Vec<LineSegment>)> {
+    let (ta, tb) = (tokenize(a), tokenize(b));
+    let prefix = ta.iter().zip(&tb).take_while(|(x, y)| x == y).count();
+    let suffix = ta[prefix..]
+        .iter()
+        .rev()
+        .zip(tb[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    if prefix + suffix == 0 {
+        return None;
+    }
+    let segments = |tokens: &[&str]| {
+        let parts = [
+            (tokens[..prefix].concat(), false),
+            (tokens[prefix..tokens.len() - suffix].concat(), true),
+            (tokens[tokens.len() - suffix..].concat(), false),
+        ];
+        parts
+            .into_iter()
+            .filter(|(text, _)| !text.is_empty())
+            .map(|(text, changed)| LineSegment { text, changed })
+            .collect()
+    };
+    Some((segments(&ta), segments(&tb)))
+}
+
+/// Pair each run of removed lines with the added lines that follow it, line
+/// by line, and mark the words that differ.
+fn add_word_segments(hunk: &mut DiffHunk) {
+    let lines = &mut hunk.lines;
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].kind != DiffLineKind::Removed {
+            i += 1;
+            continue;
+        }
+        let removed_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Removed {
+            i += 1;
+        }
+        let added_start = i;
+        while i < lines.len() && lines[i].kind == DiffLineKind::Added {
+            i += 1;
+        }
+        let pairs = (added_start - removed_start).min(i - added_start);
+        for k in 0..pairs {
+            let (old, new) = (removed_start + k, added_start + k);
+            if let Some((a, b)) = word_segments(&lines[old].content, &lines[new].content) {
+                lines[old].segments = Some(a);
+                lines[new].segments = Some(b);
+            }
+        }
+    }
+}
+
+/// Run `git <args>` and parse the diff, accepting `--no-index`'s exit status
+/// 1 ("files differ").
+pub(crate) fn run_diff(
+    repo: &Path,
+    args: &[String],
+    options: &DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    let output = run_git(repo, args)?;
+    let no_index = args.iter().any(|a| a == "--no-index");
+    if !(output.status.success() || (no_index && output.status.code() == Some(1))) {
+        return Err(git_failure("diff", &output));
+    }
+    let mut files = parse_diff(&String::from_utf8_lossy(&output.stdout));
+    if options.word_diff {
+        for hunk in files.iter_mut().flat_map(|f| f.hunks.iter_mut()) {
+            add_word_segments(hunk);
+        }
+    }
+    Ok(files)
+}
+
+/// An untracked file shown as added, via `git diff --no-index`.
+fn untracked_diff(repo: &Path, path: &str, options: &DiffOptions) -> Result<Vec<DiffFile>, String> {
+    let mut args = diff_args(options);
+    args.extend(["--no-index", "--", "/dev/null", path].map(String::from));
+    let mut files = run_diff(repo, &args, options)?;
+    for file in &mut files {
+        file.new_path = Some(path.to_string());
+    }
+    Ok(files)
+}
+
+/// Uncommitted changes (HEAD → working tree) as structured files, for one
+/// path (relative to `repo_path`) or the whole worktree. Untracked files are
+/// included as added.
+#[tauri::command]
+pub async fn get_structured_diff(
+    repo_path: String,
+    file_path: Option<String>,
+    options: DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let base = if has_head(repo) { "HEAD" } else { EMPTY_TREE };
+        let mut args = diff_args(&options);
+        args.extend([base.to_string(), "--".to_string()]);
+        args.extend(file_path.clone());
+        let mut files = run_diff(repo, &args, &options)?;
+
+        // --no-index wants paths relative to where git runs.
+        let status = git.status(repo)?.unwrap_or_default();
+        for (relative, full) in untracked_in(repo, &status)? {
+            let wanted = match &file_path {
+                Some(p) => relative == *p,
+                None => true,
+            };
+            if wanted && full.is_file() {
+                files.extend(untracked_diff(repo, &relative, &options)?);
+            }
+        }
+        Ok(files)
+    })
+    .await
+}
+
+/// A commit's changes against its first parent (or the empty tree for a
+/// root commit) as structured files. A `<a>..<b>` or `<a>...<b>` range
+/// diffs its two sides instead, as `git diff` does.
+#[tauri::command]
+pub async fn get_structured_commit_diff(
+    repo_path: String,
+    commit_ref: String,
+    options: DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    validate_rev_range(&commit_ref)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        if commit_ref.contains("..") {
+            let mut args = diff_args(&options);
+            args.extend([commit_ref, "--".to_string()]);
+            return run_diff(repo, &args, &options);
+        }
+        let parent = format!("{}^1", commit_ref);
+        let has_parent = run_git(repo, ["rev-parse", "--verify", "--quiet", &parent])?
+            .status
+            .success();
+        let mut args = diff_args(&options);
+        args.extend([
+            if has_parent {
+                parent
+            } else {
+                EMPTY_TREE.to_string()
+            },
+            commit_ref,
+            "--".to_string(),
+        ]);
+        run_diff(repo, &args, &options)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    const DIFF: &str = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,4 +1,4 @@ fn main() {
+ one
+-let total = a + b;
++let total = a - b;
+ three
+-four
+\\ No newline at end of file
++four
+diff --git a/old name.txt b/new name.txt
+similarity index 90%
+rename from old name.txt
+rename to new name.txt
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/img.png b/img.png
+new file mode 100644
+index 0000000..3333333
+Binary files /dev/null and b/img.png differ
+diff --git \"a/tab\\there\" \"b/tab\\there\"
+deleted file mode 100644
+--- \"a/tab\\there\"
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+";
+
+    #[test]
+    fn parses_files_hunks_and_metadata() {
+        let files = parse_diff(DIFF);
+        assert_eq!(files.len(), 5);
+
+        let lib = &files[0];
+        assert_eq!(lib.change, DiffChange::Modified);
+        assert_eq!((lib.added, lib.removed), (2, 2));
+        let hunk = &lib.hunks[0];
+        assert_eq!(
+            (
+                hunk.old_start,
+                hunk.old_lines,
+                hunk.new_start,
+                hunk.new_lines
+            ),
+            (1, 4, 1, 4)
+        );
+        assert_eq!(hunk.header, "fn main() {");
+        let numbers: Vec<_> = hunk
+            .lines
+            .iter()
+            .map(|l| (l.old_line, l.new_line))
+            .collect();
+        assert_eq!(
+            numbers,
+            vec![
+                (Some(1), Some(1)),
+                (Some(2), None),
+                (None, Some(2)),
+                (Some(3), Some(3)),
+                (Some(4), None),
+                (None, Some(4)),
+            ]
+        );
+        assert!(hunk.lines[4].no_newline && !hunk.lines[5].no_newline);
+
+        let renamed = &files[1];
+        assert_eq!(renamed.change, DiffChange::Renamed);
+        assert_eq!(renamed.old_path.as_deref(), Some("old name.txt"));
+        assert_eq!(renamed.new_path.as_deref(), Some("new name.txt"));
+        assert_eq!(renamed.similarity, Some(90));
+
+        assert_eq!(files[2].new_mode.as_deref(), Some("100755"));
+        assert_eq!(files[2].new_path.as_deref(), Some("run.sh"));
+        assert!(files[3].binary && files[3].old_path.is_none());
+        assert_eq!(files[3].change, DiffChange::Added);
+        assert_eq!(files[4].old_path.as_deref(), Some("tab\there"));
+        assert_eq!(files[4].change, DiffChange::Deleted);
+    }
+
+    #[test]
+    fn marks_changed_words() {
+        let mut files = parse_diff(DIFF);
+        add_word_segments(&mut files[0].hunks[0]);
+        let lines = &files[0].hunks[0].lines;
+        let changed = |l: &DiffLine| -> Vec<String> {
+            l.segments
+                .iter()
+                .flatten()
+                .filter(|s| s.changed)
+                .map(|s| s.text.clone())
+                .collect()
+        };
+        assert_eq!(changed(&lines[1]), vec!["+"]);
+        assert_eq!(changed(&lines[2]), vec!["-"]);
+        // Identical lines share everything: no changed segment.
+        assert_eq!(lines[4].segments.as_ref().map(Vec::len), Some(1));
+        assert!(lines[0].segments.is_none());
+    }
+
+    #[tokio::test]
+    async fn diffs_worktree_and_commits_with_options() {
+        let repo = test_repo("structured_diff");
+        let root = repo.to_string_lossy().to_string();
+        commit_file(&repo, "a.txt", "one\ntwo\n", "init");
+        fs::write(repo.join("a.txt"), "one  \ntwo\nthree\n").unwrap();
+        fs::write(repo.join("new.txt"), "fresh\n").unwrap();
+
+        let files = get_structured_diff(root.clone(), None, DiffOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!((files[0].added, files[0].removed), (2, 1));
+        assert_eq!(files[1].change, DiffChange::Added);
+        assert_eq!(files[1].new_path.as_deref(), Some("new.txt"));
+
+        let ignoring = DiffOptions {
+            whitespace: WhitespaceMode::IgnoreAll,
+            context_lines: Some(0),
+            ..Default::default()
+        };
+        let files = get_structured_diff(root.clone(), Some("a.txt".into()), ignoring)
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!((files[0].added, files[0].removed), (1, 0));
+        assert_eq!(files[0].hunks[0].lines.len(), 1);
+
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["mv", "new.txt", "renamed.txt"]);
+        git(&repo, &["commit", "-q", "-m", "second"]);
+        let files = get_structured_commit_diff(root.clone(), "HEAD".into(), DiffOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        let root_commit = get_structured_commit_diff(
+            root,
+            "HEAD~1".into(),
+            DiffOptions {
+                detect_renames: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(root_commit[0].change, DiffChange::Added);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,500 @@
+//! Fixes for what `diagnose_repo_health` finds: tracked build output, slow
+//! status on a big worktree, a bloated object store and index locks left
+//! behind by git processes that died.
+//!
+//! Each command takes `dry_run`, which reports what it would change without
+//! touching anything, so the UI can show the user before they commit to it.
+
+use super::types::{
+    ConfigChange, IndexLock, IndexLockReport, MaintenanceReport, ObjectStats, StatusCacheReport,
+    UntrackDirReport,
+};
+use super::{git_failure, query_backend, repo_dir, run_git, BACKEND_TIMEOUT};
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant, SystemTime};
+
+/// `git gc` on a large repo can take many minutes.
+const MAINTENANCE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// `git status` runs timed before and after enabling the caches; the fastest
+/// counts, so one cold run doesn't skew the comparison.
+const STATUS_SAMPLES: usize = 3;
+
+/// Those runs together, on a worktree big enough to need the caches.
+const STATUS_TIMING_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// `dir` as a plain relative path with no trailing slash, or an error.
+fn normalize_dir(dir: &str) -> Result<String, String> {
+    let trimmed = dir.trim_end_matches('/');
+    let path = Path::new(trimmed);
+    let plain = path.components().all(|c| matches!(c, Component::Normal(_)));
+    if trimmed.is_empty() || !plain {
+        return Err(format!("Invalid directory: {}", dir));
+    }
+    Ok(trimmed.to_string())
+}
+
+fn untrack_dir(repo: &Path, dir: &str, dry_run: bool) -> Result<UntrackDirReport, String> {
+    let dir = normalize_dir(dir)?;
+    let pathspec = format!("{}/", dir);
+    let tracked = run_git(
+        repo,
+        ["--literal-pathspecs", "ls-files", "-z", "--", &pathspec],
+    )?;
+    if !tracked.status.success() {
+        return Err(git_failure("list tracked files", &tracked));
+    }
+    let untracked_files = tracked
+        .stdout
+        .split(|b| *b == 0)
+        .filter(|p| !p.is_empty())
+        .count();
+    if untracked_files == 0 {
+        return Err(format!("No tracked files under {}", dir));
+    }
+    let ignored = run_git(repo, ["check-ignore", "--no-index", "-q", &pathspec])?
+        .status
+        .success();
+    let gitignore_entry = (!ignored).then(|| format!("/{}/", dir));
+
+    if !dry_run {
+        let output = run_git(
+            repo,
+            [
+                "--literal-pathspecs",
+                "rm",
+                "-r",
+                "-q",
+                "--cached",
+                "--",
+                &pathspec,
+            ],
+        )?;
+        if !output.status.success() {
+            return Err(git_failure("untrack the directory", &output));
+        }
+        if let Some(entry) = &gitignore_entry {
+            let path = repo.join(".gitignore");
+            let existing = fs::read(&path).unwrap_or_default();
+            let separator = if existing.is_empty() || existing.ends_with(b"\n") {
+                ""
+            } else {
+                "\n"
+            };
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .and_then(|mut file| writeln!(file, "{}{}", separator, entry))
+                .map_err(|e| format!("Failed to update .gitignore: {}", e))?;
+        }
+    }
+    Ok(UntrackDirReport {
+        dry_run,
+        dir,
+        untracked_files: untracked_files as u32,
+        gitignore_entry,
+    })
+}
+
+/// Stop tracking `dir` (relative to the repo) and add it to `.gitignore`.
+/// The files stay on disk; their removal from the index is staged for the
+/// user to commit.
+#[tauri::command]
+pub async fn untrack_directory(
+    repo_path: String,
+    dir: String,
+    dry_run: bool,
+) -> Result<UntrackDirReport, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        untrack_dir(repo, &dir, dry_run)
+    })
+    .await
+}
+
+/// The fastest of [`STATUS_SAMPLES`] `git status` runs, in milliseconds.
+fn time_status(repo: &Path) -> Result<u64, String> {
+    let mut fastest = u64::MAX;
+    for _ in 0..STATUS_SAMPLES {
+        let started = Instant::now();
+        let output = run_git(repo, ["status", "--porcelain"])?;
+        if !output.status.success() {
+            return Err(git_failure("run git status", &output));
+        }
+        fastest = fastest.min(started.elapsed().as_millis() as u64);
+    }
+    Ok(fastest)
+}
+
+fn config_value(repo: &Path, key: &str) -> Result<Option<String>, String> {
+    let output = run_git(repo, ["config", "--get", key])?;
+    Ok(output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string()))
+}
+
+fn is_true(value: &str) -> bool {
+    ["true", "yes", "on", "1"].contains(&value.to_ascii_lowercase().as_str())
+}
+
+fn is_false(value: &str) -> bool {
+    ["false", "no", "off", "0", ""].contains(&value.to_ascii_lowercase().as_str())
+}
+
+/// Whether this git has the fsmonitor daemon, and the cache settings that
+/// aren't on yet.
+fn plan_caches(repo: &Path) -> Result<(bool, Vec<ConfigChange>), String> {
+    let build = run_git(repo, ["version", "--build-options"])?;
+    let fsmonitor_supported = String::from_utf8_lossy(&build.stdout).contains("fsmonitor--daemon");
+
+    let mut keys = vec!["core.untrackedCache"];
+    if fsmonitor_supported {
+        keys.push("core.fsmonitor");
+    }
+    let mut changes = Vec::new();
+    for key in keys {
+        let from = config_value(repo, key)?;
+        // core.fsmonitor may name a hook (e.g. Watchman's); that's the
+        // user's setup, not something to overwrite.
+        let enabled = from.as_deref().is_some_and(|v| match key {
+            "core.fsmonitor" => !is_false(v),
+            _ => is_true(v),
+        });
+        if !enabled {
+            changes.push(ConfigChange {
+                key: key.to_string(),
+                from,
+                to: "true".to_string(),
+            });
+        }
+    }
+    Ok((fsmonitor_supported, changes))
+}
+
+fn apply_changes(repo: &Path, changes: &[ConfigChange]) -> Result<(), String> {
+    for change in changes {
+        let output = run_git(repo, ["config", &change.key, &change.to])?;
+        if !output.status.success() {
+            return Err(git_failure(&format!("set {}", change.key), &output));
+        }
+    }
+    Ok(())
+}
+
+/// Turn on `core.untrackedCache` and, where git supports it, the built-in
+/// `core.fsmonitor` daemon, timing `git status` before and after. The config
+/// is written in a call of its own, so a slow status can't keep it from
+/// landing; if timing afterwards fails, `after_ms` is left out.
+#[tauri::command]
+pub async fn enable_status_cache(
+    repo_path: String,
+    dry_run: bool,
+) -> Result<StatusCacheReport, String> {
+    let repo = repo_dir(&repo_path)?;
+    let (fsmonitor_supported, changes) =
+        query_backend(repo.clone(), BACKEND_TIMEOUT, |_, repo| plan_caches(repo)).await?;
+    let before_ms = query_backend(repo.clone(), STATUS_TIMING_TIMEOUT, |_, repo| {
+        time_status(repo)
+    })
+    .await?;
+    let mut report = StatusCacheReport {
+        dry_run,
+        fsmonitor_supported,
+        changes,
+        before_ms,
+        after_ms: None,
+    };
+    if dry_run {
+        return Ok(report);
+    }
+    let changes = report.changes.clone();
+    query_backend(repo.clone(), BACKEND_TIMEOUT, move |_, repo| {
+        apply_changes(repo, &changes)
+    })
+    .await?;
+    report.after_ms = query_backend(repo, STATUS_TIMING_TIMEOUT, |_, repo| {
+        // The first run after enabling fills the untracked cache and starts
+        // the fsmonitor daemon; it's the runs after that which get faster.
+        run_git(repo, ["status", "--porcelain"])?;
+        time_status(repo)
+    })
+    .await
+    .ok();
+    Ok(report)
+}
+
+/// Parse `git count-objects -v`.
+fn parse_count_objects(stdout: &str) -> ObjectStats {
+    let mut stats = ObjectStats::default();
+    for line in stdout.lines() {
+        let Some((key, value)) = line.split_once(": ") else {
+            continue;
+        };
+        let value = value.trim().parse().unwrap_or(0);
+        match key {
+            "count" => stats.loose_objects = value,
+            "size" => stats.loose_size_kb = value,
+            "packs" => stats.packs = value,
+            "size-pack" => stats.pack_size_kb = value,
+            _ => {}
+        }
+    }
+    stats
+}
+
+fn object_stats(repo: &Path) -> Result<ObjectStats, String> {
+    let output = run_git(repo, ["count-objects", "-v"])?;
+    if !output.status.success() {
+        return Err(git_failure("count objects", &output));
+    }
+    Ok(parse_count_objects(&String::from_utf8_lossy(
+        &output.stdout,
+    )))
+}
+
+/// `git maintenance run --task=gc`, or plain `git gc` on a git too old to
+/// have `maintenance` (before 2.29).
+fn maintenance_args(repo: &Path) -> Result<Vec<&'static str>, String> {
+    let probe = run_git(repo, ["maintenance", "-h"])?;
+    if String::from_utf8_lossy(&probe.stderr).contains("is not a git command") {
+        return Ok(vec!["gc", "--quiet"]);
+    }
+    Ok(vec!["maintenance", "run", "--task=gc", "--quiet"])
+}
+
+/// Repack and prune the object store, reporting its size before and after.
+#[tauri::command]
+pub async fn run_git_maintenance(
+    repo_path: String,
+    dry_run: bool,
+) -> Result<MaintenanceReport, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, MAINTENANCE_TIMEOUT, move |_, repo| {
+        let args = maintenance_args(repo)?;
+        let before = object_stats(repo)?;
+        let mut after = None;
+        if !dry_run {
+            let output = run_git(repo, &args)?;
+            if !output.status.success() {
+                return Err(git_failure("run maintenance", &output));
+            }
+            after = Some(object_stats(repo)?);
+        }
+        Ok(MaintenanceReport {
+            dry_run,
+            command: format!("git {}", args.join(" ")),
+            before,
+            after,
+        })
+    })
+    .await
+}
+
+/// Run `lsof -F` with `args`, returning each process it lists with the
+/// names (`n` fields) given for it.
+fn lsof(args: &[&str]) -> Result<Vec<(u32, Vec<String>)>, String> {
+    let output = Command::new("lsof")
+        .args(["-w", "-Fpn"])
+        .args(args)
+        .output()
+        .map_err(|e| format!("Couldn't check for running git processes: {}", e))?;
+    // lsof exits 1 when nothing matches, so only its output says anything.
+    let mut processes: Vec<(u32, Vec<String>)> = Vec::new();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(pid) = line.strip_prefix('p').and_then(|p| p.parse().ok()) {
+            processes.push((pid, Vec::new()));
+        } else if let (Some(name), Some((_, names))) =
+            (line.strip_prefix('n'), processes.last_mut())
+        {
+            names.push(name.to_string());
+        }
+    }
+    Ok(processes)
+}
+
+/// Index locks in the repo's git dir and each linked worktree's.
+fn find_index_locks(repo: &Path) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let output = run_git(repo, ["rev-parse", "--git-common-dir"])?;
+    if !output.status.success() {
+        return Err(git_failure("find the git dir", &output));
+    }
+    let common = repo.join(String::from_utf8_lossy(&output.stdout).trim());
+    let common = common.canonicalize().unwrap_or(common);
+    let mut locks = vec![common.join("index.lock")];
+    if let Ok(entries) = fs::read_dir(common.join("worktrees")) {
+        locks.extend(entries.flatten().map(|e| e.path().join("index.lock")));
+    }
+    locks.retain(|lock| lock.is_file());
+    Ok((common, locks))
+}
+
+fn clear_index_locks(
+    repo: &Path,
+    worktrees: &[PathBuf],
+    dry_run: bool,
+) -> Result<IndexLockReport, String> {
+    let (common, found) = find_index_locks(repo)?;
+    if found.is_empty() {
+        return Ok(IndexLockReport {
+            dry_run,
+            locks: Vec::new(),
+        });
+    }
+
+    // Which worktree a lock belongs to isn't worth working out: any git
+    // process in any of them, or in the git dir, keeps every lock.
+    let mut dirs = worktrees.to_vec();
+    dirs.push(common);
+    let busy: Vec<u32> = lsof(&["-a", "-c", "git", "-d", "cwd"])?
+        .into_iter()
+        .filter(|(_, cwd)| {
+            cwd.iter()
+                .any(|c| dirs.iter().any(|d| Path::new(c).starts_with(d)))
+        })
+        .map(|(pid, _)| pid)
+        .collect();
+
+    let mut locks = Vec::new();
+    for path in found {
+        let path_str = path.to_string_lossy().to_string();
+        let mut held_by = busy.clone();
+        held_by.extend(lsof(&["--", &path_str])?.into_iter().map(|(pid, _)| pid));
+        held_by.sort_unstable();
+        held_by.dedup();
+
+        let age_secs = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .map_or(0, |d| d.as_secs());
+        let removed = !dry_run && held_by.is_empty() && fs::remove_file(&path).is_ok();
+        locks.push(IndexLock {
+            path: path_str,
+            age_secs,
+            held_by,
+            removed,
+        });
+    }
+    Ok(IndexLockReport { dry_run, locks })
+}
+
+/// Delete `index.lock` files no git process could be holding: left behind
+/// by a git that crashed or was killed, they fail every later write with
+/// "index.lock exists".
+#[tauri::command]
+pub async fn remove_stale_index_locks(
+    repo_path: String,
+    dry_run: bool,
+) -> Result<IndexLockReport, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let worktrees: Vec<PathBuf> = git
+            .worktrees(repo)?
+            .iter()
+            .map(|w| {
+                Path::new(&w.path)
+                    .canonicalize()
+                    .unwrap_or_else(|_| w.path.clone().into())
+            })
+            .collect();
+        clear_index_locks(repo, &worktrees, dry_run)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+
+    #[test]
+    fn rejects_dirs_outside_the_repo() {
+        assert_eq!(normalize_dir("apps/web/dist/").unwrap(), "apps/web/dist");
+        for dir in ["", "/", "../x", "a/../b", "/abs", "./build"] {
+            assert!(normalize_dir(dir).is_err(), "{}", dir);
+        }
+    }
+
+    #[test]
+    fn parses_count_objects() {
+        let out = "count: 12\nsize: 48\nin-pack: 300\npacks: 2\nsize-pack: 1024\n";
+        assert_eq!(
+            parse_count_objects(out),
+            ObjectStats {
+                loose_objects: 12,
+                loose_size_kb: 48,
+                packs: 2,
+                pack_size_kb: 1024,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn untracks_dirs_and_clears_locks() {
+        let repo = test_repo("health");
+        fs::create_dir_all(repo.join("dist")).unwrap();
+        fs::write(repo.join("dist/a.js"), "a\n").unwrap();
+        fs::write(repo.join("dist/b.js"), "b\n").unwrap();
+        fs::write(repo.join(".gitignore"), "*.log").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        let repo_path = repo.to_string_lossy().to_string();
+
+        let plan = untrack_directory(repo_path.clone(), "dist/".into(), true)
+            .await
+            .unwrap();
+        assert_eq!(plan.untracked_files, 2);
+        assert_eq!(plan.gitignore_entry.as_deref(), Some("/dist/"));
+        assert_eq!(git(&repo, &["ls-files", "dist"]).lines().count(), 2);
+
+        untrack_directory(repo_path.clone(), "dist".into(), false)
+            .await
+            .unwrap();
+        assert_eq!(git(&repo, &["ls-files", "dist"]), "");
+        assert!(repo.join("dist/a.js").exists());
+        let gitignore = fs::read_to_string(repo.join(".gitignore")).unwrap();
+        assert_eq!(gitignore, "*.log\n/dist/\n");
+        let err = untrack_directory(repo_path.clone(), "dist".into(), false)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "No tracked files under dist");
+
+        commit_file(&repo, "c.txt", "c\n", "more");
+        let lock = repo.join(".git/index.lock");
+        fs::write(&lock, "").unwrap();
+        let plan = remove_stale_index_locks(repo_path.clone(), true)
+            .await
+            .unwrap();
+        assert_eq!(plan.locks.len(), 1);
+        assert!(plan.locks[0].held_by.is_empty() && !plan.locks[0].removed);
+        assert!(lock.exists());
+        let report = remove_stale_index_locks(repo_path.clone(), false)
+            .await
+            .unwrap();
+        assert!(report.locks[0].removed);
+        assert!(!lock.exists());
+
+        let report = run_git_maintenance(repo_path.clone(), false).await.unwrap();
+        assert_eq!(report.after.map(|s| s.loose_objects), Some(0));
+
+        let report = enable_status_cache(repo_path.clone(), true).await.unwrap();
+        assert!(report.after_ms.is_none());
+        assert!(report
+            .changes
+            .iter()
+            .any(|c| c.key == "core.untrackedCache" && c.from.is_none()));
+        enable_status_cache(repo_path.clone(), false).await.unwrap();
+        assert_eq!(
+            git(&repo, &["config", "core.untrackedCache"]).trim(),
+            "true"
+        );
+        let report = enable_status_cache(repo_path, true).await.unwrap();
+        assert!(report.changes.is_empty());
+        let _ = fs::remove_dir_all(repo);
+    }
+}
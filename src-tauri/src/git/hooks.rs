@@ -0,0 +1,469 @@
+//! Which git hooks a repo has, what installed them, and running one on
+//! demand.
+//!
+//! Hooks live in `.git/hooks` unless `core.hooksPath` points elsewhere
+//! (husky does this), and are often generated by a framework from a config
+//! file in the worktree. Running one here means running it the way
+//! `git commit` would — from the worktree root, against whatever is staged —
+//! without making a commit.
+
+use super::remote::kill_operation;
+use super::types::{
+    GitHook, HookEvent, HookFramework, HookRunResult, HooksOverview, RunHookOptions,
+};
+use super::worktrees::same_path;
+use super::{git_failure, query_backend, repo_dir, run_git, BACKEND_TIMEOUT};
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+/// Client-side hooks git knows about (githooks(5)).
+const GIT_HOOKS: [&str; 21] = [
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-auto-gc",
+    "post-rewrite",
+    "sendemail-validate",
+    "fsmonitor-watchman",
+    "p4-changelist",
+    "p4-prepare-changelist",
+    "p4-post-changelist",
+    "p4-pre-submit",
+    "post-index-change",
+];
+
+/// Hooks that can run outside the operation that normally triggers them:
+/// they check the index, or a commit message we can supply.
+const RUNNABLE_HOOKS: [&str; 4] = [
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+];
+
+/// Hook frameworks: name, config files (the first found counts), and a
+/// string their generated hook scripts contain.
+const FRAMEWORKS: [(&str, &[&str], &str); 3] = [
+    (
+        "pre-commit",
+        &[".pre-commit-config.yaml", ".pre-commit-config.yml"],
+        "pre-commit.com",
+    ),
+    ("husky", &[".husky", "package.json"], "husky"),
+    (
+        "lefthook",
+        &[
+            "lefthook.yml",
+            ".lefthook.yml",
+            "lefthook.yaml",
+            ".lefthook.yaml",
+            "lefthook.toml",
+            "lefthook.json",
+        ],
+        "lefthook",
+    ),
+];
+
+/// Bytes of a hook script searched for a framework's marker.
+const MARKER_SCAN_BYTES: u64 = 8192;
+
+/// Kill a hook that's still running after this long.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(600);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The directory git runs hooks from, honouring `core.hooksPath`.
+pub(crate) fn hooks_dir(repo: &Path) -> Result<PathBuf, String> {
+    let output = run_git(repo, ["rev-parse", "--git-path", "hooks"])?;
+    if !output.status.success() {
+        return Err(git_failure("find the hooks directory", &output));
+    }
+    Ok(repo.join(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+#[cfg(unix)]
+pub(crate) fn is_executable_file(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+pub(crate) fn is_executable_file(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// The framework whose generated script `path` is, going by its location
+/// (husky's own hooks dir) or a marker near the top of the file.
+fn hook_framework(path: &Path) -> Option<String> {
+    if path.components().any(|c| c.as_os_str() == ".husky") {
+        return Some("husky".to_string());
+    }
+    let mut head = Vec::new();
+    fs::File::open(path)
+        .ok()?
+        .take(MARKER_SCAN_BYTES)
+        .read_to_end(&mut head)
+        .ok()?;
+    let head = String::from_utf8_lossy(&head);
+    FRAMEWORKS
+        .iter()
+        .find(|(_, _, marker)| head.contains(marker))
+        .map(|(name, _, _)| name.to_string())
+}
+
+/// Executable hooks in `dir`, sample scripts and unknown names skipped.
+fn hooks_in(dir: &Path, active: bool) -> Vec<GitHook> {
+    GIT_HOOKS
+        .iter()
+        .map(|name| dir.join(name))
+        .filter(|path| is_executable_file(path))
+        .map(|path| GitHook {
+            name: path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            framework: hook_framework(&path),
+            path: path.to_string_lossy().to_string(),
+            active,
+        })
+        .collect()
+}
+
+/// A config file of `framework` in `root`. A `package.json` only counts
+/// with a top-level key named after the framework (husky before v5).
+fn framework_config(root: &Path, framework: &str, files: &[&str]) -> Option<String> {
+    files
+        .iter()
+        .find(|file| {
+            let path = root.join(file);
+            if **file != "package.json" {
+                return path.exists();
+            }
+            fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .is_some_and(|json| json.get(framework).is_some())
+        })
+        .map(|file| file.to_string())
+}
+
+fn worktree_root(repo: &Path) -> Result<PathBuf, String> {
+    let output = run_git(repo, ["rev-parse", "--show-toplevel"])?;
+    if !output.status.success() {
+        return Err(git_failure("find the worktree root", &output));
+    }
+    Ok(PathBuf::from(
+        String::from_utf8_lossy(&output.stdout).trim(),
+    ))
+}
+
+fn overview(repo: &Path) -> Result<HooksOverview, String> {
+    let root = worktree_root(repo)?;
+    let hooks_path = run_git(repo, ["config", "--get", "core.hooksPath"])?;
+    let hooks_path = hooks_path.status.success().then(|| {
+        String::from_utf8_lossy(&hooks_path.stdout)
+            .trim()
+            .to_string()
+    });
+
+    let active_dir = hooks_dir(repo)?;
+    let mut hooks = hooks_in(&active_dir, true);
+    // With core.hooksPath set, anything left in .git/hooks is dead.
+    let default_dir = repo
+        .join(
+            String::from_utf8_lossy(&run_git(repo, ["rev-parse", "--git-common-dir"])?.stdout)
+                .trim(),
+        )
+        .join("hooks");
+    if !same_path(&default_dir.to_string_lossy(), &active_dir) {
+        hooks.extend(hooks_in(&default_dir, false));
+    }
+
+    let frameworks = FRAMEWORKS
+        .iter()
+        .filter_map(|(name, files, _)| {
+            let config_path = framework_config(&root, name, files)?;
+            let installed = hooks
+                .iter()
+                .any(|h| h.active && h.framework.as_deref() == Some(*name));
+            Some(HookFramework {
+                name: name.to_string(),
+                config_path,
+                installed,
+            })
+        })
+        .collect();
+
+    Ok(HooksOverview {
+        hooks_dir: active_dir.to_string_lossy().to_string(),
+        hooks_path,
+        hooks,
+        frameworks,
+    })
+}
+
+/// The repo's hooks — those git runs, and any left inactive by
+/// `core.hooksPath` — plus the hook frameworks its config files set up.
+#[tauri::command]
+pub async fn list_git_hooks(repo_path: String) -> Result<HooksOverview, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |_, repo| overview(repo)).await
+}
+
+/// Run `hook` from `repo`'s worktree root, streaming its output through
+/// `emit`, until it exits or its timeout passes.
+fn run_hook(
+    repo: &Path,
+    hook: &str,
+    options: &RunHookOptions,
+    emit: &(dyn Fn(HookEvent) + Send + Sync),
+) -> Result<HookRunResult, String> {
+    if !RUNNABLE_HOOKS.contains(&hook) {
+        return Err(format!("The {} hook can't be run on demand", hook));
+    }
+    let path = hooks_dir(repo)?.join(hook);
+    if !is_executable_file(&path) {
+        return Err(format!("No {} hook is installed", hook));
+    }
+    let root = worktree_root(repo)?;
+
+    // The message hooks take a file to read and, possibly, rewrite.
+    let message_file = matches!(hook, "prepare-commit-msg" | "commit-msg").then(|| {
+        std::env::temp_dir().join(format!("codezilla-commit-msg-{}", uuid::Uuid::new_v4()))
+    });
+    let mut command = Command::new(&path);
+    if let Some(file) = &message_file {
+        fs::write(file, options.message.as_deref().unwrap_or_default())
+            .map_err(|e| format!("Failed to write the commit message: {}", e))?;
+        command.arg(file);
+        if hook == "prepare-commit-msg" {
+            command.arg("message");
+        }
+    }
+    command
+        .current_dir(&root)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+
+    let started = Instant::now();
+    let result = command
+        .spawn()
+        .map_err(|e| format!("Failed to run the {} hook: {}", hook, e))
+        .and_then(|mut child| {
+            let stdout = child.stdout.take();
+            let stderr = child.stderr.take();
+            let timeout = options
+                .timeout_secs
+                .map_or(HOOK_TIMEOUT, Duration::from_secs);
+            std::thread::scope(|scope| {
+                scope.spawn(|| stream_lines(stdout, &|line| emit(HookEvent::Stdout(line))));
+                scope.spawn(|| stream_lines(stderr, &|line| emit(HookEvent::Stderr(line))));
+                let deadline = Instant::now() + timeout;
+                loop {
+                    match child.try_wait() {
+                        Ok(Some(status)) => break Ok(status),
+                        Ok(None) if Instant::now() >= deadline => {
+                            kill_operation(&mut child);
+                            let _ = child.wait();
+                            break Err(format!(
+                                "The {} hook timed out after {}s",
+                                hook,
+                                timeout.as_secs()
+                            ));
+                        }
+                        Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                        Err(e) => break Err(format!("Failed to run the {} hook: {}", hook, e)),
+                    }
+                }
+            })
+        });
+    let message = message_file
+        .as_ref()
+        .and_then(|f| fs::read_to_string(f).ok());
+    if let Some(file) = &message_file {
+        let _ = fs::remove_file(file);
+    }
+    let status = result?;
+
+    emit(HookEvent::Exited {
+        code: status.code(),
+    });
+    Ok(HookRunResult {
+        hook: hook.to_string(),
+        success: status.success(),
+        code: status.code(),
+        message,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// Emit each line read from `pipe` until it closes.
+fn stream_lines(pipe: Option<impl Read>, emit: &dyn Fn(String)) {
+    let Some(pipe) = pipe else {
+        return;
+    };
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    while reader
+        .read_until(b'\n', &mut line)
+        .map(|n| n > 0)
+        .unwrap_or(false)
+    {
+        emit(String::from_utf8_lossy(&line).trim_end().to_string());
+        line.clear();
+    }
+}
+
+/// Run a commit hook (`pre-commit`, `pre-merge-commit`, `prepare-commit-msg`
+/// or `commit-msg`) against the staged changes without committing. A hook
+/// that fails isn't an error: the result says how it exited.
+#[tauri::command]
+pub async fn run_git_hook(
+    repo_path: String,
+    hook: String,
+    options: RunHookOptions,
+    channel: Channel<HookEvent>,
+) -> Result<HookRunResult, String> {
+    let repo = repo_dir(&repo_path)?;
+    // The hook enforces its own deadline; this only has to outlast it.
+    let limit = options
+        .timeout_secs
+        .map_or(HOOK_TIMEOUT, Duration::from_secs)
+        + BACKEND_TIMEOUT;
+    query_backend(repo, limit, move |_, repo| {
+        run_hook(repo, &hook, &options, &|event| {
+            let _ = channel.send(event);
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::Mutex;
+
+    fn install(dir: &Path, name: &str, script: &str) {
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    #[tokio::test]
+    async fn lists_and_runs_hooks() {
+        let repo = test_repo("hooks");
+        commit_file(&repo, "a.txt", "a\n", "init");
+        let repo_path = repo.to_string_lossy().to_string();
+        install(
+            &repo.join(".git/hooks"),
+            "pre-commit",
+            "#!/bin/sh\n# File generated by pre-commit: https://pre-commit.com\n\
+             git diff --cached --name-only\necho checked >&2\n",
+        );
+        install(
+            &repo.join(".git/hooks"),
+            "commit-msg",
+            "#!/bin/sh\necho 'Signed-off-by: Test' >> \"$1\"\nexit 3\n",
+        );
+        fs::write(repo.join(".pre-commit-config.yaml"), "repos: []\n").unwrap();
+        fs::write(repo.join("package.json"), r#"{"name": "x"}"#).unwrap();
+
+        let overview = list_git_hooks(repo_path.clone()).await.unwrap();
+        let names: Vec<_> = overview.hooks.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(names, ["pre-commit", "commit-msg"]);
+        assert_eq!(overview.hooks[0].framework.as_deref(), Some("pre-commit"));
+        assert_eq!(overview.frameworks.len(), 1);
+        assert!(overview.frameworks[0].installed);
+
+        fs::write(repo.join("b.txt"), "b\n").unwrap();
+        git(&repo, &["add", "b.txt"]);
+        let events = Mutex::new(Vec::new());
+        let result = run_hook(&repo, "pre-commit", &RunHookOptions::default(), &|e| {
+            events.lock().unwrap().push(e)
+        })
+        .unwrap();
+        assert!(result.success);
+        let mut events = events.into_inner().unwrap();
+        assert_eq!(events.pop(), Some(HookEvent::Exited { code: Some(0) }));
+        events.sort_by_key(|e| format!("{:?}", e));
+        assert_eq!(
+            events,
+            [
+                HookEvent::Stderr("checked".into()),
+                HookEvent::Stdout("b.txt".into())
+            ]
+        );
+
+        let options = RunHookOptions {
+            message: Some("Fix it\n".into()),
+            ..Default::default()
+        };
+        let result = run_hook(&repo, "commit-msg", &options, &|_| {}).unwrap();
+        assert_eq!((result.success, result.code), (false, Some(3)));
+        assert_eq!(
+            result.message.as_deref(),
+            Some("Fix it\nSigned-off-by: Test\n")
+        );
+
+        // core.hooksPath takes over; .git/hooks stops running.
+        install(
+            &repo.join(".husky/_"),
+            "pre-commit",
+            "#!/bin/sh\n. \"${0%/*}/h\"\n",
+        );
+        git(&repo, &["config", "core.hooksPath", ".husky/_"]);
+        let overview = list_git_hooks(repo_path.clone()).await.unwrap();
+        let active: Vec<_> = overview
+            .hooks
+            .iter()
+            .map(|h| (h.name.as_str(), h.active, h.framework.as_deref()))
+            .collect();
+        assert_eq!(
+            active,
+            [
+                ("pre-commit", true, Some("husky")),
+                ("pre-commit", false, Some("pre-commit")),
+                ("commit-msg", false, None),
+            ]
+        );
+        let frameworks: Vec<_> = overview
+            .frameworks
+            .iter()
+            .map(|f| (f.name.as_str(), f.config_path.as_str(), f.installed))
+            .collect();
+        assert_eq!(
+            frameworks,
+            [
+                ("pre-commit", ".pre-commit-config.yaml", false),
+                ("husky", ".husky", true),
+            ]
+        );
+
+        let err = run_hook(&repo, "pre-push", &RunHookOptions::default(), &|_| {}).unwrap_err();
+        assert_eq!(err, "The pre-push hook can't be run on demand");
+        let err = run_hook(&repo, "commit-msg", &RunHookOptions::default(), &|_| {}).unwrap_err();
+        assert_eq!(err, "No commit-msg hook is installed");
+        let _ = fs::remove_dir_all(repo);
+    }
+}
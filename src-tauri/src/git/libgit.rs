@@ -0,0 +1,572 @@
+//! In-process [`GitBackend`] on libgit2. Queries run without spawning a
+//! process, which matters when several projects are polled at once.
+//!
+//! Output mirrors the CLI backend: status and numstat paths are relative to
+//! the worktree root, pathspecs to the queried directory, and renames aren't
+//! detected in diffs (the CLI side passes `--no-renames`). Merge commits are
+//! shown against their first parent rather than as a combined diff.
+
+use super::backend::GitBackend;
+use super::types::{
+    BranchStatus, CommitFileStat, CommitInfo, CommitSummary, FileDiffStat, GitStatus, LogQuery,
+    SubmoduleState, WorktreeInfo,
+};
+use super::{classify_worktree, status_entry};
+use git2::{
+    Branch, Commit, Diff, DiffFormat, DiffOptions, ErrorCode, Patch, Repository, Revwalk, Sort,
+    Status, StatusOptions, SubmoduleIgnore, SubmoduleStatus, WorktreeLockStatus,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub(crate) struct LibgitBackend;
+
+fn err(e: git2::Error) -> String {
+    e.message().to_string()
+}
+
+/// Where `dir` sits inside the repo's worktree, e.g. `packages/app` when a
+/// project is opened below the repo root. Empty at the root.
+fn worktree_prefix(repo: &Repository, dir: &Path) -> PathBuf {
+    let Some(workdir) = repo.workdir() else {
+        return PathBuf::new();
+    };
+    let workdir = workdir.canonicalize().unwrap_or_else(|_| workdir.to_path_buf());
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    dir.strip_prefix(&workdir).map(Path::to_path_buf).unwrap_or_default()
+}
+
+fn path_str(path: Option<&Path>) -> String {
+    path.map(|p| p.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Porcelain `XY` for an unmerged path, from which stages are present.
+fn conflict_xy(ancestor: bool, ours: bool, theirs: bool) -> [u8; 2] {
+    match (ancestor, ours, theirs) {
+        (false, true, true) => *b"AA",
+        (true, true, false) => *b"UD",
+        (true, false, true) => *b"DU",
+        (false, true, false) => *b"AU",
+        (false, false, true) => *b"UA",
+        (true, false, false) => *b"DD",
+        _ => *b"UU",
+    }
+}
+
+fn index_char(status: Status) -> u8 {
+    if status.is_index_new() {
+        b'A'
+    } else if status.is_index_modified() {
+        b'M'
+    } else if status.is_index_deleted() {
+        b'D'
+    } else if status.is_index_renamed() {
+        b'R'
+    } else if status.is_index_typechange() {
+        b'T'
+    } else {
+        b'.'
+    }
+}
+
+fn worktree_char(status: Status) -> u8 {
+    if status.is_wt_modified() {
+        b'M'
+    } else if status.is_wt_deleted() {
+        b'D'
+    } else if status.is_wt_typechange() {
+        b'T'
+    } else if status.is_wt_renamed() {
+        b'R'
+    } else {
+        b'.'
+    }
+}
+
+fn branch_status(repo: &Repository) -> BranchStatus {
+    let mut branch = BranchStatus::default();
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(e) if e.code() == ErrorCode::UnbornBranch => {
+            // No commits yet: HEAD still names the branch it will create.
+            branch.head = repo
+                .find_reference("HEAD")
+                .ok()
+                .and_then(|r| r.symbolic_target().map(str::to_string))
+                .map(|t| t.strip_prefix("refs/heads/").unwrap_or(&t).to_string());
+            return branch;
+        }
+        Err(_) => return branch,
+    };
+    branch.oid = head.target().map(|oid| oid.to_string());
+    if !head.is_branch() {
+        return branch;
+    }
+    branch.head = head.shorthand().map(str::to_string);
+    let local = head.target();
+    if let Ok(upstream) = Branch::wrap(head).upstream() {
+        branch.upstream = upstream.name().ok().flatten().map(str::to_string);
+        if let (Some(local), Some(remote)) = (local, upstream.get().target()) {
+            if let Ok((ahead, behind)) = repo.graph_ahead_behind(local, remote) {
+                branch.ahead = ahead as u32;
+                branch.behind = behind as u32;
+            }
+        }
+    }
+    branch
+}
+
+fn submodule_state(repo: &Repository, name: &str) -> Option<SubmoduleState> {
+    let status = repo.submodule_status(name, SubmoduleIgnore::None).ok()?;
+    Some(SubmoduleState {
+        commit_changed: status.contains(SubmoduleStatus::WD_MODIFIED),
+        modified: status
+            .intersects(SubmoduleStatus::WD_INDEX_MODIFIED | SubmoduleStatus::WD_WD_MODIFIED),
+        untracked: status.contains(SubmoduleStatus::WD_UNTRACKED),
+    })
+}
+
+/// HEAD's tree, or None before the first commit.
+fn head_tree(repo: &Repository) -> Result<Option<git2::Tree<'_>>, String> {
+    match repo.head() {
+        Ok(head) => head.peel_to_tree().map(Some).map_err(err),
+        Err(e) if e.code() == ErrorCode::UnbornBranch => Ok(None),
+        Err(e) => Err(err(e)),
+    }
+}
+
+fn diff_options(pathspec: Option<&Path>) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    if let Some(path) = pathspec {
+        opts.pathspec(path).disable_pathspec_match(true);
+    }
+    opts
+}
+
+/// Per-file line counts of `diff`, keyed by the new path (old for deletions).
+fn diff_numstat(diff: &Diff) -> Result<Vec<FileDiffStat>, String> {
+    let mut stats = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let (added, removed) = match Patch::from_diff(diff, idx).map_err(err)? {
+            Some(patch) => {
+                let (_, added, removed) = patch.line_stats().map_err(err)?;
+                (added as u32, removed as u32)
+            }
+            None => (0, 0),
+        };
+        let delta = diff.get_delta(idx).expect("delta index in range");
+        let path = delta.new_file().path().or_else(|| delta.old_file().path());
+        stats.push(FileDiffStat {
+            path: path_str(path),
+            added,
+            removed,
+        });
+    }
+    Ok(stats)
+}
+
+/// Render `diff` as a unified patch, like `git diff` prints it.
+fn patch_text(diff: &Diff) -> Result<String, String> {
+    let mut out = Vec::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            out.push(line.origin() as u8);
+        }
+        out.extend_from_slice(line.content());
+        true
+    })
+    .map_err(err)?;
+    Ok(String::from_utf8_lossy(&out).to_string())
+}
+
+fn commit_diff<'r>(repo: &'r Repository, commit: &Commit) -> Result<Diff<'r>, String> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(err)?),
+        Err(_) => None,
+    };
+    let tree = commit.tree().map_err(err)?;
+    repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .map_err(err)
+}
+
+fn find_commit<'r>(repo: &'r Repository, rev: &str) -> Result<Commit<'r>, String> {
+    repo.revparse_single(rev)
+        .and_then(|o| o.peel_to_commit())
+        .map_err(|e| format!("Not a valid commit: {}", e.message()))
+}
+
+/// Days since 1970-01-01 → (year, month, day), proleptic Gregorian.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Strict ISO 8601 in the signature's own offset, as `%aI` prints it.
+pub(crate) fn iso8601(time: git2::Time) -> String {
+    let offset = i64::from(time.offset_minutes());
+    let local = time.seconds() + offset * 60;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let secs = local.rem_euclid(86_400);
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        sign,
+        offset.abs() / 60,
+        offset.abs() % 60
+    )
+}
+
+/// Split a commit message the way `%s` / `%b` do: the subject is the first
+/// paragraph joined onto one line, the body everything after it.
+pub(crate) fn split_message(message: &str) -> (String, String) {
+    let message = message.trim_start_matches('\n');
+    let (first, rest) = message.split_once("\n\n").unwrap_or((message, ""));
+    let subject = first
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    (subject, rest.trim_start_matches('\n').trim_end().to_string())
+}
+
+/// Whether a log query can be answered by a plain revision walk: path
+/// filtering needs git's history simplification and `a...b` a merge-base
+/// computation, so those go to the CLI.
+pub(crate) fn can_walk(query: &LogQuery) -> bool {
+    query.path.is_none() && !query.range.as_deref().unwrap_or("").contains("...")
+}
+
+/// Seed `walk` from `range` (`<rev>` or `<a>..<b>`, an empty side meaning
+/// HEAD). False when the range is HEAD and there are no commits yet.
+fn push_range(repo: &Repository, walk: &mut Revwalk, range: Option<&str>) -> Result<bool, String> {
+    let resolve = |rev: &str| {
+        let rev = if rev.is_empty() { "HEAD" } else { rev };
+        repo.revparse_single(rev)
+            .and_then(|o| o.peel_to_commit())
+            .map(|c| c.id())
+            .map_err(err)
+    };
+    match range {
+        None => {
+            if head_tree(repo)?.is_none() {
+                return Ok(false);
+            }
+            walk.push_head().map_err(err)?;
+            Ok(true)
+        }
+        Some(range) => {
+            if let Some((from, to)) = range.split_once("..") {
+                walk.hide(resolve(from)?).map_err(err)?;
+                walk.push(resolve(to)?).map_err(err)?;
+            } else {
+                walk.push(resolve(range)?).map_err(err)?;
+            }
+            Ok(true)
+        }
+    }
+}
+
+fn worktree_record(repo: &Repository, path: String, is_main: bool) -> WorktreeInfo {
+    let (head, branch) = match repo.head() {
+        Ok(head) => (
+            head.target().map(|o| o.to_string()).unwrap_or_default(),
+            if head.is_branch() {
+                head.shorthand().map(str::to_string)
+            } else {
+                None
+            },
+        ),
+        Err(_) => (
+            git2::Oid::zero().to_string(),
+            repo.find_reference("HEAD")
+                .ok()
+                .and_then(|r| r.symbolic_target().map(str::to_string))
+                .map(|t| t.strip_prefix("refs/heads/").unwrap_or(&t).to_string()),
+        ),
+    };
+    WorktreeInfo {
+        source: classify_worktree(&path, is_main),
+        detached: repo.head_detached().unwrap_or(false),
+        path,
+        branch,
+        head,
+        locked: false,
+        lock_reason: None,
+        prunable: false,
+        dirty: false,
+    }
+}
+
+fn trim_dir(path: &Path) -> String {
+    let s = path.to_string_lossy();
+    match s.strip_suffix('/') {
+        Some(trimmed) if !trimmed.is_empty() => trimmed.to_string(),
+        _ => s.to_string(),
+    }
+}
+
+impl GitBackend for LibgitBackend {
+    fn name(&self) -> &'static str {
+        "libgit2"
+    }
+
+    fn status(&self, dir: &Path) -> Result<Option<GitStatus>, String> {
+        let repo = match Repository::discover(dir) {
+            Ok(repo) if !repo.is_bare() => repo,
+            Ok(_) => return Ok(None),
+            Err(e) if e.code() == ErrorCode::NotFound => return Ok(None),
+            Err(e) => return Err(err(e)),
+        };
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .include_ignored(false)
+            .renames_head_to_index(true)
+            .exclude_submodules(false);
+        let statuses = repo.statuses(Some(&mut opts)).map_err(err)?;
+
+        let index = repo.index().map_err(err)?;
+        let mut conflicts: HashMap<String, [u8; 2]> = HashMap::new();
+        if index.has_conflicts() {
+            for conflict in index.conflicts().map_err(err)?.flatten() {
+                let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+                    .into_iter()
+                    .flatten()
+                    .next()
+                    .map(|e| String::from_utf8_lossy(&e.path).to_string());
+                if let Some(path) = path {
+                    let xy = conflict_xy(
+                        conflict.ancestor.is_some(),
+                        conflict.our.is_some(),
+                        conflict.their.is_some(),
+                    );
+                    conflicts.insert(path, xy);
+                }
+            }
+        }
+        let submodules: HashMap<String, String> = repo
+            .submodules()
+            .map(|subs| {
+                subs.iter()
+                    .filter_map(|s| Some((path_str(Some(s.path())), s.name()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut status = GitStatus {
+            branch: branch_status(&repo),
+            entries: Vec::new(),
+        };
+        for entry in statuses.iter() {
+            let flags = entry.status();
+            if flags.is_ignored() {
+                continue;
+            }
+            let (path, orig_path) = match entry.head_to_index() {
+                Some(delta) if flags.is_index_renamed() => (
+                    path_str(delta.new_file().path()),
+                    Some(path_str(delta.old_file().path())),
+                ),
+                _ => (entry.path().unwrap_or_default().to_string(), None),
+            };
+            let xy = if flags.is_conflicted() {
+                conflicts.get(&path).copied().unwrap_or(*b"UU")
+            } else if flags.is_wt_new() && index_char(flags) == b'.' {
+                *b"??"
+            } else {
+                [index_char(flags), worktree_char(flags)]
+            };
+            let submodule = submodules.get(&path).and_then(|name| submodule_state(&repo, name));
+            status.entries.extend(status_entry(xy, path, orig_path, submodule));
+        }
+        Ok(Some(status))
+    }
+
+    fn numstat(&self, dir: &Path, path: Option<&str>) -> Result<Vec<FileDiffStat>, String> {
+        let repo = Repository::discover(dir).map_err(err)?;
+        // `git diff HEAD` fails without a HEAD; match that.
+        let tree = head_tree(&repo)?.ok_or_else(|| "HEAD has no commits yet".to_string())?;
+        let pathspec = path.map(|p| worktree_prefix(&repo, dir).join(p));
+        let mut opts = diff_options(pathspec.as_deref());
+        let diff = repo
+            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut opts))
+            .map_err(err)?;
+        diff_numstat(&diff)
+    }
+
+    fn diff(&self, dir: &Path, path: &str) -> Result<String, String> {
+        let repo = Repository::discover(dir).map_err(err)?;
+        let pathspec = worktree_prefix(&repo, dir).join(path);
+        let tree = head_tree(&repo)?;
+        let mut opts = diff_options(Some(&pathspec));
+        let diff = repo
+            .diff_tree_to_workdir_with_index(tree.as_ref(), Some(&mut opts))
+            .map_err(err)?;
+        let text = patch_text(&diff)?;
+        if !text.is_empty() {
+            return Ok(text);
+        }
+
+        // Untracked: show the whole file as added.
+        let mut opts = diff_options(Some(&pathspec));
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        let diff = repo
+            .diff_index_to_workdir(None, Some(&mut opts))
+            .map_err(err)?;
+        patch_text(&diff)
+    }
+
+    fn worktrees(&self, dir: &Path) -> Result<Vec<WorktreeInfo>, String> {
+        let repo = match Repository::discover(dir) {
+            Ok(repo) => repo,
+            Err(_) => return Ok(vec![]),
+        };
+        // The main worktree owns the common dir; a linked one only points at it.
+        let main = Repository::open(repo.commondir()).map_err(err)?;
+        let main_path = match main.workdir() {
+            Some(workdir) => trim_dir(workdir),
+            None => trim_dir(main.path()),
+        };
+        let mut out = vec![if main.is_bare() {
+            WorktreeInfo {
+                source: classify_worktree(&main_path, true),
+                path: main_path,
+                branch: None,
+                detached: false,
+                head: String::new(),
+                locked: false,
+                lock_reason: None,
+                prunable: false,
+                dirty: false,
+            }
+        } else {
+            worktree_record(&main, main_path, true)
+        }];
+
+        let mut linked = Vec::new();
+        for name in main.worktrees().map_err(err)?.iter().flatten() {
+            let worktree = main.find_worktree(name).map_err(err)?;
+            // A missing (prunable) worktree can't be opened; the CLI backend
+            // still reports it, so defer to it.
+            let wt_repo = Repository::open_from_worktree(&worktree).map_err(err)?;
+            let mut record = worktree_record(&wt_repo, trim_dir(worktree.path()), false);
+            if let WorktreeLockStatus::Locked(reason) = worktree.is_locked().map_err(err)? {
+                record.locked = true;
+                record.lock_reason = reason.map(|r| r.trim_end().to_string()).filter(|r| !r.is_empty());
+            }
+            linked.push(record);
+        }
+        linked.sort_by(|a, b| a.path.cmp(&b.path));
+        out.extend(linked);
+        Ok(out)
+    }
+
+    fn commit_info(&self, dir: &Path, rev: &str) -> Result<CommitInfo, String> {
+        let repo = Repository::discover(dir).map_err(err)?;
+        let commit = find_commit(&repo, rev)?;
+        let author = commit.author();
+        let (subject, body) = split_message(&String::from_utf8_lossy(commit.message_bytes()));
+        let file_stats = diff_numstat(&commit_diff(&repo, &commit)?)?
+            .into_iter()
+            .map(|s| CommitFileStat {
+                file: s.path,
+                additions: s.added,
+                deletions: s.removed,
+            })
+            .collect();
+        Ok(CommitInfo::new(
+            commit.id().to_string(),
+            String::from_utf8_lossy(author.name_bytes()).to_string(),
+            iso8601(author.when()),
+            subject,
+            body,
+            file_stats,
+        ))
+    }
+
+    fn show(&self, dir: &Path, rev: &str) -> Result<String, String> {
+        let repo = Repository::discover(dir).map_err(err)?;
+        let commit = find_commit(&repo, rev)?;
+        let diff = commit_diff(&repo, &commit)?;
+        patch_text(&diff)
+    }
+
+    fn log(&self, dir: &Path, query: &LogQuery, max: usize) -> Result<Vec<CommitSummary>, String> {
+        if !can_walk(query) {
+            return Err("query needs the git CLI".to_string());
+        }
+        let repo = Repository::discover(dir).map_err(err)?;
+        let mut walk = repo.revwalk().map_err(err)?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(err)?;
+        if !push_range(&repo, &mut walk, query.range.as_deref())? {
+            return Ok(Vec::new());
+        }
+
+        let author = query.author.as_deref().map(str::to_lowercase);
+        let grep = query.grep.as_deref().map(str::to_lowercase);
+        let mut commits = Vec::new();
+        let mut skipped = 0;
+        for oid in walk {
+            if commits.len() >= max {
+                break;
+            }
+            let commit = repo.find_commit(oid.map_err(err)?).map_err(err)?;
+            let signature = commit.author();
+            let name = String::from_utf8_lossy(signature.name_bytes()).to_string();
+            if let Some(needle) = &author {
+                // git matches --author against the whole "Name <email>" ident.
+                let ident = format!("{} <{}>", name, String::from_utf8_lossy(signature.email_bytes()));
+                if !ident.to_lowercase().contains(needle.as_str()) {
+                    continue;
+                }
+            }
+            let message = String::from_utf8_lossy(commit.message_bytes()).to_string();
+            if let Some(needle) = &grep {
+                if !message.to_lowercase().contains(needle.as_str()) {
+                    continue;
+                }
+            }
+            if skipped < query.skip {
+                skipped += 1;
+                continue;
+            }
+            commits.push(CommitSummary {
+                hash: commit.id().to_string(),
+                parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+                author: name,
+                date: iso8601(signature.when()),
+                subject: split_message(&message).0,
+            });
+        }
+        Ok(commits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_iso8601_in_signature_offset() {
+        let time = git2::Time::new(1_700_000_000, -330);
+        assert_eq!(iso8601(time), "2023-11-14T16:43:20-05:30");
+        assert_eq!(iso8601(git2::Time::new(0, 0)), "1970-01-01T00:00:00+00:00");
+    }
+}
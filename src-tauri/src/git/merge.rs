@@ -0,0 +1,597 @@
+//! Merging a finished agent worktree's branch back into a target branch.
+//!
+//! [`preview_merge_back`] runs `git merge-tree` so conflicts are known before
+//! anything is touched. [`merge_back`] repeats the preview, then merges,
+//! squashes or rebases in the worktrees involved (the target branch's and the
+//! agent's), optionally removes the agent's worktree and branch, and streams
+//! a [`MergeBackEvent`] per step over a `Channel`.
+
+use super::backend::GitBackend;
+use super::branches::{self, dirty_error, dirty_tracked_paths};
+use super::types::{
+    GitFileStatus, MergeBackEvent, MergeBackOptions, MergeBackOutcome, MergeBackStep,
+    MergeConflict, MergePreview, MergeStrategy, WorktreeInfo,
+};
+use super::worktrees::{self, find_linked};
+use super::{
+    git_failure, query_backend, repo_dir, run_git, run_git_with_stdin, validate_rev,
+    BACKEND_TIMEOUT,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::ipc::Channel;
+
+/// A rebase replays every commit and runs hooks along the way, and a merge
+/// can rewrite much of a large worktree.
+const MERGE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Parse `git merge-tree --write-tree --name-only -z` output: the tree id,
+/// the conflicted paths up to an empty field, then informational messages as
+/// `<n> <path>... <type> <message>` groups.
+fn parse_merge_tree(stdout: &str) -> Vec<MergeConflict> {
+    let mut fields = stdout.split('\0');
+    fields.next();
+    let mut paths: Vec<&str> = Vec::new();
+    for field in fields.by_ref() {
+        if field.is_empty() {
+            break;
+        }
+        // One entry per conflicted stage; keep one per path.
+        if !paths.contains(&field) {
+            paths.push(field);
+        }
+    }
+    let mut messages: Vec<(Vec<&str>, &str, &str)> = Vec::new();
+    while let Some(Ok(n)) = fields.next().map(str::parse::<usize>) {
+        let involved: Vec<&str> = fields.by_ref().take(n).collect();
+        let (Some(kind), Some(message)) = (fields.next(), fields.next()) else {
+            break;
+        };
+        messages.push((involved, kind, message));
+    }
+    paths
+        .into_iter()
+        .map(|path| {
+            let conflict = messages.iter().find_map(|(involved, kind, message)| {
+                let kind = kind.strip_prefix("CONFLICT (")?.strip_suffix(')')?;
+                involved.contains(&path).then_some((kind, message.trim()))
+            });
+            let (kind, message) = match conflict {
+                Some((kind, message)) => (kind.to_string(), message.to_string()),
+                None => (
+                    "contents".to_string(),
+                    format!("Merge conflict in {}", path),
+                ),
+            };
+            MergeConflict {
+                path: path.to_string(),
+                kind,
+                message,
+            }
+        })
+        .collect()
+}
+
+fn preview(
+    git: &dyn GitBackend,
+    repo: &Path,
+    source: &WorktreeInfo,
+    target: &str,
+) -> Result<MergePreview, String> {
+    let source_branch = source
+        .branch
+        .clone()
+        .ok_or_else(|| format!("{} is not on a branch", source.path))?;
+    validate_rev(target)?;
+    let target_ref = format!("refs/heads/{}", target);
+    if !run_git(repo, ["show-ref", "--verify", "--quiet", &target_ref])?
+        .status
+        .success()
+    {
+        return Err(format!("No local branch named {}", target));
+    }
+    if source_branch == target {
+        return Err(format!("{} can't be merged into itself", target));
+    }
+
+    let range = format!("{}...{}", target, source_branch);
+    let output = run_git(repo, ["rev-list", "--left-right", "--count", &range])?;
+    if !output.status.success() {
+        return Err(git_failure("compare branches", &output));
+    }
+    let counts = String::from_utf8_lossy(&output.stdout);
+    let mut counts = counts.split_whitespace().map(|n| n.parse().unwrap_or(0));
+    let (behind, ahead) = (counts.next().unwrap_or(0), counts.next().unwrap_or(0));
+
+    let output = run_git(repo, ["merge-base", target, &source_branch])?;
+    let merge_base = Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|b| output.status.success() && !b.is_empty());
+
+    let output = run_git(
+        repo,
+        [
+            "merge-tree",
+            "--write-tree",
+            "--name-only",
+            "-z",
+            target,
+            &source_branch,
+        ],
+    )?;
+    // Exit status 1 means the merge has conflicts; anything else is an error.
+    let conflicts = match output.status.code() {
+        Some(0) => Vec::new(),
+        Some(1) => parse_merge_tree(&String::from_utf8_lossy(&output.stdout)),
+        _ => return Err(git_failure("preview merge", &output)),
+    };
+
+    let target_worktree = git
+        .worktrees(repo)?
+        .into_iter()
+        .find(|w| w.branch.as_deref() == Some(target))
+        .map(|w| w.path);
+
+    Ok(MergePreview {
+        source_branch,
+        target_branch: target.to_string(),
+        target_worktree,
+        merge_base,
+        ahead,
+        behind,
+        conflicts,
+    })
+}
+
+/// Preview merging the branch of the linked worktree at `worktree_path` into
+/// `target_branch`, without touching either worktree.
+#[tauri::command]
+pub async fn preview_merge_back(
+    repo_path: String,
+    worktree_path: String,
+    target_branch: String,
+) -> Result<MergePreview, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let source = find_linked(git, repo, &worktree_path)?;
+        preview(git, repo, &source, &target_branch)
+    })
+    .await
+}
+
+/// Files left unmerged in `dir`, described with the preview's details where
+/// it predicted them.
+fn unmerged(
+    git: &dyn GitBackend,
+    dir: &Path,
+    predicted: &[MergeConflict],
+) -> Result<Vec<MergeConflict>, String> {
+    let status = git.status(dir)?.unwrap_or_default();
+    Ok(status
+        .entries
+        .into_iter()
+        .filter(|e| e.status == GitFileStatus::Conflicted)
+        .map(|e| {
+            predicted
+                .iter()
+                .find(|c| c.path == e.path)
+                .cloned()
+                .unwrap_or_else(|| MergeConflict {
+                    message: format!("Merge conflict in {}", e.path),
+                    path: e.path,
+                    kind: "contents".to_string(),
+                })
+        })
+        .collect())
+}
+
+fn squash_message(dir: &Path, source: &str, target: &str) -> Result<String, String> {
+    let range = format!("{}..{}", target, source);
+    let output = run_git(dir, ["log", "--reverse", "--format=* %s", &range])?;
+    if !output.status.success() {
+        return Err(git_failure("list squashed commits", &output));
+    }
+    Ok(format!(
+        "Squash merge branch '{}'\n\n{}",
+        source,
+        String::from_utf8_lossy(&output.stdout).trim_end()
+    ))
+}
+
+fn run_merge_back(
+    git: &dyn GitBackend,
+    repo: &Path,
+    options: &MergeBackOptions,
+    emit: &dyn Fn(MergeBackEvent),
+) -> Result<MergeBackOutcome, String> {
+    use MergeBackStep::*;
+    let step = |step, message: String| emit(MergeBackEvent::Step { step, message });
+
+    let source = find_linked(git, repo, &options.worktree_path)?;
+    let target = options.target_branch.as_str();
+    step(
+        Preview,
+        format!("Checking {} against {}", source.path, target),
+    );
+    let preview = preview(git, repo, &source, target)?;
+    let source_branch = preview.source_branch.as_str();
+    if preview.ahead == 0 {
+        return Err(format!(
+            "{} has no commits that {} doesn't already have",
+            source_branch, target
+        ));
+    }
+    let target_dir = PathBuf::from(preview.target_worktree.as_deref().ok_or_else(|| {
+        format!(
+            "{} isn't checked out in any worktree; check it out to merge into it",
+            target
+        )
+    })?);
+    let source_dir = PathBuf::from(&source.path);
+    for dir in [&target_dir, &source_dir] {
+        let dirty = dirty_tracked_paths(git, dir)?;
+        if !dirty.is_empty() {
+            let action = format!(
+                "merge {} into {} ({})",
+                source_branch,
+                target,
+                dir.display()
+            );
+            return Err(dirty_error(&action, &dirty));
+        }
+    }
+
+    for conflict in &preview.conflicts {
+        emit(MergeBackEvent::Conflict(conflict.clone()));
+    }
+    if !preview.conflicts.is_empty() && !options.allow_conflicts {
+        return Ok(MergeBackOutcome::Conflicts {
+            conflicts: preview.conflicts.clone(),
+            in_progress: None,
+        });
+    }
+
+    // Run one git step in `dir`. On conflicts either leave them for
+    // resolution or undo the step with `abort`.
+    let attempt = |dir: &Path,
+                   args: &[&str],
+                   abort: &[&str],
+                   action: &str|
+     -> Result<Option<MergeBackOutcome>, String> {
+        let output = run_git(dir, args)?;
+        if output.status.success() {
+            return Ok(None);
+        }
+        let conflicts = unmerged(git, dir, &preview.conflicts)?;
+        if conflicts.is_empty() {
+            return Err(git_failure(action, &output));
+        }
+        for conflict in conflicts.iter().filter(|c| !preview.conflicts.contains(c)) {
+            emit(MergeBackEvent::Conflict(conflict.clone()));
+        }
+        let in_progress = if options.allow_conflicts {
+            Some(dir.to_string_lossy().to_string())
+        } else {
+            run_git(dir, abort)?;
+            None
+        };
+        Ok(Some(MergeBackOutcome::Conflicts {
+            conflicts,
+            in_progress,
+        }))
+    };
+
+    let stopped = match options.strategy {
+        MergeStrategy::Merge => {
+            step(Merge, format!("Merging {} into {}", source_branch, target));
+            let mut args = vec!["merge", "--no-ff"];
+            match options.message.as_deref() {
+                Some(message) => args.extend(["-m", message]),
+                None => args.push("--no-edit"),
+            }
+            args.push(source_branch);
+            attempt(&target_dir, &args, &["merge", "--abort"], "merge")?
+        }
+        MergeStrategy::Squash => {
+            step(
+                Squash,
+                format!("Squashing {} into {}", source_branch, target),
+            );
+            // A squash leaves no MERGE_HEAD, so `merge --abort` can't undo it.
+            let stopped = attempt(
+                &target_dir,
+                &["merge", "--squash", source_branch],
+                &["reset", "--merge"],
+                "squash merge",
+            )?;
+            if stopped.is_none() {
+                let message = match &options.message {
+                    Some(message) => message.clone(),
+                    None => squash_message(&target_dir, source_branch, target)?,
+                };
+                let output =
+                    run_git_with_stdin(&target_dir, ["commit", "--file", "-"], message.as_bytes())?;
+                if !output.status.success() {
+                    return Err(git_failure("commit squash merge", &output));
+                }
+            }
+            stopped
+        }
+        MergeStrategy::Rebase => {
+            step(
+                Rebase,
+                format!("Rebasing {} onto {}", source_branch, target),
+            );
+            let stopped = attempt(
+                &source_dir,
+                &["rebase", target],
+                &["rebase", "--abort"],
+                "rebase",
+            )?;
+            if stopped.is_none() {
+                step(
+                    FastForward,
+                    format!("Fast-forwarding {} to {}", target, source_branch),
+                );
+                let output = run_git(&target_dir, ["merge", "--ff-only", source_branch])?;
+                if !output.status.success() {
+                    return Err(git_failure("fast-forward", &output));
+                }
+            }
+            stopped
+        }
+    };
+    if let Some(outcome) = stopped {
+        return Ok(outcome);
+    }
+
+    let output = run_git(&target_dir, ["rev-parse", "HEAD"])?;
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let mut worktree_removed = false;
+    let mut branch_deleted = false;
+    let mut cleanup_error = None;
+    if options.remove_worktree {
+        step(RemoveWorktree, format!("Removing worktree {}", source.path));
+        match worktrees::remove(git, repo, &source.path, false) {
+            Ok(()) => worktree_removed = true,
+            Err(e) => cleanup_error = Some(e),
+        }
+    }
+    if options.delete_branch && cleanup_error.is_none() {
+        if worktree_removed {
+            step(DeleteBranch, format!("Deleting branch {}", source_branch));
+            // Run where the target is checked out so `-d` checks against it.
+            // A squash leaves the branch unmerged as far as git can tell.
+            let force = options.strategy == MergeStrategy::Squash;
+            match branches::delete(&target_dir, source_branch, force) {
+                Ok(()) => branch_deleted = true,
+                Err(e) => cleanup_error = Some(e),
+            }
+        } else {
+            cleanup_error = Some(format!(
+                "{} is still checked out in {}; remove the worktree to delete it",
+                source_branch, source.path
+            ));
+        }
+    }
+
+    step(Done, format!("Merged {} into {}", source_branch, target));
+    Ok(MergeBackOutcome::Merged {
+        commit,
+        worktree_removed,
+        branch_deleted,
+        cleanup_error,
+    })
+}
+
+/// Merge the branch of a linked worktree back into `options.target_branch`,
+/// in the worktree where the target is checked out. Both worktrees must be
+/// free of uncommitted changes to tracked files.
+#[tauri::command]
+pub async fn merge_back(
+    repo_path: String,
+    options: MergeBackOptions,
+    channel: Channel<MergeBackEvent>,
+) -> Result<MergeBackOutcome, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, MERGE_TIMEOUT, move |git, repo| {
+        run_merge_back(git, repo, &options, &|event| {
+            let _ = channel.send(event);
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::backend::backend;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use crate::git::types::CreateWorktreeOptions;
+    use crate::git::worktrees::create_worktree;
+    use std::fs;
+    use std::sync::Mutex;
+
+    /// A repo with `main` plus a sibling worktree on `agent/t` with one
+    /// commit touching `a.txt`. Returns the repo and the worktree path.
+    async fn fixture(name: &str, main_edit: &str) -> (PathBuf, String) {
+        let repo = test_repo(name);
+        commit_file(&repo, "a.txt", "1\n2\n3\n", "init");
+        let wt = create_worktree(
+            repo.to_string_lossy().to_string(),
+            CreateWorktreeOptions {
+                name: "t".into(),
+                branch: Some("agent/t".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap()
+        .path;
+        commit_file(Path::new(&wt), "a.txt", "1\nagent\n3\n", "agent work");
+        commit_file(&repo, main_edit, "main\n", "main work");
+        (repo, wt)
+    }
+
+    fn cleanup(repo: &Path) {
+        let name = repo.file_name().unwrap().to_string_lossy();
+        let _ = fs::remove_dir_all(repo.with_file_name(format!("{}.worktrees", name)));
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    fn options(wt: &str, strategy: MergeStrategy) -> MergeBackOptions {
+        MergeBackOptions {
+            worktree_path: wt.to_string(),
+            target_branch: "main".into(),
+            strategy,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn merges_back_and_cleans_up() {
+        let (repo, wt) = fixture("merge_back", "b.txt").await;
+        let root = repo.to_string_lossy().to_string();
+
+        let preview = preview_merge_back(root.clone(), wt.clone(), "main".into())
+            .await
+            .unwrap();
+        assert_eq!((preview.ahead, preview.behind), (1, 1));
+        assert!(preview.conflicts.is_empty());
+        assert!(
+            Path::new(preview.target_worktree.as_deref().unwrap()) == repo.canonicalize().unwrap()
+        );
+
+        let events = Mutex::new(Vec::new());
+        let outcome = run_merge_back(
+            backend(),
+            &repo,
+            &MergeBackOptions {
+                remove_worktree: true,
+                delete_branch: true,
+                ..options(&wt, MergeStrategy::Merge)
+            },
+            &|e| events.lock().unwrap().push(e),
+        )
+        .unwrap();
+        match outcome {
+            MergeBackOutcome::Merged {
+                worktree_removed,
+                branch_deleted,
+                cleanup_error,
+                ..
+            } => {
+                assert!(worktree_removed && branch_deleted, "{cleanup_error:?}");
+            }
+            other => panic!("unexpected outcome: {:?}", other),
+        }
+        let steps: Vec<MergeBackStep> = events
+            .into_inner()
+            .unwrap()
+            .into_iter()
+            .filter_map(|e| match e {
+                MergeBackEvent::Step { step, .. } => Some(step),
+                _ => None,
+            })
+            .collect();
+        use MergeBackStep::*;
+        assert_eq!(
+            steps,
+            vec![Preview, Merge, RemoveWorktree, DeleteBranch, Done]
+        );
+        assert_eq!(
+            git(&repo, &["rev-list", "--count", "--merges", "HEAD"]).trim(),
+            "1"
+        );
+        assert!(git(&repo, &["branch", "--list", "agent/t"]).is_empty());
+        assert!(!Path::new(&wt).exists());
+        cleanup(&repo);
+    }
+
+    #[tokio::test]
+    async fn rebase_fast_forwards_target() {
+        let (repo, wt) = fixture("merge_back_rebase", "b.txt").await;
+        let outcome = run_merge_back(
+            backend(),
+            &repo,
+            &options(&wt, MergeStrategy::Rebase),
+            &|_| {},
+        )
+        .unwrap();
+        let MergeBackOutcome::Merged { commit, .. } = outcome else {
+            panic!("unexpected outcome: {:?}", outcome);
+        };
+        assert_eq!(git(Path::new(&wt), &["rev-parse", "HEAD"]).trim(), commit);
+        assert_eq!(
+            git(&repo, &["rev-list", "--count", "--merges", "HEAD"]).trim(),
+            "0"
+        );
+        cleanup(&repo);
+    }
+
+    #[tokio::test]
+    async fn reports_conflicts_before_touching_anything() {
+        let (repo, wt) = fixture("merge_back_conflict", "a.txt").await;
+        let head = git(&repo, &["rev-parse", "HEAD"]);
+
+        let outcome = run_merge_back(
+            backend(),
+            &repo,
+            &options(&wt, MergeStrategy::Squash),
+            &|_| {},
+        )
+        .unwrap();
+        let MergeBackOutcome::Conflicts {
+            conflicts,
+            in_progress,
+        } = outcome
+        else {
+            panic!("unexpected outcome: {:?}", outcome);
+        };
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            (conflicts[0].path.as_str(), conflicts[0].kind.as_str()),
+            ("a.txt", "contents")
+        );
+        assert!(in_progress.is_none());
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), head);
+        assert!(git(&repo, &["status", "--porcelain"]).is_empty());
+
+        // With allow_conflicts the squash is left for resolution.
+        let outcome = run_merge_back(
+            backend(),
+            &repo,
+            &MergeBackOptions {
+                allow_conflicts: true,
+                ..options(&wt, MergeStrategy::Squash)
+            },
+            &|_| {},
+        )
+        .unwrap();
+        assert!(matches!(
+            outcome,
+            MergeBackOutcome::Conflicts {
+                in_progress: Some(_),
+                ..
+            }
+        ));
+        assert!(git(&repo, &["status", "--porcelain"]).contains("UU a.txt"));
+        cleanup(&repo);
+    }
+
+    #[test]
+    fn parses_merge_tree_messages() {
+        let out = "tree\0f\0g\0\0\
+                   1\0f\0Auto-merging\0Auto-merging f\n\0\
+                   1\0f\0CONFLICT (contents)\0CONFLICT (content): Merge conflict in f\n\0\
+                   1\0g\0CONFLICT (modify/delete)\0CONFLICT (modify/delete): g deleted\n\0";
+        let conflicts = parse_merge_tree(out);
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].kind, "contents");
+        assert_eq!(
+            conflicts[0].message,
+            "CONFLICT (content): Merge conflict in f"
+        );
+        assert_eq!(conflicts[1].kind, "modify/delete");
+    }
+}
@@ -1,12 +1,239 @@
+pub mod backend;
+pub mod blame;
+pub mod branches;
+mod cli;
+pub mod commit;
+pub mod compare;
+pub mod conflicts;
+pub mod diff;
+pub mod health;
+pub mod hooks;
+mod libgit;
+pub mod merge;
+pub mod nested;
+pub mod patches;
+pub mod remote;
+pub mod snapshots;
+pub mod staging;
+pub mod stash;
+pub mod state;
 pub mod types;
+pub mod worktrees;
 
+use backend::GitBackend;
 use log::error;
-use std::process::Command;
+use remote::kill_operation;
+use std::cell::Cell;
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
 use types::{
-    CommitFileStat, CommitInfo, FileDiffStat, GitFileStatus, GitStatusEntry, RepoHealth,
-    SuspiciousTrackedDir, WorktreeInfo,
+    CommitInfo, FileDiffStat, FileState, GitFileStatus, GitStatus, GitStatusEntry, LogPage,
+    LogQuery, RepoHealth, SubmoduleState, SuspiciousTrackedDir, WorktreeInfo,
 };
 
+/// Canonicalize `path` and require it to be a directory — the preamble shared
+/// by every command that runs git in a repo.
+pub(crate) fn repo_dir(path: &str) -> Result<PathBuf, String> {
+    let canonical = crate::fs::canonicalize_path(path)?;
+    if !canonical.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    Ok(canonical)
+}
+
+thread_local! {
+    /// When the [`query_backend`] call running on this thread gives up, and
+    /// the limit it was given. Set only while the query runs.
+    static DEADLINE: Cell<Option<(Instant, Duration)>> = const { Cell::new(None) };
+}
+
+/// Clears [`DEADLINE`] when the query ends, panic or not, so the next task
+/// on this pool thread doesn't inherit it.
+struct DeadlineGuard;
+
+impl DeadlineGuard {
+    fn set(deadline: Instant, limit: Duration) -> Self {
+        DEADLINE.with(|d| d.set(Some((deadline, limit))));
+        DeadlineGuard
+    }
+}
+
+impl Drop for DeadlineGuard {
+    fn drop(&mut self) {
+        DEADLINE.with(|d| d.set(None));
+    }
+}
+
+fn read_all(pipe: Option<impl Read>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    if let Some(mut pipe) = pipe {
+        let _ = pipe.read_to_end(&mut buf);
+    }
+    buf
+}
+
+/// Wait for `child`, killing it once the current query's deadline passes.
+/// Polls quickly at first so short commands aren't held up.
+pub(crate) fn wait_until_deadline(child: &mut Child) -> Result<ExitStatus, String> {
+    let Some((deadline, limit)) = DEADLINE.with(Cell::get) else {
+        return child
+            .wait()
+            .map_err(|e| format!("Failed to run git: {}", e));
+    };
+    let mut interval = Duration::from_millis(1);
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Ok(status),
+            Ok(None) if Instant::now() >= deadline => {
+                kill_operation(child);
+                let _ = child.wait();
+                return Err(format!("git timed out after {}s", limit.as_secs()));
+            }
+            Ok(None) => {
+                std::thread::sleep(interval);
+                interval = (interval * 2).min(Duration::from_millis(50));
+            }
+            Err(e) => return Err(format!("Failed to run git: {}", e)),
+        }
+    }
+}
+
+/// Run `command` to completion, feeding it `input` on stdin, and capture its
+/// output. Inside [`query_backend`] nothing is started once the query's time
+/// is up, and a child still running then is killed — along with any hooks it
+/// started — so a timed-out command can't land its change later or keep
+/// holding `index.lock`.
+fn run_killable(mut command: Command, input: Option<&[u8]>) -> Result<Output, String> {
+    if let Some((deadline, limit)) = DEADLINE.with(Cell::get) {
+        if Instant::now() >= deadline {
+            return Err(format!("git timed out after {}s", limit.as_secs()));
+        }
+    }
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    let stdin = child.stdin.take();
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+    std::thread::scope(|scope| {
+        // Pipes are drained and fed on their own threads so a chatty git
+        // never blocks on a full pipe while we wait on it.
+        let writer = scope.spawn(move || match (stdin, input) {
+            (Some(mut stdin), Some(input)) => stdin.write_all(input),
+            _ => Ok(()),
+        });
+        let stdout = scope.spawn(move || read_all(stdout));
+        let stderr = scope.spawn(move || read_all(stderr));
+        let status = wait_until_deadline(&mut child)?;
+        writer
+            .join()
+            .unwrap_or(Ok(()))
+            .map_err(|e| format!("Failed to write to git: {}", e))?;
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    })
+}
+
+/// Run `git <args>` in `repo` and capture its output. Only a failure to spawn
+/// is an error here; callers decide what a non-zero exit means.
+pub(crate) fn run_git<I, S>(repo: &Path, args: I) -> Result<Output, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+    command.args(args).current_dir(repo);
+    run_killable(command, None)
+}
+
+/// [`run_git`] with extra environment variables, e.g. a scratch
+/// `GIT_INDEX_FILE`.
+pub(crate) fn run_git_with_env<I, S>(
+    repo: &Path,
+    args: I,
+    env: &[(&str, &OsStr)],
+) -> Result<Output, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .envs(env.iter().copied())
+        .current_dir(repo);
+    run_killable(command, None)
+}
+
+/// [`run_git`] with `input` fed on stdin, e.g. a patch for `git apply`.
+pub(crate) fn run_git_with_stdin<I, S>(repo: &Path, args: I, input: &[u8]) -> Result<Output, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new("git");
+    command.args(args).current_dir(repo);
+    run_killable(command, Some(input))
+}
+
+/// True when HEAD points at a commit (false in a repo with no commits yet).
+pub(crate) fn has_head(repo: &Path) -> bool {
+    run_git(repo, ["rev-parse", "--verify", "--quiet", "HEAD"])
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Paths with unresolved conflicts, e.g. after a stash apply or a pull that
+/// stopped partway.
+pub(crate) fn unmerged_paths(repo: &Path) -> Result<Vec<String>, String> {
+    let output = run_git(
+        repo,
+        ["-c", "core.quotePath=false", "diff", "--name-only", "--diff-filter=U"],
+    )?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::to_string)
+        .collect())
+}
+
+/// Describe a failed git invocation for the UI. Index-lock contention gets its
+/// own message: it's the usual failure while an agent runs git in the same
+/// repo, and it's transient.
+pub(crate) fn git_failure(action: &str, output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if stderr.contains("index.lock") {
+        return format!(
+            "Could not {}: the git index is locked by another git process. \
+             Retry once it finishes, or remove .git/index.lock if none is running.",
+            action
+        );
+    }
+    let detail = match stderr.trim() {
+        "" => String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        s => s.to_string(),
+    };
+    format!("Could not {}: {}", action, detail)
+}
+
 fn parse_status(xy: &str) -> Option<GitFileStatus> {
     let bytes = xy.as_bytes();
     if bytes.len() < 2 {
@@ -79,350 +306,249 @@ fn classify_worktree(path: &str, is_main: bool) -> String {
 /// --porcelain`. A single query covers the main worktree and all linked
 /// worktrees (Claude, Codex, manual) regardless of physical location — git is
 /// the source of truth. Branch names are read from porcelain, never derived.
+/// Each present, non-bare worktree is also checked for uncommitted changes.
 #[tauri::command]
 pub async fn get_git_worktrees(path: String) -> Result<Vec<WorktreeInfo>, String> {
-    let canonical = crate::fs::canonicalize_path(&path)?;
-    let repo_path = canonical.as_path();
-    if !repo_path.is_dir() {
-        return Err(format!("Not a directory: {}", path));
-    }
-
-    let output = Command::new("git")
-        .args(["worktree", "list", "--porcelain"])
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    if !output.status.success() {
-        // Not a git repo (or no worktree support) — surface nothing.
-        return Ok(vec![]);
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut worktrees: Vec<WorktreeInfo> = Vec::new();
-
-    // Records are separated by blank lines. Each starts with `worktree <path>`,
-    // then `HEAD <sha>`, then either `branch refs/heads/<name>` or `detached`
-    // (and `bare` for a bare main repo, which has no HEAD/branch).
-    let mut cur_path: Option<String> = None;
-    let mut cur_head = String::new();
-    let mut cur_branch: Option<String> = None;
-    let mut cur_detached = false;
-
-    let flush =
-        |path: &mut Option<String>, head: &mut String, branch: &mut Option<String>, detached: &mut bool, out: &mut Vec<WorktreeInfo>| {
-            if let Some(p) = path.take() {
-                let is_main = out.is_empty();
-                let source = classify_worktree(&p, is_main);
-                out.push(WorktreeInfo {
-                    path: p,
-                    branch: branch.take(),
-                    detached: *detached,
-                    head: std::mem::take(head),
-                    source,
-                });
-            }
-            *detached = false;
-        };
-
-    for line in stdout.lines() {
-        if line.is_empty() {
-            flush(&mut cur_path, &mut cur_head, &mut cur_branch, &mut cur_detached, &mut worktrees);
-            continue;
+    let repo = repo_dir(&path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |git, repo| {
+        let mut worktrees = git.worktrees(repo)?;
+        for wt in worktrees.iter_mut().filter(|w| !w.prunable && !w.head.is_empty()) {
+            // A worktree we can't read isn't reported as dirty; the list
+            // itself shouldn't fail over it.
+            wt.dirty = git
+                .status(Path::new(&wt.path))
+                .ok()
+                .flatten()
+                .is_some_and(|s| !s.entries.is_empty());
         }
-        if let Some(p) = line.strip_prefix("worktree ") {
-            cur_path = Some(p.to_string());
-        } else if let Some(h) = line.strip_prefix("HEAD ") {
-            cur_head = h.to_string();
-        } else if let Some(b) = line.strip_prefix("branch ") {
-            cur_branch = Some(b.strip_prefix("refs/heads/").unwrap_or(b).to_string());
-        } else if line == "detached" {
-            cur_detached = true;
-        }
-        // `bare`, `locked`, `prunable` lines are ignored.
-    }
-    // Final record (porcelain output may not end with a blank line).
-    flush(&mut cur_path, &mut cur_head, &mut cur_branch, &mut cur_detached, &mut worktrees);
-
-    Ok(worktrees)
+        Ok(worktrees)
+    })
+    .await
 }
 
-#[tauri::command]
-pub async fn get_git_status(path: String) -> Result<Vec<GitStatusEntry>, String> {
-    let canonical = crate::fs::canonicalize_path(&path)?;
-    let repo_path = canonical.as_path();
-    if !repo_path.is_dir() {
-        return Err(format!("Not a directory: {}", path));
+fn parse_file_state(c: u8) -> FileState {
+    match c {
+        b'M' => FileState::Modified,
+        b'T' => FileState::TypeChanged,
+        b'A' => FileState::Added,
+        b'D' => FileState::Deleted,
+        b'R' => FileState::Renamed,
+        b'C' => FileState::Copied,
+        b'U' => FileState::Unmerged,
+        b'?' => FileState::Untracked,
+        b'!' => FileState::Ignored,
+        _ => FileState::Unmodified,
     }
+}
 
-    let output = Command::new("git")
-        .args(["status", "--porcelain=v1", "-uall"])
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+/// Build a status entry from a porcelain v2 `XY` pair ('.' = unmodified).
+fn status_entry(
+    xy: [u8; 2],
+    path: String,
+    orig_path: Option<String>,
+    submodule: Option<SubmoduleState>,
+) -> Option<GitStatusEntry> {
+    // v1 spells "unmodified" as a space, which is what parse_status expects.
+    let v1_xy: String = xy
+        .iter()
+        .map(|&c| if c == b'.' { ' ' } else { c as char })
+        .collect();
+    Some(GitStatusEntry {
+        path,
+        status: parse_status(&v1_xy)?,
+        index: parse_file_state(xy[0]),
+        worktree: parse_file_state(xy[1]),
+        orig_path,
+        submodule,
+    })
+}
 
-    if !output.status.success() {
-        return Ok(vec![]);
-    }
+/// Default cap on a backend query, so a slow or locked repo can't hang a
+/// command indefinitely.
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long past its deadline a query is waited for before being abandoned.
+/// git children are killed at the deadline itself, so this only matters for
+/// in-process (libgit2) work, which can't be interrupted.
+const ABANDON_GRACE: Duration = Duration::from_secs(5);
+
+/// Run `query` against the active [`GitBackend`] on the blocking pool — git
+/// work is synchronous, and several projects poll at once — giving up after
+/// `limit`. The deadline travels with the query: every git it runs through
+/// [`run_git`] and friends is killed when it passes.
+async fn query_backend<T, F>(repo: PathBuf, limit: Duration, query: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&dyn GitBackend, &Path) -> Result<T, String> + Send + 'static,
+{
+    let deadline = Instant::now() + limit;
+    let task = tokio::task::spawn_blocking(move || {
+        let _guard = DeadlineGuard::set(deadline, limit);
+        query(backend::backend(), &repo)
+    });
+    tokio::time::timeout(limit + ABANDON_GRACE, task)
+        .await
+        .map_err(|_| format!("git timed out after {}s", limit.as_secs()))?
+        .map_err(|e| format!("git task failed: {}", e))?
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut entries: Vec<GitStatusEntry> = Vec::new();
+#[tauri::command]
+pub async fn get_git_status(path: String) -> Result<GitStatus, String> {
+    let repo = repo_dir(&path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |git, repo| git.status(repo))
+        .await
+        .map(Option::unwrap_or_default)
+}
 
-    for line in stdout.lines() {
-        if line.len() < 4 {
-            continue;
-        }
-        let xy = &line[0..2];
-        let raw_path = &line[3..];
-        let file_path = if let Some(arrow_pos) = raw_path.find(" -> ") {
-            &raw_path[arrow_pos + 4..]
-        } else {
-            raw_path
-        };
-
-        if let Some(status) = parse_status(xy) {
-            entries.push(GitStatusEntry {
-                path: file_path.to_string(),
-                status,
-            });
+/// Lines in an untracked file, counted as additions. Large, binary or
+/// unreadable files count as one line: uncommitted work all the same, but not
+/// worth reading.
+pub(crate) fn untracked_lines(path: &Path) -> u32 {
+    let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size > 1_000_000 {
+        return 1;
+    }
+    match std::fs::read(path) {
+        Ok(bytes) if !bytes.contains(&0) => {
+            let nl = bytes.iter().filter(|&&b| b == b'\n').count() as u32;
+            let trailing = u32::from(!bytes.is_empty() && *bytes.last().unwrap() != b'\n');
+            nl + trailing
         }
+        _ => 1,
     }
+}
+
+/// The worktree root of `repo`, and `repo`'s path inside it — empty unless
+/// the project is a subdirectory of its repository.
+fn worktree_root(repo: &Path) -> Result<(PathBuf, PathBuf), String> {
+    let root = run_git(repo, ["rev-parse", "--show-toplevel"])?;
+    if !root.status.success() {
+        return Err(git_failure("find the worktree root", &root));
+    }
+    let root = PathBuf::from(String::from_utf8_lossy(&root.stdout).trim());
+    let prefix = repo.strip_prefix(&root).unwrap_or(Path::new("")).to_path_buf();
+    Ok((root, prefix))
+}
 
-    Ok(entries)
+/// Untracked files under `repo` as (path relative to `repo`, absolute path).
+/// Status paths are relative to the worktree root, which is an ancestor of
+/// `repo` when the project is a subdirectory of its repository; files outside
+/// the project are left out.
+pub(crate) fn untracked_in(repo: &Path, status: &GitStatus) -> Result<Vec<(String, PathBuf)>, String> {
+    let (root, prefix) = worktree_root(repo)?;
+    Ok(status
+        .entries
+        .iter()
+        .filter(|e| e.worktree == FileState::Untracked)
+        .filter_map(|e| {
+            let relative = Path::new(&e.path).strip_prefix(&prefix).ok()?;
+            Some((relative.to_string_lossy().to_string(), root.join(&e.path)))
+        })
+        .collect())
+}
+
+/// The `numstat` entries under `repo`, with paths made relative to it as
+/// [`untracked_in`] does.
+pub(crate) fn numstat_in(repo: &Path, stats: Vec<FileDiffStat>) -> Result<Vec<FileDiffStat>, String> {
+    let (_, prefix) = worktree_root(repo)?;
+    Ok(stats
+        .into_iter()
+        .filter_map(|stat| {
+            let relative = Path::new(&stat.path).strip_prefix(&prefix).ok()?;
+            Some(FileDiffStat {
+                path: relative.to_string_lossy().to_string(),
+                ..stat
+            })
+        })
+        .collect())
 }
 
 #[tauri::command]
 pub async fn get_git_diff_stat(path: String) -> Result<(u32, u32), String> {
-    use tokio::process::Command as AsyncCommand;
-    use tokio::time::{timeout, Duration};
-
-    // Cap each git invocation so a slow or locked repo can't stall the diff-stat
-    // fetch — a stall would otherwise leave the env's +/- numbers blank for its
-    // whole duration. kill_on_drop reaps the child if the timeout fires.
+    // Cap the query so a slow or locked repo can't stall the diff-stat fetch —
+    // a stall would otherwise leave the env's +/- numbers blank for its whole
+    // duration.
     const GIT_TIMEOUT: Duration = Duration::from_secs(10);
 
-    let canonical = crate::fs::canonicalize_path(&path)?;
-    let repo_path = canonical.as_path();
-    if !repo_path.is_dir() {
-        return Err(format!("Not a directory: {}", path));
-    }
-
-    let mut diff_cmd = AsyncCommand::new("git");
-    diff_cmd
-        .args(["diff", "--numstat", "HEAD"])
-        .current_dir(repo_path)
-        .kill_on_drop(true);
-    let output = timeout(GIT_TIMEOUT, diff_cmd.output())
-        .await
-        .map_err(|_| format!("git diff timed out for {}", path))?
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    // A non-zero exit is a genuine git error — most often index.lock contention
-    // while another git process runs (e.g. a worktree being created). Surface it
-    // as Err so callers keep their last-known stats instead of blanking to 0/0.
-    // `git diff` (without --exit-code) exits 0 whether or not the tree has changes,
-    // so this never fires merely because the env is clean.
-    if !output.status.success() {
-        return Err(format!(
-            "git diff failed for {}: {}",
-            path,
-            String::from_utf8_lossy(&output.stderr).trim()
-        ));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut added: u32 = 0;
-    let mut removed: u32 = 0;
-
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() >= 2 {
-            added += parts[0].parse::<u32>().unwrap_or(0);
-            removed += parts[1].parse::<u32>().unwrap_or(0);
-        }
-    }
-
-    // Untracked files don't show up in `git diff HEAD`, but a brand-new file is
-    // uncommitted work all the same — count its lines as additions so a fresh
-    // file (e.g. in a just-created worktree) marks the env dirty. `--exclude-standard`
-    // honours .gitignore so ignored cruft (node_modules, build output) is skipped.
-    // Best-effort: a failure or timeout here just omits untracked lines, it never
-    // blanks the (already-known) tracked diff totals.
-    let mut untracked_cmd = AsyncCommand::new("git");
-    untracked_cmd
-        .args(["ls-files", "--others", "--exclude-standard", "-z"])
-        .current_dir(repo_path)
-        .kill_on_drop(true);
-    if let Ok(Ok(out)) = timeout(GIT_TIMEOUT, untracked_cmd.output()).await {
-        if out.status.success() {
-            let list = String::from_utf8_lossy(&out.stdout);
-            for rel in list.split('\0').filter(|s| !s.is_empty()) {
-                let full = repo_path.join(rel);
-                let size = std::fs::metadata(&full).map(|m| m.len()).unwrap_or(0);
-                // Don't read large/binary blobs to count lines — just mark dirty.
-                if size > 1_000_000 {
-                    added = added.saturating_add(1);
-                    continue;
-                }
-                match std::fs::read(&full) {
-                    Ok(bytes) if !bytes.contains(&0) => {
-                        let nl = bytes.iter().filter(|&&b| b == b'\n').count() as u32;
-                        let trailing = u32::from(!bytes.is_empty() && *bytes.last().unwrap() != b'\n');
-                        added = added.saturating_add(nl + trailing);
-                    }
-                    // Binary or unreadable but present → still uncommitted work.
-                    _ => added = added.saturating_add(1),
-                }
+    let repo = repo_dir(&path)?;
+    query_backend(repo, GIT_TIMEOUT, move |git, repo_path| {
+        // An error is a genuine git error — most often index.lock contention
+        // while another git process runs (e.g. a worktree being created).
+        // Surface it as Err so callers keep their last-known stats instead of
+        // blanking to 0/0.
+        let stats = git
+            .numstat(repo_path, None)
+            .map_err(|e| format!("git diff failed for {}: {}", path, e))?;
+        let mut added: u32 = stats.iter().map(|s| s.added).sum();
+        let removed: u32 = stats.iter().map(|s| s.removed).sum();
+
+        // Untracked files don't show up in a diff against HEAD, but a brand-new
+        // file is uncommitted work all the same — count its lines as additions
+        // so a fresh file (e.g. in a just-created worktree) marks the env dirty.
+        // Status honours .gitignore so ignored cruft (node_modules, build
+        // output) is skipped. Best-effort: a failure here just omits untracked
+        // lines, it never blanks the (already-known) tracked diff totals.
+        if let Ok(Some(status)) = git.status(repo_path) {
+            for (_, full) in untracked_in(repo_path, &status).unwrap_or_default() {
+                added = added.saturating_add(untracked_lines(&full));
             }
         }
-    }
 
-    Ok((added, removed))
+        Ok((added, removed))
+    })
+    .await
 }
 
 #[tauri::command]
 pub async fn get_file_diff_stat(repo_path: String, file_path: String) -> Result<(u32, u32), String> {
-    let canonical = crate::fs::canonicalize_path(&repo_path)?;
-    let repo = canonical.as_path();
-    if !repo.is_dir() {
-        return Err(format!("Not a directory: {}", repo_path));
-    }
-
-    let output = Command::new("git")
-        .args(["diff", "--numstat", "HEAD", "--", &file_path])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    if !output.status.success() {
-        return Ok((0, 0));
-    }
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let mut added: u32 = 0;
-    let mut removed: u32 = 0;
-
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split('\t').collect();
-        if parts.len() >= 2 {
-            added += parts[0].parse::<u32>().unwrap_or(0);
-            removed += parts[1].parse::<u32>().unwrap_or(0);
-        }
-    }
-
+    let repo = repo_dir(&repo_path)?;
+    let stats = query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        // No HEAD yet, or git failing: report no changes rather than an error.
+        Ok(git.numstat(repo, Some(&file_path)).unwrap_or_default())
+    })
+    .await?;
+    let added = stats.iter().map(|s| s.added).sum();
+    let removed = stats.iter().map(|s| s.removed).sum();
     Ok((added, removed))
 }
 
 #[tauri::command]
 pub async fn get_git_diff(repo_path: String, file_path: String) -> Result<String, String> {
-    let canonical = crate::fs::canonicalize_path(&repo_path)?;
-    let repo = canonical.as_path();
-    if !repo.is_dir() {
-        return Err(format!("Not a directory: {}", repo_path));
-    }
-
-    // Try normal diff first (tracked files)
-    let output = Command::new("git")
-        .args(["diff", "HEAD", "--", &file_path])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-
-    if !stdout.is_empty() {
-        return Ok(stdout);
-    }
-
-    // If empty, check if file is untracked and show as new file diff
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain", "--", &file_path])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    let status_str = String::from_utf8_lossy(&status_output.stdout);
-    if status_str.starts_with("??") {
-        let untracked = Command::new("git")
-            .args(["diff", "--no-index", "/dev/null", &file_path])
-            .current_dir(repo)
-            .output()
-            .map_err(|e| format!("Failed to run git: {}", e))?;
-
-        // git diff --no-index exits with 1 when there are differences, that's expected
-        return Ok(String::from_utf8_lossy(&untracked.stdout).to_string());
-    }
-
-    Ok(String::new())
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| git.diff(repo, &file_path)).await
 }
 
 /// Returns per-file diff stats (added/removed lines) for all uncommitted changes.
 /// Includes both tracked (diff HEAD) and untracked files (counted via wc -l equivalent).
 #[tauri::command]
 pub async fn get_all_file_diff_stats(path: String) -> Result<Vec<FileDiffStat>, String> {
-    let canonical = crate::fs::canonicalize_path(&path)?;
-    let repo_path = canonical.as_path();
-    if !repo_path.is_dir() {
-        return Err(format!("Not a directory: {}", path));
-    }
-
-    let mut stats: Vec<FileDiffStat> = Vec::new();
-
-    // Get diff stats for tracked files
-    let output = Command::new("git")
-        .args(["diff", "--numstat", "HEAD"])
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    if output.status.success() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        for line in stdout.lines() {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                let added = parts[0].parse::<u32>().unwrap_or(0);
-                let removed = parts[1].parse::<u32>().unwrap_or(0);
-                stats.push(FileDiffStat {
-                    path: parts[2].to_string(),
-                    added,
-                    removed,
-                });
-            }
-        }
-    }
-
-    // Get untracked files and count their lines
-    let status_output = Command::new("git")
-        .args(["status", "--porcelain=v1", "-uall"])
-        .current_dir(repo_path)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
-
-    if status_output.status.success() {
-        let stdout = String::from_utf8_lossy(&status_output.stdout);
-        for line in stdout.lines() {
-            if line.starts_with("??") && line.len() > 3 {
-                let file_path = &line[3..];
-                // Count lines in untracked file
-                let full_path = repo_path.join(file_path);
+    let repo = repo_dir(&path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |git, repo_path| {
+        // Diff stats for tracked files; none before the first commit.
+        let mut stats = git.numstat(repo_path, None).unwrap_or_default();
+
+        // Untracked files: count their lines
+        if let Some(status) = git.status(repo_path)? {
+            for entry in status.entries.iter().filter(|e| e.worktree == FileState::Untracked) {
+                let full_path = repo_path.join(&entry.path);
                 if full_path.is_file() {
                     let line_count = std::fs::read_to_string(&full_path)
                         .map(|c| c.lines().count() as u32)
                         .unwrap_or(0);
                     stats.push(FileDiffStat {
-                        path: file_path.to_string(),
+                        path: entry.path.clone(),
                         added: line_count,
                         removed: 0,
                     });
                 }
             }
         }
-    }
-
-    // Sort by total changes descending
-    stats.sort_by(|a, b| (b.added + b.removed).cmp(&(a.added + a.removed)));
 
-    Ok(stats)
+        // Sort by total changes descending
+        stats.sort_by(|a, b| (b.added + b.removed).cmp(&(a.added + a.removed)));
+        Ok(stats)
+    })
+    .await
 }
 
 /// Directory names that almost always mean build output or vendored
@@ -511,119 +637,220 @@ pub async fn diagnose_repo_health(path: String) -> Result<RepoHealth, String> {
     })
 }
 
-fn validate_commit_ref(commit_ref: &str) -> Result<(), String> {
-    if commit_ref.is_empty() || commit_ref.len() > 64 {
-        return Err("Invalid commit ref".to_string());
-    }
-    if !commit_ref
-        .chars()
-        .all(|c| c.is_ascii_alphanumeric() || c == '~' || c == '^')
-    {
-        return Err("Invalid commit ref".to_string());
+/// One side of a revision range: a ref name or commit-ish such as
+/// `origin/main`, `v1.2^{commit}` or `HEAD~3`. Option-looking and
+/// range-looking input is rejected.
+fn validate_rev(rev: &str) -> Result<(), String> {
+    let valid = !rev.starts_with('-')
+        && !rev.contains("..")
+        && rev
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./~^@{}".contains(c));
+    if !valid {
+        return Err(format!("Invalid revision: {}", rev));
     }
     Ok(())
 }
 
-#[tauri::command]
-pub async fn get_commit_info(repo_path: String, commit_ref: String) -> Result<CommitInfo, String> {
-    validate_commit_ref(&commit_ref)?;
-    let canonical = crate::fs::canonicalize_path(&repo_path)?;
-    let repo = canonical.as_path();
-    if !repo.is_dir() {
-        return Err(format!("Not a directory: {}", repo_path));
+/// `<rev>`, `<a>..<b>` or `<a>...<b>`, where at most one side may be empty
+/// (meaning HEAD).
+fn validate_rev_range(range: &str) -> Result<(), String> {
+    if range.is_empty() || range.len() > 256 {
+        return Err("Invalid revision range".to_string());
+    }
+    let sides = match range.split_once("...").or_else(|| range.split_once("..")) {
+        Some((from, to)) => vec![from, to],
+        None => vec![range],
+    };
+    if sides.iter().all(|s| s.is_empty()) {
+        return Err("Invalid revision range".to_string());
     }
+    sides
+        .into_iter()
+        .filter(|s| !s.is_empty())
+        .try_for_each(validate_rev)
+}
 
-    let output = Command::new("git")
-        .args(["log", "-1", "--format=%H%n%an%n%aI%n%s%n%b", &commit_ref])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+const LOG_DEFAULT_LIMIT: usize = 100;
+const LOG_MAX_LIMIT: usize = 1000;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Not a valid commit: {}", stderr.trim()));
-    }
+/// A page of history for `query.range` (HEAD by default). Fetches one commit
+/// past the page to know whether there's more.
+#[tauri::command]
+pub async fn get_git_log(repo_path: String, query: LogQuery) -> Result<LogPage, String> {
+    if let Some(range) = &query.range {
+        validate_rev_range(range)?;
+    }
+    let limit = query.limit.unwrap_or(LOG_DEFAULT_LIMIT).clamp(1, LOG_MAX_LIMIT);
+    let repo = repo_dir(&repo_path)?;
+    let mut commits =
+        query_backend(repo, BACKEND_TIMEOUT, move |git, repo| git.log(repo, &query, limit + 1))
+            .await?;
+    let has_more = commits.len() > limit;
+    commits.truncate(limit);
+    Ok(LogPage { commits, has_more })
+}
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let lines: Vec<&str> = stdout.splitn(5, '\n').collect();
-    if lines.len() < 4 {
-        return Err("Unexpected git log output".to_string());
+#[tauri::command]
+pub async fn get_commit_info(repo_path: String, commit_ref: String) -> Result<CommitInfo, String> {
+    validate_rev_range(&commit_ref)?;
+    if commit_ref.contains("..") {
+        return Err("Expected a single commit, not a range".to_string());
     }
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| git.commit_info(repo, &commit_ref)).await
+}
 
-    let hash = lines[0].to_string();
-    let author = lines[1].to_string();
-    let date = lines[2].to_string();
-    let subject = lines[3].to_string();
-    let body = if lines.len() >= 5 {
-        lines[4].trim_end().to_string()
-    } else {
-        String::new()
-    };
+/// A commit's patch, or for `<a>..<b>` / `<a>...<b>` the diff between the
+/// two sides (from their merge base, for `...`) as `git diff` prints it.
+#[tauri::command]
+pub async fn get_commit_diff(repo_path: String, commit_ref: String) -> Result<String, String> {
+    validate_rev_range(&commit_ref)?;
+    let repo = repo_dir(&repo_path)?;
+    if !commit_ref.contains("..") {
+        return query_backend(repo, BACKEND_TIMEOUT, move |git, repo| git.show(repo, &commit_ref))
+            .await;
+    }
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let output = run_git(
+            repo,
+            ["diff", "--no-color", "--no-ext-diff", &commit_ref, "--"],
+        )?;
+        if !output.status.success() {
+            return Err(git_failure("diff the range", &output));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+    .await
+}
 
-    // Get per-file stats via --numstat
-    let numstat_output = Command::new("git")
-        .args(["show", "--numstat", "--format=", &commit_ref])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+#[cfg(test)]
+pub(crate) mod test_util {
+    use std::path::{Path, PathBuf};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    /// A fresh `git init`ed directory under the temp dir.
+    pub fn test_repo(name: &str) -> PathBuf {
+        let unique = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let root = std::env::temp_dir().join(format!("codezilla_git_{}_{}", name, unique));
+        std::fs::create_dir_all(&root).unwrap();
+        git(&root, &["init", "-q", "-b", "main"]);
+        git(&root, &["config", "user.name", "Test"]);
+        git(&root, &["config", "user.email", "test@example.com"]);
+        root
+    }
+
+    /// Run git in `repo`, asserting success, and return stdout.
+    pub fn git(repo: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(repo)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
 
-    let mut file_stats: Vec<CommitFileStat> = Vec::new();
-    let mut additions: u32 = 0;
-    let mut deletions: u32 = 0;
-
-    if numstat_output.status.success() {
-        let numstat_str = String::from_utf8_lossy(&numstat_output.stdout);
-        for line in numstat_str.lines() {
-            let parts: Vec<&str> = line.split('\t').collect();
-            if parts.len() >= 3 {
-                let add = parts[0].parse::<u32>().unwrap_or(0);
-                let del = parts[1].parse::<u32>().unwrap_or(0);
-                let file = parts[2].to_string();
-                additions += add;
-                deletions += del;
-                file_stats.push(CommitFileStat {
-                    file,
-                    additions: add,
-                    deletions: del,
-                });
-            }
-        }
+    /// Write `content` to `file` and commit it.
+    pub fn commit_file(repo: &Path, file: &str, content: &str, message: &str) {
+        std::fs::write(repo.join(file), content).unwrap();
+        git(repo, &["add", "--", file]);
+        git(repo, &["commit", "-q", "-m", message]);
     }
+}
 
-    let files_changed = file_stats.len() as u32;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{commit_file, git, test_repo};
+
+    #[tokio::test]
+    async fn timed_out_query_kills_its_git() {
+        let repo = test_repo("query_deadline");
+        commit_file(&repo, "a.txt", "1\n", "init");
+        // A shell alias stands in for a slow git that writes once it's done.
+        let started = Instant::now();
+        let err = query_backend(repo.clone(), Duration::from_millis(200), |_, repo| {
+            run_git(repo, ["-c", "alias.slow=!sleep 1 && touch late", "slow"])
+        })
+        .await
+        .unwrap_err();
+        assert!(err.contains("timed out"), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(1));
 
-    Ok(CommitInfo {
-        hash,
-        author,
-        date,
-        subject,
-        body,
-        files_changed,
-        additions,
-        deletions,
-        file_stats,
-    })
-}
+        std::thread::sleep(Duration::from_millis(1500));
+        assert!(!repo.join("late").exists());
+        let _ = std::fs::remove_dir_all(repo);
+    }
 
-#[tauri::command]
-pub async fn get_commit_diff(repo_path: String, commit_ref: String) -> Result<String, String> {
-    validate_commit_ref(&commit_ref)?;
-    let canonical = crate::fs::canonicalize_path(&repo_path)?;
-    let repo = canonical.as_path();
-    if !repo.is_dir() {
-        return Err(format!("Not a directory: {}", repo_path));
+    #[test]
+    fn validates_revision_ranges() {
+        for ok in ["HEAD", "main..HEAD", "origin/feature-x...main", "v1.0^{commit}", "HEAD~3.."] {
+            assert!(validate_rev_range(ok).is_ok(), "{}", ok);
+        }
+        for bad in ["", "..", "--output=/tmp/x", "a..b..c", "main;rm", "HEAD:secret", "a b"] {
+            assert!(validate_rev_range(bad).is_err(), "{}", bad);
+        }
     }
 
-    let output = Command::new("git")
-        .args(["show", "--format=", &commit_ref])
-        .current_dir(repo)
-        .output()
-        .map_err(|e| format!("Failed to run git: {}", e))?;
+    #[tokio::test]
+    async fn commit_diff_accepts_ranges_of_slashed_branches() {
+        let repo = test_repo("commit_diff_range");
+        let root = repo.to_string_lossy().to_string();
+        commit_file(&repo, "a.txt", "1\n", "init");
+        git(&repo, &["switch", "--quiet", "--create", "agent/t"]);
+        commit_file(&repo, "a.txt", "2\n", "agent work");
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Not a valid commit: {}", stderr.trim()));
+        for range in ["main..agent/t", "main...agent/t"] {
+            let diff = get_commit_diff(root.clone(), range.into()).await.unwrap();
+            assert!(diff.contains("+2"), "{range}: {diff}");
+        }
+        let err = get_commit_info(root.clone(), "main..agent/t".into())
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Expected a single commit, not a range");
+        assert!(get_commit_diff(root, "--output=x..main".into())
+            .await
+            .is_err());
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn diff_stat_counts_untracked_files_of_a_subdirectory_project() {
+        let repo = test_repo("diff_stat_subdir");
+        commit_file(&repo, "README.md", "hi\n", "init");
+        std::fs::create_dir_all(repo.join("app")).unwrap();
+        std::fs::create_dir_all(repo.join("other")).unwrap();
+        std::fs::write(repo.join("app/new.txt"), "a\nb\nc\n").unwrap();
+        std::fs::write(repo.join("other/skip.txt"), "1\n2\n3\n4\n5\n").unwrap();
+
+        let stat = get_git_diff_stat(repo.join("app").to_string_lossy().to_string())
+            .await
+            .unwrap();
+        assert_eq!(stat, (3, 0));
+        let _ = std::fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn get_git_status_keeps_odd_filenames_intact() {
+        let repo = test_repo("status_v2");
+        commit_file(&repo, "tracked.txt", "one\n", "init");
+        std::fs::write(repo.join("tracked.txt"), "two\n").unwrap();
+        git(&repo, &["add", "tracked.txt"]);
+        std::fs::write(repo.join("tracked.txt"), "three\n").unwrap();
+        std::fs::write(repo.join("with space \"quoted\".txt"), "x").unwrap();
+
+        let status = get_git_status(repo.to_string_lossy().to_string()).await.unwrap();
+        assert_eq!(status.branch.head.as_deref(), Some("main"));
+        let tracked = status.entries.iter().find(|e| e.path == "tracked.txt").unwrap();
+        assert_eq!((tracked.index, tracked.worktree), (FileState::Modified, FileState::Modified));
+        assert!(status
+            .entries
+            .iter()
+            .any(|e| e.path == "with space \"quoted\".txt" && e.worktree == FileState::Untracked));
+        let _ = std::fs::remove_dir_all(repo);
     }
-
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
@@ -0,0 +1,278 @@
+//! Submodules and other git repos nested inside a project.
+//!
+//! A project's own `git status` shows a submodule as one modified entry and
+//! doesn't see independent repos under it at all (vendored checkouts,
+//! monorepos made of sub-repos). This module finds both and summarizes each
+//! on its own terms, so the UI can list them and point the status and diff
+//! commands at their paths.
+
+use super::remote::REMOTE_TIMEOUT;
+use super::state::take_snapshot;
+use super::types::{NestedRepo, NestedRepoKind, SubmoduleUpdateOptions};
+use super::{
+    git_failure, query_backend, repo_dir, run_git, run_git_with_env, BACKEND_TIMEOUT,
+    SUSPICIOUS_DIR_NAMES,
+};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How deep under the project to look for nested `.git` dirs.
+const NESTED_MAX_DEPTH: usize = 5;
+
+/// Submodule paths and recorded commits from `git ls-files --stage -z`:
+/// the gitlink (mode 160000) entries.
+fn parse_gitlinks(stdout: &str) -> Vec<(String, String)> {
+    stdout
+        .split('\0')
+        .filter_map(|record| {
+            let (meta, path) = record.split_once('\t')?;
+            let mut fields = meta.split(' ');
+            if fields.next()? != "160000" {
+                return None;
+            }
+            Some((path.to_string(), fields.next()?.to_string()))
+        })
+        .collect()
+}
+
+/// Summarize the repo at `path`. Uninitialized submodules have nothing to
+/// summarize beyond where they'd go.
+fn describe(
+    root: &Path,
+    path: &Path,
+    kind: NestedRepoKind,
+    recorded_commit: Option<String>,
+) -> Result<NestedRepo, String> {
+    let mut repo = NestedRepo {
+        path: path.to_string_lossy().to_string(),
+        relative_path: path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .to_string(),
+        kind,
+        recorded_commit,
+        initialized: path.join(".git").exists(),
+        head: None,
+        branch: None,
+        out_of_sync: false,
+        dirty: false,
+        added: 0,
+        removed: 0,
+    };
+    if !repo.initialized {
+        return Ok(repo);
+    }
+    let (snapshot, _) = take_snapshot(path)?;
+    repo.head = snapshot.status.branch.oid.clone();
+    repo.branch = snapshot.status.branch.head.clone();
+    repo.out_of_sync = repo.recorded_commit.is_some() && repo.head != repo.recorded_commit;
+    repo.dirty = !snapshot.status.entries.is_empty();
+    repo.added = snapshot.added;
+    repo.removed = snapshot.removed;
+    Ok(repo)
+}
+
+/// The submodules of `repo`, and of each checked-out submodule in turn.
+fn submodules(root: &Path, repo: &Path, found: &mut Vec<NestedRepo>) -> Result<(), String> {
+    let output = run_git(repo, ["ls-files", "--stage", "-z"])?;
+    if !output.status.success() {
+        return Err(git_failure("list submodules", &output));
+    }
+    for (path, commit) in parse_gitlinks(&String::from_utf8_lossy(&output.stdout)) {
+        let path = repo.join(path);
+        let submodule = describe(root, &path, NestedRepoKind::Submodule, Some(commit))?;
+        let initialized = submodule.initialized;
+        found.push(submodule);
+        if initialized {
+            submodules(root, &path, found)?;
+        }
+    }
+    Ok(())
+}
+
+/// Directories under `root` with a `.git` dir of their own. Gitignored dirs
+/// are searched too (vendored repos usually are ignored), but build and
+/// dependency output isn't, nor are checkouts with a `.git` file — linked
+/// worktrees and submodules, which are listed elsewhere.
+fn nested_repo_dirs(root: &Path) -> Vec<PathBuf> {
+    WalkBuilder::new(root)
+        .standard_filters(false)
+        .max_depth(Some(NESTED_MAX_DEPTH))
+        .filter_entry(|entry| {
+            if entry.depth() == 0 {
+                return true;
+            }
+            let name = entry.file_name().to_string_lossy();
+            name != ".git"
+                && !SUSPICIOUS_DIR_NAMES.contains(&name.as_ref())
+                && !entry.path().join(".git").is_file()
+        })
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 0 && entry.file_type().is_some_and(|t| t.is_dir()))
+        .map(|entry| entry.into_path())
+        .filter(|dir| dir.join(".git").is_dir())
+        .collect()
+}
+
+fn list(root: &Path) -> Result<Vec<NestedRepo>, String> {
+    let mut found = Vec::new();
+    submodules(root, root, &mut found)?;
+    // A submodule cloned before git moved module repos into `.git/modules`
+    // still has a `.git` dir; it's listed once, as a submodule.
+    let known: HashSet<PathBuf> = found.iter().map(|r| PathBuf::from(&r.path)).collect();
+    for dir in nested_repo_dirs(root) {
+        if !known.contains(&dir) {
+            found.push(describe(root, &dir, NestedRepoKind::Nested, None)?);
+        }
+    }
+    found.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(found)
+}
+
+/// Every submodule (recursively) and nested repo under `path`, each with its
+/// own branch, dirty state and uncommitted line counts.
+#[tauri::command]
+pub async fn list_nested_repos(path: String) -> Result<Vec<NestedRepo>, String> {
+    let root = repo_dir(&path)?;
+    query_backend(root, BACKEND_TIMEOUT, |_, root| list(root)).await
+}
+
+/// Register submodules in `.git/config` without checking them out. All of
+/// them when `paths` is empty.
+#[tauri::command]
+pub async fn init_submodules(
+    repo_path: String,
+    paths: Vec<String>,
+) -> Result<Vec<NestedRepo>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let mut args = vec!["submodule", "init", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("initialize submodules", &output));
+        }
+        list(repo)
+    })
+    .await
+}
+
+/// Check submodules out at their recorded commits, cloning them first if
+/// needed. Credentials are never prompted for: a clone that needs them fails.
+#[tauri::command]
+pub async fn update_submodules(
+    repo_path: String,
+    options: SubmoduleUpdateOptions,
+) -> Result<Vec<NestedRepo>, String> {
+    let repo = repo_dir(&repo_path)?;
+    let timeout = options
+        .timeout_secs
+        .map_or(REMOTE_TIMEOUT, Duration::from_secs);
+    query_backend(repo, timeout, move |_, repo| {
+        let mut args = vec!["submodule", "update"];
+        if options.init {
+            args.push("--init");
+        }
+        if options.recursive {
+            args.push("--recursive");
+        }
+        args.push("--");
+        args.extend(options.paths.iter().map(String::as_str));
+        let output = run_git_with_env(
+            repo,
+            &args,
+            &[
+                ("GIT_TERMINAL_PROMPT", OsStr::new("0")),
+                ("GCM_INTERACTIVE", OsStr::new("never")),
+            ],
+        )?;
+        if !output.status.success() {
+            return Err(git_failure("update submodules", &output));
+        }
+        list(repo)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    #[test]
+    fn parses_gitlinks() {
+        let out = "100644 aaaa 0\t.gitmodules\x00160000 bbbb 0\tlibs/a b\0";
+        assert_eq!(
+            parse_gitlinks(out),
+            [("libs/a b".to_string(), "bbbb".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_submodules_and_nested_repos() {
+        let upstream = test_repo("nested_upstream");
+        commit_file(&upstream, "lib.txt", "lib\n", "lib");
+        let repo = test_repo("nested");
+        commit_file(&repo, "a.txt", "a\n", "init");
+        git(
+            &repo,
+            &[
+                "-c",
+                "protocol.file.allow=always",
+                "submodule",
+                "add",
+                "-q",
+                &upstream.to_string_lossy(),
+                "libs/sub",
+            ],
+        );
+        git(&repo, &["commit", "-q", "-m", "add submodule"]);
+        fs::write(repo.join("libs/sub/lib.txt"), "lib\nmore\n").unwrap();
+
+        let vendored = repo.join("vendor/tool");
+        fs::create_dir_all(&vendored).unwrap();
+        git(&vendored, &["init", "-q", "-b", "trunk"]);
+        git(&vendored, &["config", "user.name", "Test"]);
+        git(&vendored, &["config", "user.email", "test@example.com"]);
+        commit_file(&vendored, "t.txt", "t\n", "tool");
+        // Build output is never searched.
+        fs::create_dir_all(repo.join("node_modules/pkg/.git")).unwrap();
+
+        let repo_path = repo.to_string_lossy().to_string();
+        let found = list_nested_repos(repo_path.clone()).await.unwrap();
+        let paths: Vec<_> = found.iter().map(|r| r.relative_path.as_str()).collect();
+        assert_eq!(paths, ["libs/sub", "vendor/tool"]);
+
+        let sub = &found[0];
+        assert_eq!(sub.kind, NestedRepoKind::Submodule);
+        assert!(sub.initialized && sub.dirty && !sub.out_of_sync);
+        assert_eq!(sub.added, 1);
+        let tool = &found[1];
+        assert_eq!(tool.kind, NestedRepoKind::Nested);
+        assert_eq!(tool.branch.as_deref(), Some("trunk"));
+        assert!(!tool.dirty && tool.recorded_commit.is_none());
+
+        // Deinit removes the checkout but keeps the module repo, so update
+        // restores it without cloning.
+        git(&repo, &["submodule", "deinit", "-q", "-f", "libs/sub"]);
+        let found = list_nested_repos(repo_path.clone()).await.unwrap();
+        assert!(!found[0].initialized && found[0].head.is_none());
+        let options = SubmoduleUpdateOptions {
+            init: true,
+            ..Default::default()
+        };
+        let found = update_submodules(repo_path, options).await.unwrap();
+        assert!(found[0].initialized && !found[0].dirty);
+        assert_eq!(found[0].head, found[0].recorded_commit);
+        assert_eq!(found[0].branch, None);
+
+        let _ = fs::remove_dir_all(repo);
+        let _ = fs::remove_dir_all(upstream);
+    }
+}
@@ -0,0 +1,371 @@
+//! Getting a range of commits out of the repo: as patches for `git am`, or
+//! as a summary of what it changes for a pull request description.
+//!
+//! Ranges are read the way a pull request is: `<base>..<head>` is the
+//! commits on `head` that `base` lacks, and their changes are diffed from
+//! the merge base. A single revision means `<rev>..HEAD`.
+
+use super::cli::parse_numstat;
+use super::types::{
+    ChangeSummary, DirectoryChanges, FileDiffStat, PatchExport, PatchOutput, SummaryCommit,
+    SummaryFormat,
+};
+use super::{git_failure, query_backend, repo_dir, run_git, validate_rev_range, BACKEND_TIMEOUT};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Split a range into its base and head, either defaulting to HEAD.
+fn range_ends(range: &str) -> Result<(String, String), String> {
+    validate_rev_range(range)?;
+    let (base, head) = range
+        .split_once("...")
+        .or_else(|| range.split_once(".."))
+        .unwrap_or((range, ""));
+    let or_head = |side: &str| {
+        if side.is_empty() {
+            "HEAD".to_string()
+        } else {
+            side.to_string()
+        }
+    };
+    Ok((or_head(base), or_head(head)))
+}
+
+/// Commits in an mbox: each starts with git's fixed `From <sha> <date>` line.
+fn count_mbox_commits(mbox: &str) -> u32 {
+    mbox.lines()
+        .filter(|line| line.starts_with("From ") && line.ends_with("Mon Sep 17 00:00:00 2001"))
+        .count() as u32
+}
+
+fn export(repo: &Path, range: &str, output: &PatchOutput) -> Result<PatchExport, String> {
+    let (base, head) = range_ends(range)?;
+    let revs = format!("{}..{}", base, head);
+    match output {
+        PatchOutput::Series { dir } => {
+            let dir = Path::new(dir);
+            if !dir.is_absolute() {
+                return Err(format!("Not an absolute path: {}", dir.display()));
+            }
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            let out = run_git(
+                repo,
+                [
+                    OsStr::new("format-patch"),
+                    OsStr::new("-o"),
+                    dir.as_os_str(),
+                    OsStr::new(&revs),
+                ],
+            )?;
+            if !out.status.success() {
+                return Err(git_failure("export patches", &out));
+            }
+            // format-patch prints each file it writes.
+            let names: Vec<String> = String::from_utf8_lossy(&out.stdout)
+                .lines()
+                .filter_map(|line| Path::new(line).file_name())
+                .map(|name| dir.join(name).to_string_lossy().to_string())
+                .collect();
+            if names.is_empty() {
+                return Err(format!("No commits in {}", range));
+            }
+            Ok(PatchExport {
+                commits: names.len() as u32,
+                files: names,
+            })
+        }
+        PatchOutput::Mbox { path } => {
+            let path = Path::new(path);
+            if !path.is_absolute() {
+                return Err(format!("Not an absolute path: {}", path.display()));
+            }
+            let out = run_git(repo, ["format-patch", "--stdout", &revs])?;
+            if !out.status.success() {
+                return Err(git_failure("export patches", &out));
+            }
+            let commits = count_mbox_commits(&String::from_utf8_lossy(&out.stdout));
+            if commits == 0 {
+                return Err(format!("No commits in {}", range));
+            }
+            std::fs::write(path, &out.stdout)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            Ok(PatchExport {
+                files: vec![path.to_string_lossy().to_string()],
+                commits,
+            })
+        }
+    }
+}
+
+/// Write the commits in `range` as `git format-patch` output: a numbered
+/// series of files, or one mbox. Merge commits are left out, as
+/// `format-patch` does.
+#[tauri::command]
+pub async fn export_patches(
+    repo_path: String,
+    range: String,
+    output: PatchOutput,
+) -> Result<PatchExport, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        export(repo, &range, &output)
+    })
+    .await
+}
+
+/// Group per-file stats by parent directory, both sorted by path.
+fn group_by_directory(stats: Vec<FileDiffStat>) -> Vec<DirectoryChanges> {
+    let mut dirs: BTreeMap<String, Vec<FileDiffStat>> = BTreeMap::new();
+    for stat in stats {
+        let dir = match stat.path.rsplit_once('/') {
+            Some((dir, _)) => dir.to_string(),
+            None => ".".to_string(),
+        };
+        dirs.entry(dir).or_default().push(stat);
+    }
+    dirs.into_iter()
+        .map(|(dir, mut files)| {
+            files.sort_by(|a, b| a.path.cmp(&b.path));
+            DirectoryChanges {
+                dir,
+                added: files.iter().map(|f| f.added).sum(),
+                removed: files.iter().map(|f| f.removed).sum(),
+                files,
+            }
+        })
+        .collect()
+}
+
+fn summarize(repo: &Path, range: &str) -> Result<ChangeSummary, String> {
+    let (base, head) = range_ends(range)?;
+    let log = run_git(
+        repo,
+        [
+            "log",
+            "--reverse",
+            "--format=%H%x1f%h%x1f%an%x1f%s",
+            &format!("{}..{}", base, head),
+            "--",
+        ],
+    )?;
+    if !log.status.success() {
+        return Err(git_failure("list the commits", &log));
+    }
+    let commits = String::from_utf8_lossy(&log.stdout)
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.splitn(4, '\x1f').collect();
+            let [hash, short_hash, author, subject] = fields[..] else {
+                return None;
+            };
+            Some(SummaryCommit {
+                hash: hash.to_string(),
+                short_hash: short_hash.to_string(),
+                author: author.to_string(),
+                subject: subject.to_string(),
+            })
+        })
+        .collect();
+
+    let numstat = run_git(
+        repo,
+        [
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--numstat",
+            "-z",
+            "--no-renames",
+            &format!("{}...{}", base, head),
+            "--",
+        ],
+    )?;
+    if !numstat.status.success() {
+        return Err(git_failure("diff", &numstat));
+    }
+    let stats = parse_numstat(&String::from_utf8_lossy(&numstat.stdout));
+    Ok(ChangeSummary {
+        commits,
+        files_changed: stats.len() as u32,
+        added: stats.iter().map(|s| s.added).sum(),
+        removed: stats.iter().map(|s| s.removed).sum(),
+        directories: group_by_directory(stats),
+    })
+}
+
+fn plural(count: u32, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+/// The summary as Markdown for a pull request description: totals, the
+/// commit list, then the files under each directory.
+fn render_markdown(summary: &ChangeSummary) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}, {} changed, +{} −{}",
+        plural(summary.commits.len() as u32, "commit"),
+        plural(summary.files_changed, "file"),
+        summary.added,
+        summary.removed
+    );
+    if !summary.commits.is_empty() {
+        out.push_str("\n### Commits\n\n");
+        for commit in &summary.commits {
+            let _ = writeln!(out, "- `{}` {}", commit.short_hash, commit.subject);
+        }
+    }
+    if !summary.directories.is_empty() {
+        out.push_str("\n### Files\n");
+        for dir in &summary.directories {
+            let _ = writeln!(out, "\n**{}** (+{} −{})\n", dir.dir, dir.added, dir.removed);
+            for file in &dir.files {
+                let name = file.path.rsplit('/').next().unwrap_or(&file.path);
+                let _ = writeln!(out, "- `{}` +{} −{}", name, file.added, file.removed);
+            }
+        }
+    }
+    out
+}
+
+/// What `range` changes: its commits, and line counts per file grouped by
+/// directory, with totals.
+#[tauri::command]
+pub async fn get_change_summary(repo_path: String, range: String) -> Result<ChangeSummary, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        summarize(repo, &range)
+    })
+    .await
+}
+
+/// [`get_change_summary`] as text to copy out: pretty-printed JSON, or
+/// Markdown for a pull request description.
+#[tauri::command]
+pub async fn export_change_summary(
+    repo_path: String,
+    range: String,
+    format: SummaryFormat,
+) -> Result<String, String> {
+    let summary = get_change_summary(repo_path, range).await?;
+    match format {
+        SummaryFormat::Json => serde_json::to_string_pretty(&summary)
+            .map_err(|e| format!("Failed to serialize the summary: {}", e)),
+        SummaryFormat::Markdown => Ok(render_markdown(&summary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    #[test]
+    fn reads_ranges_like_a_pull_request() {
+        let ends = |r: &str| range_ends(r).unwrap();
+        assert_eq!(ends("main..topic"), ("main".into(), "topic".into()));
+        assert_eq!(ends("main...topic"), ("main".into(), "topic".into()));
+        assert_eq!(ends("origin/main"), ("origin/main".into(), "HEAD".into()));
+        assert_eq!(ends("main.."), ("main".into(), "HEAD".into()));
+        assert!(range_ends("--output=x").is_err());
+    }
+
+    #[tokio::test]
+    async fn exports_patches_and_summaries() {
+        let repo = test_repo("patches");
+        commit_file(&repo, "README.md", "hi\n", "init");
+        git(&repo, &["checkout", "-q", "-b", "topic"]);
+        fs::create_dir_all(repo.join("src")).unwrap();
+        commit_file(&repo, "src/a.rs", "a\nb\n", "Add a");
+        commit_file(&repo, "README.md", "hello\n", "Reword readme");
+        let repo_path = repo.to_string_lossy().to_string();
+
+        let out = repo.with_file_name(format!(
+            "{}-patches",
+            repo.file_name().unwrap().to_string_lossy()
+        ));
+        let series = export_patches(
+            repo_path.clone(),
+            "main..topic".into(),
+            PatchOutput::Series {
+                dir: out.to_string_lossy().to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(series.commits, 2);
+        assert!(
+            series.files[0].ends_with("0001-Add-a.patch"),
+            "{:?}",
+            series
+        );
+        assert!(series.files.iter().all(|f| Path::new(f).is_file()));
+
+        let mbox = out.join("all.mbox");
+        let export = export_patches(
+            repo_path.clone(),
+            "main".into(),
+            PatchOutput::Mbox {
+                path: mbox.to_string_lossy().to_string(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(export.commits, 2);
+        assert!(fs::read_to_string(&mbox)
+            .unwrap()
+            .contains("Subject: [PATCH 2/2] Reword readme"));
+        let err = export_patches(
+            repo_path.clone(),
+            "topic..main".into(),
+            PatchOutput::Mbox {
+                path: mbox.to_string_lossy().to_string(),
+            },
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err, "No commits in topic..main");
+
+        let summary = get_change_summary(repo_path.clone(), "main..topic".into())
+            .await
+            .unwrap();
+        let subjects: Vec<_> = summary.commits.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["Add a", "Reword readme"]);
+        assert_eq!(
+            (summary.files_changed, summary.added, summary.removed),
+            (2, 3, 1)
+        );
+        let dirs: Vec<_> = summary.directories.iter().map(|d| d.dir.as_str()).collect();
+        assert_eq!(dirs, [".", "src"]);
+
+        let markdown = export_change_summary(
+            repo_path.clone(),
+            "main..topic".into(),
+            SummaryFormat::Markdown,
+        )
+        .await
+        .unwrap();
+        assert!(
+            markdown.starts_with("2 commits, 2 files changed, +3 −1\n"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("\n**src** (+2 −0)\n\n- `a.rs` +2 −0\n"),
+            "{}",
+            markdown
+        );
+        let json = export_change_summary(repo_path, "main..topic".into(), SummaryFormat::Json)
+            .await
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["files_changed"], 2);
+
+        let _ = fs::remove_dir_all(repo);
+        let _ = fs::remove_dir_all(out);
+    }
+}
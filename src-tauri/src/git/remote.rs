@@ -0,0 +1,552 @@
+//! Fetch, pull and push.
+//!
+//! These are the only git commands that talk to the network, so they run
+//! differently from the rest: git's `--progress` output is parsed off stderr
+//! and streamed as [`RemoteEvent`]s over a `Channel`, each operation has a
+//! caller-chosen id it can be cancelled by (see [`cancel_remote_operation`]),
+//! and a deadline kills it rather than leaving it to hang. Git never prompts
+//! for credentials here — there's no terminal to answer on — so an
+//! authentication failure comes back as an error saying so.
+
+use super::types::{FetchOptions, PullMode, PullOptions, PushOptions, RemoteEvent};
+use super::{repo_dir, unmerged_paths, validate_rev};
+use std::collections::HashMap;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::ipc::Channel;
+
+/// Cancel flags for in-flight remote operations, keyed by the caller-chosen
+/// operation id.
+pub type RemoteOpState = Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>;
+
+/// How long a remote operation may run when the caller doesn't say.
+pub(crate) const REMOTE_TIMEOUT: Duration = Duration::from_secs(300);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// stderr fragments meaning git needed credentials it couldn't ask for.
+const AUTH_FAILURES: [&str; 6] = [
+    "terminal prompts disabled",
+    "could not read Username",
+    "could not read Password",
+    "Authentication failed",
+    "Permission denied (publickey",
+    "Host key verification failed",
+];
+
+/// A `<phase>: NN% (current/total)` progress line, with any `remote: `
+/// prefix dropped.
+fn parse_progress(line: &str) -> Option<RemoteEvent> {
+    let line = line.strip_prefix("remote: ").unwrap_or(line);
+    let (phase, rest) = line.split_once(": ")?;
+    let (percent, rest) = rest.trim_start().split_once('%')?;
+    let counts = rest.trim_start().strip_prefix('(')?;
+    let (counts, _) = counts.split_once(')')?;
+    let (current, total) = counts.split_once('/')?;
+    Some(RemoteEvent::Progress {
+        phase: phase.trim().to_string(),
+        percent: percent.parse().ok()?,
+        current: current.parse().ok()?,
+        total: total.parse().ok()?,
+    })
+}
+
+/// Explain a failed remote operation from git's stderr (progress lines
+/// already stripped).
+fn remote_failure(action: &str, stderr: &str) -> String {
+    if AUTH_FAILURES.iter().any(|s| stderr.contains(s)) {
+        return format!(
+            "Could not {}: the remote needs credentials. Set up a credential helper or \
+             SSH key so git can authenticate without prompting, then retry.",
+            action
+        );
+    }
+    if stderr.contains("stale info") {
+        return format!(
+            "Could not {}: the remote branch has moved since it was last fetched. \
+             Fetch and review it before forcing.",
+            action
+        );
+    }
+    let rejected = stderr.contains("[rejected]") || stderr.contains("[remote rejected]");
+    if rejected && (stderr.contains("non-fast-forward") || stderr.contains("fetch first")) {
+        return format!(
+            "Could not {}: the remote has commits this branch doesn't. \
+             Pull first, or push with force-with-lease.",
+            action
+        );
+    }
+    if stderr.contains("Not possible to fast-forward") {
+        return format!(
+            "Could not {}: the branches have diverged, so a fast-forward isn't possible. \
+             Pull with rebase instead.",
+            action
+        );
+    }
+    format!("Could not {}: {}", action, stderr.trim())
+}
+
+/// Stop the operation now, along with any helpers it started (ssh,
+/// `git-remote-https`) that would otherwise hold its pipes open.
+#[cfg(unix)]
+pub(crate) fn kill_operation(child: &mut Child) {
+    // Spawned as its own process group, so this reaches the helpers too.
+    unsafe {
+        libc::kill(-(child.id() as i32), libc::SIGKILL);
+    }
+    let _ = child.kill();
+}
+
+#[cfg(not(unix))]
+pub(crate) fn kill_operation(child: &mut Child) {
+    let _ = child.kill();
+}
+
+/// Run a remote `git <args>` in `repo`, streaming progress and other stderr
+/// lines through `emit`, until it exits, `cancel` is set or `timeout` passes.
+fn run_remote(
+    repo: &Path,
+    args: &[String],
+    action: &str,
+    cancel: &AtomicBool,
+    timeout: Duration,
+    emit: &(dyn Fn(RemoteEvent) + Send + Sync),
+) -> Result<(), String> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err("Cancelled".to_string());
+    }
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(repo)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        command.process_group(0);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    std::thread::scope(|scope| {
+        // Nothing useful comes on stdout; drain it so git never blocks.
+        scope.spawn(move || {
+            if let Some(mut stdout) = stdout {
+                let _ = std::io::copy(&mut stdout, &mut std::io::sink());
+            }
+        });
+        // Progress lines end in `\r` as they update, everything else in `\n`.
+        let messages = scope.spawn(move || {
+            let mut messages = String::new();
+            let Some(stderr) = stderr else {
+                return messages;
+            };
+            let mut reader = BufReader::new(stderr);
+            let mut line = Vec::new();
+            let mut byte = [0u8; 1];
+            while reader.read(&mut byte).map(|n| n > 0).unwrap_or(false) {
+                if byte[0] != b'\r' && byte[0] != b'\n' {
+                    line.push(byte[0]);
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&line).trim_end().to_string();
+                line.clear();
+                if text.is_empty() {
+                    continue;
+                }
+                match parse_progress(&text) {
+                    Some(progress) => emit(progress),
+                    None => {
+                        messages.push_str(&text);
+                        messages.push('\n');
+                        emit(RemoteEvent::Message(text));
+                    }
+                }
+            }
+            messages
+        });
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) if cancel.load(Ordering::Relaxed) => {
+                    kill_operation(&mut child);
+                    let _ = child.wait();
+                    break Err("Cancelled".to_string());
+                }
+                Ok(None) if Instant::now() >= deadline => {
+                    kill_operation(&mut child);
+                    let _ = child.wait();
+                    break Err(format!(
+                        "Could not {}: timed out after {}s",
+                        action,
+                        timeout.as_secs()
+                    ));
+                }
+                Ok(None) => std::thread::sleep(POLL_INTERVAL),
+                Err(e) => break Err(format!("Failed to run git: {}", e)),
+            }
+        };
+        let messages = messages.join().unwrap_or_default();
+        if status?.success() {
+            Ok(())
+        } else {
+            Err(remote_failure(action, &messages))
+        }
+    })
+}
+
+/// A remote or branch name: the same characters as a revision.
+fn validate_name(name: &Option<String>) -> Result<(), String> {
+    name.as_deref().map_or(Ok(()), validate_rev)
+}
+
+fn fetch_args(options: &FetchOptions) -> Result<Vec<String>, String> {
+    validate_name(&options.remote)?;
+    let mut args = vec!["fetch".to_string(), "--progress".to_string()];
+    if options.prune {
+        args.push("--prune".into());
+    }
+    if options.all {
+        args.push("--all".into());
+    } else {
+        args.extend(options.remote.clone());
+    }
+    Ok(args)
+}
+
+fn pull_args(options: &PullOptions) -> Result<Vec<String>, String> {
+    validate_name(&options.remote)?;
+    validate_name(&options.branch)?;
+    if options.branch.is_some() && options.remote.is_none() {
+        return Err("Pulling a branch needs a remote".to_string());
+    }
+    let mut args = vec!["pull".to_string(), "--progress".to_string()];
+    args.push(match options.mode {
+        PullMode::FastForwardOnly => "--ff-only".into(),
+        PullMode::Rebase => "--rebase".into(),
+    });
+    args.extend(options.remote.clone());
+    args.extend(options.branch.clone());
+    Ok(args)
+}
+
+fn push_args(options: &PushOptions) -> Result<Vec<String>, String> {
+    validate_name(&options.remote)?;
+    validate_name(&options.branch)?;
+    let mut args = vec!["push".to_string(), "--progress".to_string()];
+    if options.set_upstream {
+        args.push("--set-upstream".into());
+    }
+    if options.force_with_lease {
+        args.push("--force-with-lease".into());
+    }
+    // --set-upstream needs an explicit destination.
+    let remote = match (
+        &options.remote,
+        options.set_upstream || options.branch.is_some(),
+    ) {
+        (Some(remote), _) => Some(remote.clone()),
+        (None, true) => Some("origin".to_string()),
+        (None, false) => None,
+    };
+    if let Some(remote) = remote {
+        args.push(remote);
+        args.push(options.branch.clone().unwrap_or_else(|| "HEAD".into()));
+    }
+    Ok(args)
+}
+
+/// Register a cancel flag under `op_id`, run `git <args>` on the blocking
+/// pool, and unregister it.
+async fn run_operation(
+    ops: RemoteOpState,
+    repo: PathBuf,
+    op_id: String,
+    args: Vec<String>,
+    action: &'static str,
+    timeout_secs: Option<u64>,
+    channel: Channel<RemoteEvent>,
+) -> Result<(), String> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut ops = ops.lock().map_err(|e| format!("Lock error: {}", e))?;
+        if ops.contains_key(&op_id) {
+            return Err(format!("Operation {} is already running", op_id));
+        }
+        ops.insert(op_id.clone(), cancel.clone());
+    }
+    let timeout = timeout_secs.map_or(REMOTE_TIMEOUT, Duration::from_secs);
+    let result = tokio::task::spawn_blocking(move || {
+        let result = run_remote(&repo, &args, action, &cancel, timeout, &|event| {
+            let _ = channel.send(event);
+        });
+        // A pull that stopped on conflicts leaves a merge or rebase for the
+        // conflicts view to finish.
+        match result {
+            Err(e) if action == "pull" => match unmerged_paths(&repo) {
+                Ok(conflicted) if !conflicted.is_empty() => Err(format!(
+                    "Pull stopped on conflicts in {} file(s). Resolve them and continue, or abort.",
+                    conflicted.len()
+                )),
+                _ => Err(e),
+            },
+            other => other,
+        }
+    })
+    .await
+    .map_err(|e| format!("git task failed: {}", e));
+    if let Ok(mut ops) = ops.lock() {
+        ops.remove(&op_id);
+    }
+    result?
+}
+
+/// Fetch from a remote (the current branch's, by default) or all of them.
+#[tauri::command]
+pub async fn git_fetch(
+    state: tauri::State<'_, RemoteOpState>,
+    repo_path: String,
+    op_id: String,
+    options: FetchOptions,
+    channel: Channel<RemoteEvent>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    let args = fetch_args(&options)?;
+    let ops = state.inner().clone();
+    run_operation(
+        ops,
+        repo,
+        op_id,
+        args,
+        "fetch",
+        options.timeout_secs,
+        channel,
+    )
+    .await
+}
+
+/// Pull into the current branch, fast-forward only unless rebasing.
+#[tauri::command]
+pub async fn git_pull(
+    state: tauri::State<'_, RemoteOpState>,
+    repo_path: String,
+    op_id: String,
+    options: PullOptions,
+    channel: Channel<RemoteEvent>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    let args = pull_args(&options)?;
+    let ops = state.inner().clone();
+    run_operation(
+        ops,
+        repo,
+        op_id,
+        args,
+        "pull",
+        options.timeout_secs,
+        channel,
+    )
+    .await
+}
+
+/// Push the current branch (or `branch`). With `set_upstream` and no remote,
+/// pushes to `origin`.
+#[tauri::command]
+pub async fn git_push(
+    state: tauri::State<'_, RemoteOpState>,
+    repo_path: String,
+    op_id: String,
+    options: PushOptions,
+    channel: Channel<RemoteEvent>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    let args = push_args(&options)?;
+    let ops = state.inner().clone();
+    run_operation(
+        ops,
+        repo,
+        op_id,
+        args,
+        "push",
+        options.timeout_secs,
+        channel,
+    )
+    .await
+}
+
+/// Ask an in-flight fetch, pull or push to stop. A no-op if it already
+/// finished.
+#[tauri::command]
+pub fn cancel_remote_operation(
+    state: tauri::State<'_, RemoteOpState>,
+    op_id: String,
+) -> Result<(), String> {
+    let ops = state.lock().map_err(|e| format!("Lock error: {}", e))?;
+    if let Some(cancel) = ops.get(&op_id) {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    fn run(repo: &Path, args: Vec<String>, action: &str) -> Result<Vec<RemoteEvent>, String> {
+        let events = Mutex::new(Vec::new());
+        run_remote(
+            repo,
+            &args,
+            action,
+            &AtomicBool::new(false),
+            REMOTE_TIMEOUT,
+            &|e| events.lock().unwrap().push(e),
+        )?;
+        Ok(events.into_inner().unwrap())
+    }
+
+    fn clone(remote: &Path, name: &str) -> PathBuf {
+        let dir = remote.with_file_name(format!(
+            "{}-{}",
+            remote.file_name().unwrap().to_string_lossy(),
+            name
+        ));
+        git(
+            remote.parent().unwrap(),
+            &[
+                "clone",
+                "-q",
+                &remote.to_string_lossy(),
+                &dir.to_string_lossy(),
+            ],
+        );
+        git(&dir, &["config", "user.name", "Test"]);
+        git(&dir, &["config", "user.email", "test@example.com"]);
+        dir
+    }
+
+    #[test]
+    fn parses_progress_lines() {
+        match parse_progress("remote: Counting objects:  50% (2/4)") {
+            Some(RemoteEvent::Progress {
+                phase,
+                percent,
+                current,
+                total,
+            }) => {
+                assert_eq!(
+                    (phase.as_str(), percent, current, total),
+                    ("Counting objects", 50, 2, 4)
+                );
+            }
+            other => panic!("unexpected: {:?}", other),
+        }
+        assert!(
+            parse_progress("Writing objects: 100% (3/3), 220 bytes | 220.00 KiB/s, done.")
+                .is_some()
+        );
+        assert!(parse_progress("To /tmp/remote.git").is_none());
+        assert!(parse_progress(" * [new branch]      main -> main").is_none());
+    }
+
+    #[test]
+    fn explains_credential_failures() {
+        let err = remote_failure(
+            "fetch",
+            "fatal: could not read Username for 'https://example.com': terminal prompts disabled",
+        );
+        assert!(err.contains("needs credentials"), "{}", err);
+    }
+
+    #[test]
+    fn builds_push_args() {
+        let args = push_args(&PushOptions {
+            set_upstream: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            args,
+            ["push", "--progress", "--set-upstream", "origin", "HEAD"]
+        );
+        let bad = PushOptions {
+            remote: Some("--exec=evil".into()),
+            ..Default::default()
+        };
+        assert!(push_args(&bad).is_err());
+    }
+
+    #[tokio::test]
+    async fn pushes_fetches_and_pulls_against_a_bare_remote() {
+        let remote = test_repo("remote_bare");
+        fs::remove_dir_all(&remote).unwrap();
+        fs::create_dir_all(&remote).unwrap();
+        git(&remote, &["init", "-q", "--bare", "-b", "main"]);
+
+        let a = clone(&remote, "a");
+        commit_file(&a, "f.txt", "one\n", "init");
+        let push_upstream = PushOptions {
+            set_upstream: true,
+            ..Default::default()
+        };
+        run(&a, push_args(&push_upstream).unwrap(), "push").unwrap();
+        assert_eq!(
+            git(&a, &["rev-parse", "--abbrev-ref", "@{u}"]).trim(),
+            "origin/main"
+        );
+
+        let b = clone(&remote, "b");
+        commit_file(&a, "f.txt", "two\n", "second");
+        run(&a, push_args(&PushOptions::default()).unwrap(), "push").unwrap();
+
+        let events = run(&b, fetch_args(&FetchOptions::default()).unwrap(), "fetch").unwrap();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, RemoteEvent::Message(m) if m.contains("main"))));
+        run(&b, pull_args(&PullOptions::default()).unwrap(), "pull").unwrap();
+        assert_eq!(fs::read_to_string(b.join("f.txt")).unwrap(), "two\n");
+
+        // Diverge: b's push is rejected until forced with a fresh lease.
+        commit_file(&a, "f.txt", "three\n", "from a");
+        run(&a, push_args(&PushOptions::default()).unwrap(), "push").unwrap();
+        commit_file(&b, "g.txt", "b\n", "from b");
+        let err = run(&b, push_args(&PushOptions::default()).unwrap(), "push").unwrap_err();
+        assert!(err.contains("Pull first"), "{}", err);
+        let err = run(&b, pull_args(&PullOptions::default()).unwrap(), "pull").unwrap_err();
+        assert!(err.contains("diverged"), "{}", err);
+        let rebase = PullOptions {
+            mode: PullMode::Rebase,
+            ..Default::default()
+        };
+        run(&b, pull_args(&rebase).unwrap(), "pull").unwrap();
+        run(&b, push_args(&PushOptions::default()).unwrap(), "push").unwrap();
+
+        let cancelled = run_remote(
+            &a,
+            &fetch_args(&FetchOptions::default()).unwrap(),
+            "fetch",
+            &AtomicBool::new(true),
+            REMOTE_TIMEOUT,
+            &|_| {},
+        );
+        assert_eq!(cancelled.unwrap_err(), "Cancelled");
+
+        for dir in [a, b, remote] {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
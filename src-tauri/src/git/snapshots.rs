@@ -0,0 +1,299 @@
+//! Per-thread snapshots: what a thread's worktree looked like when the
+//! thread began, so the UI can show the changes made since.
+//!
+//! A snapshot is a commit under `refs/codezilla/threads/<thread_id>` whose
+//! tree is the working tree at the time — staged, unstaged and untracked
+//! files alike, as `compare` snapshots a working tree — with HEAD as its
+//! parent. Blobs are shared with the rest of the repo, so it costs little,
+//! and the ref keeps it from being garbage collected until the thread is
+//! removed.
+
+use super::cli::parse_numstat;
+use super::compare::working_tree_id;
+use super::diff::{diff_args, run_diff};
+use super::types::{DiffFile, DiffOptions, FileDiffStat, ThreadSnapshot};
+use super::{
+    git_failure, has_head, query_backend, repo_dir, run_git, run_git_with_env, BACKEND_TIMEOUT,
+};
+use std::ffi::OsStr;
+use std::path::Path;
+
+const SNAPSHOT_REF_PREFIX: &str = "refs/codezilla/threads/";
+
+/// Snapshot commits are ours, not the user's: they get a fixed identity,
+/// which also means they work in a repo with no `user.name` set.
+const SNAPSHOT_IDENTITY: [(&str, &str); 4] = [
+    ("GIT_AUTHOR_NAME", "Codezilla"),
+    ("GIT_AUTHOR_EMAIL", "codezilla@localhost"),
+    ("GIT_COMMITTER_NAME", "Codezilla"),
+    ("GIT_COMMITTER_EMAIL", "codezilla@localhost"),
+];
+
+/// The ref holding `thread_id`'s snapshot. Thread ids are UUIDs; anything
+/// else that couldn't be a ref name component is refused.
+fn snapshot_ref(thread_id: &str) -> Result<String, String> {
+    let valid = !thread_id.is_empty()
+        && thread_id.len() <= 64
+        && thread_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if !valid {
+        return Err(format!("Invalid thread id: {}", thread_id));
+    }
+    Ok(format!("{}{}", SNAPSHOT_REF_PREFIX, thread_id))
+}
+
+/// The snapshot recorded for `thread_id`, if there is one.
+fn read_snapshot(repo: &Path, thread_id: &str) -> Result<Option<ThreadSnapshot>, String> {
+    let name = snapshot_ref(thread_id)?;
+    let output = run_git(
+        repo,
+        ["show", "-s", "--format=%H%x1f%P%x1f%cI", &name, "--"],
+    )?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let fields: Vec<&str> = stdout.trim_end().splitn(3, '\x1f').collect();
+    let [commit, parents, created_at] = fields[..] else {
+        return Ok(None);
+    };
+    Ok(Some(ThreadSnapshot {
+        thread_id: thread_id.to_string(),
+        commit: commit.to_string(),
+        head: parents
+            .split(' ')
+            .next()
+            .filter(|p| !p.is_empty())
+            .map(str::to_string),
+        created_at: created_at.to_string(),
+    }))
+}
+
+fn create(repo: &Path, thread_id: &str) -> Result<ThreadSnapshot, String> {
+    if let Some(existing) = read_snapshot(repo, thread_id)? {
+        return Ok(existing);
+    }
+    let name = snapshot_ref(thread_id)?;
+    let tree = working_tree_id(repo)?;
+    let message = format!("Snapshot at the start of thread {}", thread_id);
+    let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+    if has_head(repo) {
+        args.extend(["-p", "HEAD"]);
+    }
+    let env = SNAPSHOT_IDENTITY.map(|(key, value)| (key, OsStr::new(value)));
+    let output = run_git_with_env(repo, &args, &env)?;
+    if !output.status.success() {
+        return Err(git_failure("snapshot the working tree", &output));
+    }
+    let commit = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    // An empty old value makes this fail if the ref appeared meanwhile; the
+    // snapshot that got there first wins.
+    let output = run_git(repo, ["update-ref", &name, &commit, ""])?;
+    if !output.status.success() {
+        if let Some(existing) = read_snapshot(repo, thread_id)? {
+            return Ok(existing);
+        }
+        return Err(git_failure("save the snapshot", &output));
+    }
+    read_snapshot(repo, thread_id)?.ok_or_else(|| "Could not read the snapshot back".to_string())
+}
+
+/// `thread_id`'s snapshot tree and the working tree's now, or an error when
+/// the thread has no snapshot.
+fn endpoints(repo: &Path, thread_id: &str) -> Result<[String; 2], String> {
+    let snapshot = read_snapshot(repo, thread_id)?
+        .ok_or_else(|| format!("No snapshot for thread {}", thread_id))?;
+    Ok([
+        format!("{}^{{tree}}", snapshot.commit),
+        working_tree_id(repo)?,
+    ])
+}
+
+/// Record where `repo_path`'s working tree stands as the start of
+/// `thread_id`. A thread that already has a snapshot keeps it, so resuming
+/// a thread doesn't move its starting point. None outside a git work tree.
+#[tauri::command]
+pub async fn create_thread_snapshot(
+    repo_path: String,
+    thread_id: String,
+) -> Result<Option<ThreadSnapshot>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let inside = run_git(repo, ["rev-parse", "--is-inside-work-tree"])?;
+        if !inside.status.success() {
+            return Ok(None);
+        }
+        create(repo, &thread_id).map(Some)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_thread_snapshot(
+    repo_path: String,
+    thread_id: String,
+) -> Result<Option<ThreadSnapshot>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        read_snapshot(repo, &thread_id)
+    })
+    .await
+}
+
+/// Structured diff of everything that changed in the working tree since
+/// `thread_id` began, committed or not; optionally limited to one path.
+#[tauri::command]
+pub async fn get_thread_changes(
+    repo_path: String,
+    thread_id: String,
+    file_path: Option<String>,
+    options: DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let mut args = diff_args(&options);
+        args.extend(endpoints(repo, &thread_id)?);
+        args.push("--".to_string());
+        args.extend(file_path);
+        run_diff(repo, &args, &options)
+    })
+    .await
+}
+
+/// Per-file line counts since `thread_id` began.
+#[tauri::command]
+pub async fn get_thread_numstat(
+    repo_path: String,
+    thread_id: String,
+) -> Result<Vec<FileDiffStat>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let [base, target] = endpoints(repo, &thread_id)?;
+        let output = run_git(
+            repo,
+            [
+                "diff",
+                "--numstat",
+                "-z",
+                "--no-renames",
+                &base,
+                &target,
+                "--",
+            ],
+        )?;
+        if !output.status.success() {
+            return Err(git_failure("diff", &output));
+        }
+        Ok(parse_numstat(&String::from_utf8_lossy(&output.stdout)))
+    })
+    .await
+}
+
+/// Drop `thread_id`'s snapshot, e.g. when the thread is removed. Nothing
+/// happens if it has none.
+#[tauri::command]
+pub async fn delete_thread_snapshot(repo_path: String, thread_id: String) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let name = snapshot_ref(&thread_id)?;
+        let output = run_git(repo, ["update-ref", "-d", &name])?;
+        if !output.status.success() {
+            return Err(git_failure("delete the snapshot", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, test_repo};
+    use std::fs;
+
+    #[test]
+    fn refuses_odd_thread_ids() {
+        assert_eq!(
+            snapshot_ref("0b7c6a1e-4f7e-4c1b-9d1a-2f3e4d5c6b7a").unwrap(),
+            "refs/codezilla/threads/0b7c6a1e-4f7e-4c1b-9d1a-2f3e4d5c6b7a"
+        );
+        for id in ["", "../heads/main", "a b", "x.lock"] {
+            assert!(snapshot_ref(id).is_err(), "{}", id);
+        }
+    }
+
+    #[tokio::test]
+    async fn diffs_against_the_thread_start() {
+        let repo = test_repo("snapshots");
+        commit_file(&repo, "a.txt", "one\n", "init");
+        // Uncommitted work from before the thread isn't the thread's change.
+        fs::write(repo.join("wip.txt"), "before\n").unwrap();
+        let repo_path = repo.to_string_lossy().to_string();
+        let thread = "5d0c1c7e-0000-4000-8000-000000000001".to_string();
+
+        let snapshot = create_thread_snapshot(repo_path.clone(), thread.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(snapshot.head.is_some());
+        assert!(get_thread_numstat(repo_path.clone(), thread.clone())
+            .await
+            .unwrap()
+            .is_empty());
+
+        commit_file(&repo, "a.txt", "one\ntwo\n", "agent commit");
+        fs::write(repo.join("new.txt"), "x\ny\n").unwrap();
+        // A second create (a resumed thread) keeps the original start.
+        let again = create_thread_snapshot(repo_path.clone(), thread.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.commit, snapshot.commit);
+
+        let mut stats = get_thread_numstat(repo_path.clone(), thread.clone())
+            .await
+            .unwrap();
+        stats.sort_by(|a, b| a.path.cmp(&b.path));
+        let stats: Vec<_> = stats
+            .iter()
+            .map(|s| (s.path.as_str(), s.added, s.removed))
+            .collect();
+        assert_eq!(stats, [("a.txt", 1, 0), ("new.txt", 2, 0)]);
+        let files = get_thread_changes(
+            repo_path.clone(),
+            thread.clone(),
+            Some("new.txt".into()),
+            DiffOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(files.len(), 1);
+
+        delete_thread_snapshot(repo_path.clone(), thread.clone())
+            .await
+            .unwrap();
+        assert!(get_thread_snapshot(repo_path.clone(), thread.clone())
+            .await
+            .unwrap()
+            .is_none());
+        let err = get_thread_numstat(repo_path, thread).await.unwrap_err();
+        assert!(err.starts_with("No snapshot for thread"), "{}", err);
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn no_snapshot_outside_a_repo() {
+        let repo = test_repo("snapshots_plain");
+        fs::remove_dir_all(repo.join(".git")).unwrap();
+        let snapshot = create_thread_snapshot(
+            repo.to_string_lossy().to_string(),
+            "5d0c1c7e-0000-4000-8000-000000000002".into(),
+        )
+        .await
+        .unwrap();
+        assert!(snapshot.is_none());
+        let _ = fs::remove_dir_all(repo);
+    }
+}
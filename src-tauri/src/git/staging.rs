@@ -0,0 +1,620 @@
+//! Index manipulation: stage, unstage and discard whole files, or just some
+//! hunks / lines of a file's diff. Partial operations rebuild a patch from the
+//! file's current diff containing only the selected changes and feed it to
+//! `git apply`, the same approach as `git add -p`.
+
+use super::types::{DiffSide, PatchSelection};
+use super::{
+    git_failure, has_head, query_backend, repo_dir, run_git, run_git_with_stdin, BACKEND_TIMEOUT,
+};
+use std::path::Path;
+
+fn require_paths(paths: &[String]) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("No files given".to_string());
+    }
+    Ok(())
+}
+
+fn split_nul(stdout: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(stdout)
+        .split('\0')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Which of `paths` are in the index, as git spells them.
+fn tracked_files(repo: &Path, paths: &[String]) -> Result<Vec<String>, String> {
+    let mut args = vec!["ls-files", "-z", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        return Err(git_failure("list tracked files", &output));
+    }
+    Ok(split_nul(&output.stdout))
+}
+
+/// Stage whole files, including deletions and untracked files.
+#[tauri::command]
+pub async fn stage_files(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    require_paths(&paths)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let mut args = vec!["add", "-A", "--"];
+        args.extend(paths.iter().map(String::as_str));
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("stage files", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+fn unstage(repo: &Path, paths: &[String]) -> Result<(), String> {
+    // Before the first commit there's no HEAD to reset to; dropping the paths
+    // from the index is the equivalent.
+    let mut args = if has_head(repo) {
+        vec!["reset", "--quiet", "HEAD", "--"]
+    } else {
+        vec!["rm", "--cached", "-r", "--quiet", "--ignore-unmatch", "--"]
+    };
+    args.extend(paths.iter().map(String::as_str));
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        return Err(git_failure("unstage files", &output));
+    }
+    Ok(())
+}
+
+/// Reset the index entries of whole files to HEAD, keeping worktree changes.
+#[tauri::command]
+pub async fn unstage_files(repo_path: String, paths: Vec<String>) -> Result<(), String> {
+    require_paths(&paths)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| unstage(repo, &paths)).await
+}
+
+fn discard(repo: &Path, paths: &[String], include_staged: bool) -> Result<(), String> {
+    if include_staged {
+        unstage(repo, paths)?;
+    }
+
+    let tracked = tracked_files(repo, paths)?;
+    if !tracked.is_empty() {
+        let mut args = vec!["checkout", "--"];
+        args.extend(tracked.iter().map(String::as_str));
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("discard changes", &output));
+        }
+    }
+
+    // Only files git itself reports as untracked are deleted: `paths` may
+    // spell a tracked file differently (`./a.txt`, `src/../a.txt`).
+    let mut args = vec!["ls-files", "-z", "--others", "--exclude-standard", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    let untracked = run_git(repo, &args)?;
+    if !untracked.status.success() {
+        return Err(git_failure("discard changes", &untracked));
+    }
+    for path in split_nul(&untracked.stdout) {
+        let full = repo.join(&path);
+        // Never delete through a path that resolves outside the repo.
+        let canonical = match full.canonicalize() {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+        crate::fs::validate_within_root(&canonical, repo)?;
+        if canonical.is_file() {
+            std::fs::remove_file(&canonical)
+                .map_err(|e| format!("Failed to delete {}: {}", path, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Throw away worktree changes to whole files: tracked files are restored from
+/// the index, untracked files are deleted. With `include_staged`, the files are
+/// unstaged first so they end up matching HEAD.
+#[tauri::command]
+pub async fn discard_file_changes(
+    repo_path: String,
+    paths: Vec<String>,
+    include_staged: Option<bool>,
+) -> Result<(), String> {
+    require_paths(&paths)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        discard(repo, &paths, include_staged.unwrap_or(false))
+    })
+    .await
+}
+
+struct Hunk<'a> {
+    old_start: u32,
+    new_start: u32,
+    /// Bytes after the second `@@` (the function-context hint), newline
+    /// included.
+    section: &'a [u8],
+    lines: Vec<&'a [u8]>,
+}
+
+/// Parse `@@ -a,b +c,d @@ section` into (a, c, section).
+fn parse_hunk_header(line: &[u8]) -> Option<(u32, u32, &[u8])> {
+    let rest = line.strip_prefix(b"@@ -")?;
+    let end = rest.windows(3).position(|w| w == b" @@")?;
+    let (ranges, section) = (std::str::from_utf8(&rest[..end]).ok()?, &rest[end + 3..]);
+    let (old, new) = ranges.split_once(" +")?;
+    let start = |r: &str| r.split(',').next().and_then(|n| n.parse::<u32>().ok());
+    Some((start(old)?, start(new)?, section))
+}
+
+/// Split a single-file unified diff into its header lines and hunks. Lines
+/// keep their `\n` and are left as bytes, so CRLF endings and text in any
+/// encoding reach `git apply` exactly as `git diff` produced them.
+fn parse_patch(diff: &[u8]) -> (Vec<&[u8]>, Vec<Hunk<'_>>) {
+    let mut header = Vec::new();
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for line in diff.split_inclusive(|&b| b == b'\n') {
+        if let Some((old_start, new_start, section)) = parse_hunk_header(line) {
+            hunks.push(Hunk {
+                old_start,
+                new_start,
+                section,
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = hunks.last_mut() {
+            hunk.lines.push(line);
+        } else {
+            header.push(line);
+        }
+    }
+    (header, hunks)
+}
+
+/// `line` with its `+`/`-` marker turned into a context space.
+fn as_context(line: &[u8]) -> Vec<u8> {
+    let mut context = line.to_vec();
+    context[0] = b' ';
+    context
+}
+
+/// Build a patch holding only the selected changes of `diff`.
+///
+/// Unselected changes are neutralised relative to the side the patch will be
+/// applied to: applied forwards (staging), an unselected `+` is dropped and an
+/// unselected `-` becomes context; applied in `reverse` (unstaging, discarding)
+/// the roles swap. Returns None when nothing in the selection is a change.
+fn build_patch(diff: &[u8], selection: &PatchSelection, reverse: bool) -> Option<Vec<u8>> {
+    let (header, hunks) = parse_patch(diff);
+    let covers = |side: DiffSide, line: u32| {
+        selection
+            .lines
+            .iter()
+            .any(|r| r.side == side && (r.start..=r.end).contains(&line))
+    };
+
+    let mut out: Vec<u8> = header.concat();
+    let mut any = false;
+    // Net line shift introduced by the hunks kept so far, used to re-derive the
+    // start of the side git doesn't anchor on.
+    let mut shift: i64 = 0;
+
+    for (index, hunk) in hunks.iter().enumerate() {
+        let whole = selection.hunks.contains(&index);
+        let (mut old_no, mut new_no) = (hunk.old_start, hunk.new_start);
+        let mut body: Vec<u8> = Vec::new();
+        let mut changed = false;
+        let (mut old_count, mut new_count) = (0u32, 0u32);
+        // Whether the previous line survived, so a following "\ No newline"
+        // marker is kept or dropped with it.
+        let mut kept_previous = true;
+
+        for line in &hunk.lines {
+            match line.first() {
+                Some(b'+') => {
+                    let selected = whole || covers(DiffSide::New, new_no);
+                    new_no += 1;
+                    if selected {
+                        body.extend_from_slice(line);
+                        new_count += 1;
+                        changed = true;
+                        kept_previous = true;
+                    } else if reverse {
+                        body.extend(as_context(line));
+                        old_count += 1;
+                        new_count += 1;
+                        kept_previous = true;
+                    } else {
+                        kept_previous = false;
+                    }
+                }
+                Some(b'-') => {
+                    let selected = whole || covers(DiffSide::Old, old_no);
+                    old_no += 1;
+                    if selected {
+                        body.extend_from_slice(line);
+                        old_count += 1;
+                        changed = true;
+                        kept_previous = true;
+                    } else if reverse {
+                        kept_previous = false;
+                    } else {
+                        body.extend(as_context(line));
+                        old_count += 1;
+                        new_count += 1;
+                        kept_previous = true;
+                    }
+                }
+                Some(b'\\') => {
+                    if kept_previous {
+                        body.extend_from_slice(line);
+                    }
+                }
+                _ => {
+                    old_no += 1;
+                    new_no += 1;
+                    body.extend_from_slice(line);
+                    old_count += 1;
+                    new_count += 1;
+                    kept_previous = true;
+                }
+            }
+        }
+
+        if !changed {
+            continue;
+        }
+        any = true;
+        // Forward patches are located by their old side, reverse ones by their
+        // new side; the other start follows from the hunks kept before this one.
+        let (old_start, new_start) = if reverse {
+            let new_start = hunk.new_start as i64;
+            ((new_start - shift).max(0) as u32, hunk.new_start)
+        } else {
+            let old_start = hunk.old_start as i64;
+            (hunk.old_start, (old_start + shift).max(0) as u32)
+        };
+        shift += new_count as i64 - old_count as i64;
+        out.extend_from_slice(
+            format!(
+                "@@ -{},{} +{},{} @@",
+                old_start, old_count, new_start, new_count
+            )
+            .as_bytes(),
+        );
+        out.extend_from_slice(hunk.section);
+        out.extend(body);
+    }
+
+    if !any {
+        return None;
+    }
+    if !out.ends_with(b"\n") {
+        out.push(b'\n');
+    }
+    Some(out)
+}
+
+/// Which diff a partial operation works from, and how its patch is applied.
+#[derive(Clone, Copy)]
+enum PartialOp {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+impl PartialOp {
+    fn verb(self) -> &'static str {
+        match self {
+            PartialOp::Stage => "stage",
+            PartialOp::Unstage => "unstage",
+            PartialOp::Discard => "discard",
+        }
+    }
+}
+
+fn require_selection(selection: &PatchSelection, op: PartialOp) -> Result<(), String> {
+    if selection.hunks.is_empty() && selection.lines.is_empty() {
+        return Err(format!("Nothing selected to {}", op.verb()));
+    }
+    Ok(())
+}
+
+fn apply_selection(
+    repo: &Path,
+    file_path: &str,
+    selection: &PatchSelection,
+    op: PartialOp,
+) -> Result<(), String> {
+    require_selection(selection, op)?;
+    let diff_args: &[&str] = match op {
+        // Unstaged changes: index → worktree.
+        PartialOp::Stage | PartialOp::Discard => &["diff", "--no-color", "--no-ext-diff", "--"],
+        // Staged changes: HEAD → index.
+        PartialOp::Unstage => &["diff", "--cached", "--no-color", "--no-ext-diff", "--"],
+    };
+    let mut args = diff_args.to_vec();
+    args.push(file_path);
+    let diff = run_git(repo, &args)?;
+    if !diff.status.success() {
+        return Err(git_failure(&format!("{} changes", op.verb()), &diff));
+    }
+    let diff = diff.stdout;
+    if diff.starts_with(b"Binary files ") || diff.windows(14).any(|w| w == b"\nBinary files ") {
+        return Err(format!("Cannot {} part of a binary file", op.verb()));
+    }
+
+    let reverse = !matches!(op, PartialOp::Stage);
+    let patch = build_patch(&diff, selection, reverse)
+        .ok_or_else(|| format!("Selection contains no changes to {}", op.verb()))?;
+
+    let apply_args: &[&str] = match op {
+        PartialOp::Stage => &["apply", "--cached", "--recount", "-"],
+        PartialOp::Unstage => &["apply", "--cached", "--recount", "-R", "-"],
+        PartialOp::Discard => &["apply", "--recount", "-R", "-"],
+    };
+    let output = run_git_with_stdin(repo, apply_args, &patch)?;
+    if !output.status.success() {
+        return Err(git_failure(
+            &format!("{} selected changes", op.verb()),
+            &output,
+        ));
+    }
+    Ok(())
+}
+
+/// Stage selected hunks/lines of a file's unstaged changes. Line numbers are
+/// those of the index → worktree diff (the same as `get_git_diff` whenever
+/// nothing in the file is staged yet).
+#[tauri::command]
+pub async fn stage_selection(
+    repo_path: String,
+    file_path: String,
+    selection: PatchSelection,
+) -> Result<(), String> {
+    require_selection(&selection, PartialOp::Stage)?;
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        // An untracked file has no index → worktree diff until git knows about
+        // it. If the selection is then refused, that placeholder entry comes
+        // back out so the file is untracked again.
+        let untracked = tracked_files(repo, std::slice::from_ref(&file_path))?.is_empty();
+        if untracked {
+            let output = run_git(repo, ["add", "--intent-to-add", "--", &file_path])?;
+            if !output.status.success() {
+                return Err(git_failure("stage selected changes", &output));
+            }
+        }
+        let result = apply_selection(repo, &file_path, &selection, PartialOp::Stage);
+        if result.is_err() && untracked {
+            let _ = unstage(repo, std::slice::from_ref(&file_path));
+        }
+        result
+    })
+    .await
+}
+
+/// Unstage selected hunks/lines of a file's staged changes (HEAD → index diff).
+#[tauri::command]
+pub async fn unstage_selection(
+    repo_path: String,
+    file_path: String,
+    selection: PatchSelection,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        apply_selection(repo, &file_path, &selection, PartialOp::Unstage)
+    })
+    .await
+}
+
+/// Revert selected hunks/lines of a file's unstaged changes in the worktree.
+#[tauri::command]
+pub async fn discard_selection(
+    repo_path: String,
+    file_path: String,
+    selection: PatchSelection,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        apply_selection(repo, &file_path, &selection, PartialOp::Discard)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{git, test_repo};
+    use crate::git::types::LineRange;
+    use std::fs;
+
+    const DIFF: &str = "diff --git a/f.txt b/f.txt
+index 1111111..2222222 100644
+--- a/f.txt
++++ b/f.txt
+@@ -1,4 +1,4 @@ fn main
+ one
+-two
++TWO
++extra
+ three
+-four
+";
+
+    fn lines(side: DiffSide, start: u32, end: u32) -> PatchSelection {
+        PatchSelection {
+            hunks: vec![],
+            lines: vec![LineRange { side, start, end }],
+        }
+    }
+
+    #[test]
+    fn forward_patch_keeps_only_selected_lines() {
+        let patch = build_patch(DIFF.as_bytes(), &lines(DiffSide::New, 3, 3), false).unwrap();
+        assert!(String::from_utf8(patch)
+            .unwrap()
+            .contains("@@ -1,4 +1,5 @@ fn main\n one\n two\n+extra\n three\n four\n"));
+    }
+
+    #[test]
+    fn reverse_patch_turns_unselected_additions_into_context() {
+        let patch = build_patch(DIFF.as_bytes(), &lines(DiffSide::Old, 2, 2), true).unwrap();
+        assert!(String::from_utf8(patch)
+            .unwrap()
+            .contains("@@ -1,5 +1,4 @@ fn main\n one\n-two\n TWO\n extra\n three\n"));
+    }
+
+    #[test]
+    fn selection_without_changes_builds_nothing() {
+        assert!(build_patch(DIFF.as_bytes(), &lines(DiffSide::New, 1, 1), false).is_none());
+    }
+
+    #[tokio::test]
+    async fn stage_selection_stages_one_hunk() {
+        let repo = test_repo("stage_hunk");
+        let original: Vec<String> = (1..=20).map(|n| format!("line {}", n)).collect();
+        fs::write(repo.join("f.txt"), original.join("\n") + "\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        let mut edited = original.clone();
+        edited[1] = "changed 2".to_string();
+        edited[18] = "changed 19".to_string();
+        fs::write(repo.join("f.txt"), edited.join("\n") + "\n").unwrap();
+
+        let root = repo.to_string_lossy().to_string();
+        let selection = PatchSelection {
+            hunks: vec![1],
+            lines: vec![],
+        };
+        stage_selection(root.clone(), "f.txt".to_string(), selection.clone())
+            .await
+            .unwrap();
+        let staged = git(&repo, &["diff", "--cached"]);
+        assert!(staged.contains("+changed 19") && !staged.contains("+changed 2\n"));
+
+        // The staged hunk is hunk 0 of the HEAD → index diff.
+        let selection = PatchSelection {
+            hunks: vec![0],
+            lines: vec![],
+        };
+        unstage_selection(root.clone(), "f.txt".to_string(), selection)
+            .await
+            .unwrap();
+        assert!(git(&repo, &["diff", "--cached"]).is_empty());
+
+        discard_file_changes(root, vec!["f.txt".to_string()], None)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(repo.join("f.txt")).unwrap(),
+            original.join("\n") + "\n"
+        );
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn partial_staging_keeps_crlf_and_non_utf8_bytes() {
+        let repo = test_repo("stage_bytes");
+        let crlf = |lines: &[&str]| {
+            lines
+                .iter()
+                .map(|l| format!("{}\r\n", l))
+                .collect::<String>()
+        };
+        fs::write(repo.join("dos.txt"), crlf(&["a", "b", "c"])).unwrap();
+        fs::write(repo.join("latin1.txt"), b"caf\xe9\nna\xefve\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+
+        fs::write(repo.join("dos.txt"), crlf(&["a", "B", "c", "d"])).unwrap();
+        fs::write(
+            repo.join("latin1.txt"),
+            b"caf\xe9\nna\xefve\nr\xe9sum\xe9\ngar\xe7on\n",
+        )
+        .unwrap();
+        // A new file is added intent-to-add, so its patch is all `+` lines.
+        fs::write(repo.join("new.txt"), b"x\r\n\xff\xfe\r\n").unwrap();
+        let root = repo.to_string_lossy().to_string();
+
+        // Only the `d` line of dos.txt, and the first new line of latin1.txt.
+        stage_selection(root.clone(), "dos.txt".into(), lines(DiffSide::New, 4, 4))
+            .await
+            .unwrap();
+        stage_selection(
+            root.clone(),
+            "latin1.txt".into(),
+            lines(DiffSide::New, 3, 3),
+        )
+        .await
+        .unwrap();
+        stage_selection(root, "new.txt".into(), lines(DiffSide::New, 1, 2))
+            .await
+            .unwrap();
+
+        let show = |path: &str| {
+            let output = run_git(&repo, ["show", &format!(":{}", path)]).unwrap();
+            output.stdout
+        };
+        assert_eq!(show("dos.txt"), crlf(&["a", "b", "c", "d"]).into_bytes());
+        assert_eq!(show("latin1.txt"), b"caf\xe9\nna\xefve\nr\xe9sum\xe9\n");
+        assert_eq!(show("new.txt"), b"x\r\n\xff\xfe\r\n");
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn discard_never_deletes_tracked_files_spelled_differently() {
+        let repo = test_repo("discard_spelling");
+        fs::create_dir_all(repo.join("src")).unwrap();
+        fs::write(repo.join("a.txt"), "kept\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        fs::write(repo.join("a.txt"), "edited\n").unwrap();
+        fs::write(repo.join("b.txt"), "scratch\n").unwrap();
+
+        let root = repo.to_string_lossy().to_string();
+        let paths = vec!["./a.txt".into(), "src/../a.txt".into(), "./b.txt".into()];
+        discard_file_changes(root, paths, None).await.unwrap();
+        assert_eq!(fs::read_to_string(repo.join("a.txt")).unwrap(), "kept\n");
+        assert!(!repo.join("b.txt").exists());
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn refused_selection_leaves_untracked_file_untracked() {
+        let repo = test_repo("stage_refused");
+        fs::write(repo.join("a.txt"), "a\n").unwrap();
+        git(&repo, &["add", "."]);
+        git(&repo, &["commit", "-q", "-m", "init"]);
+        fs::write(repo.join("new.txt"), "one\ntwo\n").unwrap();
+        let root = repo.to_string_lossy().to_string();
+
+        let empty = PatchSelection {
+            hunks: vec![],
+            lines: vec![],
+        };
+        assert!(stage_selection(root.clone(), "new.txt".into(), empty)
+            .await
+            .is_err());
+        let err = stage_selection(root, "new.txt".into(), lines(DiffSide::Old, 1, 1))
+            .await
+            .unwrap_err();
+        assert_eq!(err, "Selection contains no changes to stage");
+        assert_eq!(git(&repo, &["status", "--porcelain"]), "?? new.txt\n");
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[test]
+    fn locked_index_is_reported() {
+        let repo = test_repo("locked");
+        fs::write(repo.join("a.txt"), "a").unwrap();
+        fs::write(repo.join(".git/index.lock"), "").unwrap();
+        let output = run_git(&repo, ["add", "a.txt"]).unwrap();
+        assert!(git_failure("stage files", &output).contains("index is locked"));
+        let _ = fs::remove_dir_all(repo);
+    }
+}
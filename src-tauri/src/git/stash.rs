@@ -0,0 +1,306 @@
+//! Listing, creating, applying and dropping stashes.
+//!
+//! Stashes are addressed by their position in `git stash list`, as
+//! `stash@{<index>}`; the index shifts as stashes are pushed and dropped, so
+//! callers should re-list after changing anything.
+
+use super::cli::parse_numstat;
+use super::diff::{diff_args, run_diff, EMPTY_TREE};
+use super::types::{DiffFile, DiffOptions, StashApplyOutcome, StashEntry};
+use super::{git_failure, query_backend, repo_dir, run_git, unmerged_paths, BACKEND_TIMEOUT};
+use std::path::Path;
+
+fn stash_ref(index: u32) -> String {
+    format!("stash@{{{}}}", index)
+}
+
+fn rev_exists(repo: &Path, rev: &str) -> Result<bool, String> {
+    Ok(run_git(repo, ["rev-parse", "--verify", "--quiet", rev])?
+        .status
+        .success())
+}
+
+/// `stash@{index}`, or an error when there's no such stash.
+fn existing_stash(repo: &Path, index: u32) -> Result<String, String> {
+    let name = stash_ref(index);
+    if !rev_exists(repo, &name)? {
+        return Err(format!("No stash at index {}", index));
+    }
+    Ok(name)
+}
+
+/// Split a stash reflog subject — `WIP on main: abc123 subject` or
+/// `On main: message` — into the branch (None when detached) and message.
+fn parse_stash_subject(subject: &str) -> (Option<String>, String) {
+    let rest = subject
+        .strip_prefix("WIP on ")
+        .or_else(|| subject.strip_prefix("On "));
+    let Some((branch, message)) = rest.and_then(|r| r.split_once(": ")) else {
+        return (None, subject.to_string());
+    };
+    let branch = (branch != "(no branch)").then(|| branch.to_string());
+    (branch, message.to_string())
+}
+
+/// Parse `git stash list --format=%gd%x1f%H%x1f%cI%x1f%gs`, leaving the
+/// file stats empty.
+fn parse_stash_list(stdout: &str) -> Vec<StashEntry> {
+    stdout
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let fields: Vec<&str> = line.splitn(4, '\x1f').collect();
+            if fields.len() < 4 {
+                return None;
+            }
+            let (branch, message) = parse_stash_subject(fields[3]);
+            Some(StashEntry {
+                index: index as u32,
+                name: fields[0].to_string(),
+                commit: fields[1].to_string(),
+                date: fields[2].to_string(),
+                branch,
+                message,
+                file_stats: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+fn list(repo: &Path) -> Result<Vec<StashEntry>, String> {
+    let output = run_git(repo, ["stash", "list", "--format=%gd%x1f%H%x1f%cI%x1f%gs"])?;
+    if !output.status.success() {
+        return Err(git_failure("list stashes", &output));
+    }
+    let mut stashes = parse_stash_list(&String::from_utf8_lossy(&output.stdout));
+    for stash in &mut stashes {
+        let stats = run_git(
+            repo,
+            [
+                "stash",
+                "show",
+                "--numstat",
+                "-z",
+                "--no-renames",
+                "--include-untracked",
+                &stash.name,
+            ],
+        )?;
+        if stats.status.success() {
+            stash.file_stats = parse_numstat(&String::from_utf8_lossy(&stats.stdout));
+        }
+    }
+    Ok(stashes)
+}
+
+/// Apply `stash@{index}`, dropping it afterwards when `pop` is set and it
+/// applied cleanly (git keeps it on conflicts, and so do we).
+fn apply(
+    repo: &Path,
+    index: u32,
+    restore_index: bool,
+    pop: bool,
+) -> Result<StashApplyOutcome, String> {
+    let name = existing_stash(repo, index)?;
+    let mut args = vec!["stash", if pop { "pop" } else { "apply" }];
+    if restore_index {
+        args.push("--index");
+    }
+    args.push(&name);
+    let output = run_git(repo, &args)?;
+    let conflicted = unmerged_paths(repo)?;
+    if !output.status.success() && conflicted.is_empty() {
+        let action = if pop {
+            "pop the stash"
+        } else {
+            "apply the stash"
+        };
+        return Err(git_failure(action, &output));
+    }
+    Ok(StashApplyOutcome {
+        dropped: pop && output.status.success(),
+        conflicted,
+    })
+}
+
+/// Stashes newest first, each with per-file line counts (untracked files
+/// included).
+#[tauri::command]
+pub async fn list_stashes(repo_path: String) -> Result<Vec<StashEntry>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, |_, repo| list(repo)).await
+}
+
+/// Stash local changes and return the new stash. `include_untracked` takes
+/// untracked files too; `keep_index` leaves staged changes in place.
+#[tauri::command]
+pub async fn create_stash(
+    repo_path: String,
+    message: Option<String>,
+    include_untracked: bool,
+    keep_index: bool,
+) -> Result<StashEntry, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let before = run_git(repo, ["rev-parse", "--verify", "--quiet", "refs/stash"])?;
+        let mut args = vec!["stash", "push", "--quiet"];
+        if include_untracked {
+            args.push("--include-untracked");
+        }
+        if keep_index {
+            args.push("--keep-index");
+        }
+        if let Some(message) = &message {
+            args.extend(["--message", message]);
+        }
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("stash", &output));
+        }
+        // With nothing to save git exits 0 without creating a stash.
+        let after = run_git(repo, ["rev-parse", "--verify", "--quiet", "refs/stash"])?;
+        if !after.status.success() || after.stdout == before.stdout {
+            return Err("No local changes to stash".to_string());
+        }
+        list(repo)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| "No local changes to stash".to_string())
+    })
+    .await
+}
+
+/// Apply a stash, keeping it. `restore_index` re-stages what was staged.
+#[tauri::command]
+pub async fn apply_stash(
+    repo_path: String,
+    index: u32,
+    restore_index: Option<bool>,
+) -> Result<StashApplyOutcome, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        apply(repo, index, restore_index.unwrap_or(false), false)
+    })
+    .await
+}
+
+/// Apply a stash and drop it, unless applying it conflicted.
+#[tauri::command]
+pub async fn pop_stash(
+    repo_path: String,
+    index: u32,
+    restore_index: Option<bool>,
+) -> Result<StashApplyOutcome, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        apply(repo, index, restore_index.unwrap_or(false), true)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn drop_stash(repo_path: String, index: u32) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let name = existing_stash(repo, index)?;
+        let output = run_git(repo, ["stash", "drop", "--quiet", &name])?;
+        if !output.status.success() {
+            return Err(git_failure("drop the stash", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// A stash's changes against the commit it was made on, untracked files
+/// included as added.
+#[tauri::command]
+pub async fn show_stash(
+    repo_path: String,
+    index: u32,
+    options: DiffOptions,
+) -> Result<Vec<DiffFile>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |_, repo| {
+        let name = existing_stash(repo, index)?;
+        let mut args = diff_args(&options);
+        args.extend([format!("{}^1", name), name.clone(), "--".to_string()]);
+        let mut files = run_diff(repo, &args, &options)?;
+
+        // Untracked files live in a third parent, when there were any.
+        let untracked = format!("{}^3", name);
+        if rev_exists(repo, &untracked)? {
+            let mut args = diff_args(&options);
+            args.extend([EMPTY_TREE.to_string(), untracked, "--".to_string()]);
+            files.extend(run_diff(repo, &args, &options)?);
+        }
+        Ok(files)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, git, test_repo};
+    use std::fs;
+
+    #[test]
+    fn parses_stash_subjects() {
+        assert_eq!(
+            parse_stash_subject("WIP on main: abc123 fix it"),
+            (Some("main".to_string()), "abc123 fix it".to_string())
+        );
+        assert_eq!(
+            parse_stash_subject("On feature/x: before rebase"),
+            (Some("feature/x".to_string()), "before rebase".to_string())
+        );
+        assert_eq!(parse_stash_subject("On (no branch): detached").0, None);
+    }
+
+    #[tokio::test]
+    async fn stashes_round_trip() {
+        let repo = test_repo("stash");
+        commit_file(&repo, "a.txt", "one\n", "init");
+        let repo_path = repo.to_string_lossy().to_string();
+
+        let err = create_stash(repo_path.clone(), None, false, false)
+            .await
+            .unwrap_err();
+        assert_eq!(err, "No local changes to stash");
+
+        fs::write(repo.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(repo.join("new.txt"), "untracked\n").unwrap();
+        let stash = create_stash(repo_path.clone(), Some("wip: both".into()), true, false)
+            .await
+            .unwrap();
+        assert_eq!((stash.index, stash.message.as_str()), (0, "wip: both"));
+        assert_eq!(stash.branch.as_deref(), Some("main"));
+        assert!(!repo.join("new.txt").exists());
+        let mut paths: Vec<_> = stash.file_stats.iter().map(|s| s.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["a.txt", "new.txt"]);
+
+        let files = show_stash(repo_path.clone(), 0, DiffOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(files.len(), 2);
+        assert!(files
+            .iter()
+            .any(|f| f.new_path.as_deref() == Some("new.txt") && f.added == 1));
+
+        // Conflicting pop keeps the stash.
+        commit_file(&repo, "a.txt", "one\nother\n", "diverge");
+        let outcome = pop_stash(repo_path.clone(), 0, None).await.unwrap();
+        assert_eq!(outcome.conflicted, ["a.txt"]);
+        assert!(!outcome.dropped);
+        assert_eq!(list_stashes(repo_path.clone()).await.unwrap().len(), 1);
+
+        git(&repo, &["reset", "-q", "--hard"]);
+        drop_stash(repo_path.clone(), 0).await.unwrap();
+        assert!(list_stashes(repo_path.clone()).await.unwrap().is_empty());
+        let err = drop_stash(repo_path, 0).await.unwrap_err();
+        assert_eq!(err, "No stash at index 0");
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -0,0 +1,412 @@
+//! A cached, event-driven view of each repo's uncommitted state.
+//!
+//! Rather than every panel polling `git status` and `git diff --numstat` on
+//! its own schedule, [`GitStateService`] keeps one [`GitSnapshot`] per repo
+//! and refreshes it when the `FileWatcher` sees a change — to files in the
+//! worktree, or to the index, HEAD or refs under `.git`. Requests that arrive
+//! while a refresh runs wait for it instead of starting another, and changes
+//! that land mid-refresh fold into one more pass. Whenever a snapshot differs
+//! from the last, it's pushed to the frontend as a `git-state-changed` event.
+
+use super::backend::backend;
+use super::types::{FileDiffStat, GitSnapshot};
+use super::{
+    has_head, numstat_in, repo_dir, run_git, untracked_in, untracked_lines, BACKEND_TIMEOUT,
+};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::oneshot;
+
+pub const GIT_STATE_EVENT: &str = "git-state-changed";
+
+/// Refresh passes run back to back while changes keep arriving, before
+/// answering with what the last pass saw.
+const MAX_REFRESH_PASSES: usize = 3;
+
+/// Files under a git dir whose changes mean status, branch or ahead/behind
+/// may differ. Everything else in there (objects, logs, locks) is churn.
+const GIT_STATE_FILES: [&str; 7] = [
+    "index",
+    "HEAD",
+    "ORIG_HEAD",
+    "MERGE_HEAD",
+    "CHERRY_PICK_HEAD",
+    "REBASE_HEAD",
+    "packed-refs",
+];
+
+/// A path inside a `.git` directory that the service should react to.
+pub fn is_git_state_path(path: &Path) -> bool {
+    let mut components = path.components().map(|c| c.as_os_str().to_string_lossy());
+    if !components.any(|c| c == ".git") {
+        return false;
+    }
+    let inside: Vec<_> = components.collect();
+    inside.iter().any(|c| c == "refs")
+        || inside
+            .last()
+            .is_some_and(|name| GIT_STATE_FILES.contains(&name.as_ref()))
+}
+
+type Waiter = oneshot::Sender<Result<GitSnapshot, String>>;
+
+#[derive(Default)]
+struct RepoEntry {
+    snapshot: Option<GitSnapshot>,
+    /// Something changed since `snapshot` was taken.
+    stale: bool,
+    refreshing: bool,
+    /// Requests waiting on the running refresh.
+    waiters: Vec<Waiter>,
+    /// The repo's git dir and, for a linked worktree, the shared common
+    /// dir — where its index, HEAD and refs changes show up.
+    git_dirs: Vec<PathBuf>,
+}
+
+pub struct GitStateService {
+    repos: Mutex<HashMap<PathBuf, RepoEntry>>,
+    /// What the `FileWatcher` currently covers. A repo outside it gets no
+    /// change reports, so its snapshot can't be trusted from cache.
+    watch_root: Mutex<Option<PathBuf>>,
+    notify: Box<dyn Fn(&GitSnapshot) + Send + Sync>,
+}
+
+/// Managed as app state; the commands below take it.
+pub type GitStateHandle = Arc<GitStateService>;
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Status plus per-file line counts for `repo`, and the git dirs to watch.
+pub(crate) fn take_snapshot(repo: &Path) -> Result<(GitSnapshot, Vec<PathBuf>), String> {
+    let git = backend();
+    let started = Instant::now();
+    let status = git.status(repo)?.unwrap_or_default();
+    let mut file_stats = if has_head(repo) {
+        numstat_in(repo, git.numstat(repo, None)?)?
+    } else {
+        Vec::new()
+    };
+    for (path, full) in untracked_in(repo, &status)? {
+        file_stats.push(FileDiffStat {
+            added: untracked_lines(&full),
+            path,
+            removed: 0,
+        });
+    }
+    file_stats.sort_by_key(|s| std::cmp::Reverse(s.added + s.removed));
+
+    let dirs = run_git(
+        repo,
+        ["rev-parse", "--absolute-git-dir", "--git-common-dir"],
+    )?;
+    let git_dirs = String::from_utf8_lossy(&dirs.stdout)
+        .lines()
+        .map(|dir| repo.join(dir))
+        .filter_map(|dir| dir.canonicalize().ok())
+        .collect();
+
+    let snapshot = GitSnapshot {
+        path: repo.to_string_lossy().to_string(),
+        added: file_stats.iter().map(|s| s.added).sum(),
+        removed: file_stats.iter().map(|s| s.removed).sum(),
+        status,
+        file_stats,
+        duration_ms: started.elapsed().as_millis() as u64,
+        refreshed_at: now_ms(),
+    };
+    Ok((snapshot, git_dirs))
+}
+
+impl GitStateService {
+    /// `notify` is called with each snapshot that differs from the one
+    /// before it.
+    pub fn new(notify: impl Fn(&GitSnapshot) + Send + Sync + 'static) -> GitStateHandle {
+        Arc::new(GitStateService {
+            repos: Mutex::new(HashMap::new()),
+            watch_root: Mutex::new(None),
+            notify: Box::new(notify),
+        })
+    }
+
+    /// The cached snapshot of `repo` if the watcher covers it, nothing has
+    /// changed since it was taken and it's no older than `max_age`;
+    /// otherwise the result of a refresh, joining one already running.
+    /// `force` skips the cache.
+    pub async fn get(
+        self: &Arc<Self>,
+        repo: PathBuf,
+        max_age: Option<Duration>,
+        force: bool,
+    ) -> Result<GitSnapshot, String> {
+        let watched = self.is_watched(&repo);
+        let result = {
+            let mut repos = self
+                .repos
+                .lock()
+                .map_err(|e| format!("Lock error: {}", e))?;
+            let entry = repos.entry(repo.clone()).or_default();
+            let fresh = entry.snapshot.as_ref().filter(|s| {
+                let age = Duration::from_millis(now_ms().saturating_sub(s.refreshed_at));
+                !force
+                    && watched
+                    && !entry.stale
+                    && !entry.refreshing
+                    && max_age.is_none_or(|max| age <= max)
+            });
+            if let Some(snapshot) = fresh {
+                return Ok(snapshot.clone());
+            }
+            if force {
+                // Don't settle for a pass that may predate the caller's change.
+                entry.stale = true;
+            }
+            let (tx, rx) = oneshot::channel();
+            entry.waiters.push(tx);
+            self.start_refresh(&repo, entry);
+            rx
+        };
+        tokio::time::timeout(BACKEND_TIMEOUT, result)
+            .await
+            .map_err(|_| format!("git timed out after {}s", BACKEND_TIMEOUT.as_secs()))?
+            .map_err(|_| "git state refresh was dropped".to_string())?
+    }
+
+    /// React to changed files: refresh every tracked repo they belong to.
+    pub fn paths_changed(self: &Arc<Self>, paths: &[PathBuf]) {
+        let Ok(mut repos) = self.repos.lock() else {
+            return;
+        };
+        for (repo, entry) in repos.iter_mut() {
+            let affected = paths.iter().any(|path| {
+                path.starts_with(repo) || entry.git_dirs.iter().any(|dir| path.starts_with(dir))
+            });
+            if affected {
+                entry.stale = true;
+                self.start_refresh(repo, entry);
+            }
+        }
+    }
+
+    /// The watcher now covers `root` (or nothing). Repos outside it stop
+    /// hearing about their changes, so they're refreshed on every request
+    /// until it covers them again.
+    pub fn set_watch_root(&self, root: Option<PathBuf>) {
+        if let Ok(mut watch_root) = self.watch_root.lock() {
+            *watch_root = root;
+        }
+    }
+
+    fn is_watched(&self, repo: &Path) -> bool {
+        self.watch_root
+            .lock()
+            .ok()
+            .and_then(|root| root.as_ref().map(|root| repo.starts_with(root)))
+            .unwrap_or(false)
+    }
+
+    /// Stop tracking `repo`, e.g. when its project is closed.
+    pub fn forget(&self, repo: &Path) {
+        if let Ok(mut repos) = self.repos.lock() {
+            repos.remove(repo);
+        }
+    }
+
+    /// Start a refresh unless one is running; a running one notices `stale`
+    /// and goes round again.
+    fn start_refresh(self: &Arc<Self>, repo: &Path, entry: &mut RepoEntry) {
+        if entry.refreshing {
+            return;
+        }
+        entry.refreshing = true;
+        let service = Arc::clone(self);
+        let repo = repo.to_path_buf();
+        std::thread::spawn(move || service.refresh(&repo));
+    }
+
+    fn refresh(&self, repo: &Path) {
+        let mut passes = 0;
+        loop {
+            match self.repos.lock() {
+                Ok(mut repos) => match repos.get_mut(repo) {
+                    Some(entry) => entry.stale = false,
+                    None => return,
+                },
+                Err(_) => return,
+            }
+            let result = take_snapshot(repo);
+            passes += 1;
+
+            let Ok(mut repos) = self.repos.lock() else {
+                return;
+            };
+            let Some(entry) = repos.get_mut(repo) else {
+                return;
+            };
+            if entry.stale && result.is_ok() && passes < MAX_REFRESH_PASSES {
+                continue;
+            }
+            entry.refreshing = false;
+            let waiters = std::mem::take(&mut entry.waiters);
+            let result = match result {
+                Ok((snapshot, git_dirs)) => {
+                    let changed = entry.snapshot.as_ref().is_none_or(|old| {
+                        old.status != snapshot.status || old.file_stats != snapshot.file_stats
+                    });
+                    entry.snapshot = Some(snapshot.clone());
+                    entry.git_dirs = git_dirs;
+                    drop(repos);
+                    if changed {
+                        (self.notify)(&snapshot);
+                    }
+                    Ok(snapshot)
+                }
+                Err(e) => {
+                    // Keep the last good snapshot, but don't trust it.
+                    entry.stale = true;
+                    Err(e)
+                }
+            };
+            for waiter in waiters {
+                let _ = waiter.send(result.clone());
+            }
+            return;
+        }
+    }
+}
+
+/// Status and uncommitted line counts for `path`, from cache when nothing
+/// has changed since and it's no older than `max_age_ms`.
+#[tauri::command]
+pub async fn get_git_state(
+    state: tauri::State<'_, GitStateHandle>,
+    path: String,
+    max_age_ms: Option<u64>,
+) -> Result<GitSnapshot, String> {
+    let repo = repo_dir(&path)?;
+    let service = state.inner().clone();
+    service
+        .get(repo, max_age_ms.map(Duration::from_millis), false)
+        .await
+}
+
+/// Re-read `path` now, e.g. after git ran somewhere the watcher can't see.
+#[tauri::command]
+pub async fn refresh_git_state(
+    state: tauri::State<'_, GitStateHandle>,
+    path: String,
+) -> Result<GitSnapshot, String> {
+    let repo = repo_dir(&path)?;
+    let service = state.inner().clone();
+    service.get(repo, None, true).await
+}
+
+#[tauri::command]
+pub fn forget_git_state(
+    state: tauri::State<'_, GitStateHandle>,
+    path: String,
+) -> Result<(), String> {
+    state.forget(&repo_dir(&path)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::test_util::{commit_file, test_repo};
+    use std::fs;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn recognises_git_state_paths() {
+        assert!(is_git_state_path(Path::new("/p/.git/index")));
+        assert!(is_git_state_path(Path::new("/p/.git/HEAD")));
+        assert!(is_git_state_path(Path::new("/p/.git/refs/heads/main")));
+        assert!(is_git_state_path(Path::new("/p/.git/worktrees/wt/index")));
+        assert!(!is_git_state_path(Path::new("/p/.git/objects/ab/cdef")));
+        assert!(!is_git_state_path(Path::new("/p/.git/index.lock")));
+        assert!(!is_git_state_path(Path::new("/p/src/index")));
+    }
+
+    #[test]
+    fn snapshot_counts_untracked_files_of_a_subdirectory_project() {
+        let repo = test_repo("state_subdir").canonicalize().unwrap();
+        fs::create_dir_all(repo.join("app")).unwrap();
+        fs::create_dir_all(repo.join("other")).unwrap();
+        commit_file(&repo, "app/kept.txt", "1\n", "init");
+        commit_file(&repo, "other/old.txt", "1\n", "other");
+        fs::write(repo.join("app/kept.txt"), "1\n2\n").unwrap();
+        fs::write(repo.join("other/old.txt"), "x\ny\nz\n").unwrap();
+        fs::write(repo.join("app/new.txt"), "a\nb\nc\n").unwrap();
+        fs::write(repo.join("other/skip.txt"), "1\n2\n").unwrap();
+
+        let (snapshot, _) = take_snapshot(&repo.join("app")).unwrap();
+        assert_eq!((snapshot.added, snapshot.removed), (4, 0));
+        let mut paths: Vec<_> = snapshot
+            .file_stats
+            .iter()
+            .map(|s| s.path.as_str())
+            .collect();
+        paths.sort();
+        assert_eq!(paths, ["kept.txt", "new.txt"]);
+        let _ = fs::remove_dir_all(repo);
+    }
+
+    #[tokio::test]
+    async fn caches_until_the_watcher_reports_a_change() {
+        let repo = test_repo("state").canonicalize().unwrap();
+        commit_file(&repo, "a.txt", "one\n", "init");
+        let notified = Arc::new(AtomicUsize::new(0));
+        let counter = notified.clone();
+        let service = GitStateService::new(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        service.set_watch_root(Some(repo.clone()));
+
+        // Concurrent first requests share one refresh.
+        let (first, second) = tokio::join!(
+            service.get(repo.clone(), None, false),
+            service.get(repo.clone(), None, false)
+        );
+        let first = first.unwrap();
+        assert_eq!(first.refreshed_at, second.unwrap().refreshed_at);
+        assert_eq!((first.added, first.removed), (0, 0));
+        assert_eq!(notified.load(Ordering::SeqCst), 1);
+
+        // Unreported changes aren't seen until the watcher says so.
+        fs::write(repo.join("a.txt"), "one\ntwo\n").unwrap();
+        fs::write(repo.join("new.txt"), "x\ny\n").unwrap();
+        let cached = service.get(repo.clone(), None, false).await.unwrap();
+        assert_eq!(cached.refreshed_at, first.refreshed_at);
+
+        service.paths_changed(&[repo.join("a.txt")]);
+        let updated = service.get(repo.clone(), None, false).await.unwrap();
+        assert_eq!((updated.added, updated.removed), (3, 0));
+        assert_eq!(updated.file_stats[0].path, "new.txt");
+        assert_eq!(updated.status.branch.head.as_deref(), Some("main"));
+        assert_eq!(notified.load(Ordering::SeqCst), 2);
+
+        // Unrelated paths leave the cache alone; a forced refresh with no
+        // change doesn't notify.
+        service.paths_changed(&[PathBuf::from("/somewhere/else.txt")]);
+        let same = service.get(repo.clone(), None, false).await.unwrap();
+        assert_eq!(same.refreshed_at, updated.refreshed_at);
+        service.get(repo.clone(), None, true).await.unwrap();
+        assert_eq!(notified.load(Ordering::SeqCst), 2);
+
+        // Once the watcher moves to another project, nothing reports this
+        // repo's changes: every request re-reads it.
+        service.set_watch_root(Some(PathBuf::from("/somewhere")));
+        fs::write(repo.join("later.txt"), "z\n").unwrap();
+        let unwatched = service.get(repo.clone(), None, false).await.unwrap();
+        assert_eq!(unwatched.added, 4);
+
+        let _ = fs::remove_dir_all(repo);
+    }
+}
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Clone, Debug, PartialEq)]
 pub enum GitFileStatus {
@@ -11,13 +11,65 @@ pub enum GitFileStatus {
     Conflicted,
 }
 
-#[derive(Serialize, Clone, Debug)]
+/// One side (index or worktree) of a porcelain v2 `XY` pair.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum FileState {
+    Unmodified,
+    Modified,
+    TypeChanged,
+    Added,
+    Deleted,
+    Renamed,
+    Copied,
+    Unmerged,
+    Untracked,
+    Ignored,
+}
+
+/// Porcelain v2 `S<c><m><u>` field for a submodule entry.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SubmoduleState {
+    /// The recorded commit differs from the submodule's HEAD.
+    pub commit_changed: bool,
+    /// Tracked changes inside the submodule.
+    pub modified: bool,
+    /// Untracked files inside the submodule.
+    pub untracked: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
 pub struct GitStatusEntry {
     pub path: String,
+    /// Both sides collapsed into one state, for tree colouring.
     pub status: GitFileStatus,
+    /// HEAD → index.
+    pub index: FileState,
+    /// Index → worktree.
+    pub worktree: FileState,
+    /// Source path of a rename or copy.
+    pub orig_path: Option<String>,
+    pub submodule: Option<SubmoduleState>,
 }
 
-#[derive(Serialize, Clone, Debug)]
+/// The `# branch.*` headers of `git status --porcelain=v2 --branch`.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct BranchStatus {
+    /// None before the first commit.
+    pub oid: Option<String>,
+    /// None when HEAD is detached.
+    pub head: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: u32,
+    pub behind: u32,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct GitStatus {
+    pub branch: BranchStatus,
+    pub entries: Vec<GitStatusEntry>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
 pub struct FileDiffStat {
     pub path: String,
     pub added: u32,
@@ -44,6 +96,30 @@ pub struct CommitInfo {
     pub file_stats: Vec<CommitFileStat>,
 }
 
+impl CommitInfo {
+    /// Fill in the totals from `file_stats`.
+    pub fn new(
+        hash: String,
+        author: String,
+        date: String,
+        subject: String,
+        body: String,
+        file_stats: Vec<CommitFileStat>,
+    ) -> Self {
+        CommitInfo {
+            hash,
+            author,
+            date,
+            subject,
+            body,
+            files_changed: file_stats.len() as u32,
+            additions: file_stats.iter().map(|s| s.additions).sum(),
+            deletions: file_stats.iter().map(|s| s.deletions).sum(),
+            file_stats,
+        }
+    }
+}
+
 /// A tracked directory that looks like build output / dependencies.
 #[derive(Serialize, Clone, Debug)]
 pub struct SuspiciousTrackedDir {
@@ -62,6 +138,13 @@ pub struct WorktreeInfo {
     pub head: String,
     /// "main" (repo root) | "claude" | "codex" | "manual" — classified by path.
     pub source: String,
+    pub locked: bool,
+    pub lock_reason: Option<String>,
+    /// The worktree's directory is gone; `prune_worktrees` would remove it.
+    pub prunable: bool,
+    /// Has uncommitted changes or untracked files. Filled in by
+    /// `get_git_worktrees`, not the backends.
+    pub dirty: bool,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -71,3 +154,801 @@ pub struct RepoHealth {
     pub tracked_count: u32,
     pub suspicious: Vec<SuspiciousTrackedDir>,
 }
+
+/// Which side of a diff a line number refers to: `Old` for removed lines,
+/// `New` for added ones.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffSide {
+    Old,
+    New,
+}
+
+/// Inclusive range of line numbers on one side of a diff.
+#[derive(Deserialize, Clone, Debug)]
+pub struct LineRange {
+    pub side: DiffSide,
+    pub start: u32,
+    pub end: u32,
+}
+
+/// Part of one file's diff to stage, unstage or discard: whole hunks by index
+/// plus individual changed lines.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct PatchSelection {
+    #[serde(default)]
+    pub hunks: Vec<usize>,
+    #[serde(default)]
+    pub lines: Vec<LineRange>,
+}
+
+/// Options for `git_commit`. An empty message is only allowed with `amend`,
+/// where it keeps the existing message.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct CommitOptions {
+    pub message: String,
+    pub amend: bool,
+    pub signoff: bool,
+    /// "Name <email>" to record as author instead of the configured identity.
+    pub author: Option<String>,
+    pub allow_empty: bool,
+    pub no_verify: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum CommitOutcome {
+    Committed {
+        commit: CommitInfo,
+        /// Anything the commit hooks printed.
+        hook_output: String,
+    },
+    /// A commit hook exited non-zero. `hooks` lists the installed commit hooks,
+    /// since git doesn't say which one refused.
+    HookRejected { hooks: Vec<String>, output: String },
+}
+
+/// Filters and paging for `get_git_log`. Author and message filters are
+/// case-insensitive substring matches, and both must match when both are set.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct LogQuery {
+    pub skip: usize,
+    /// Page size; defaults to 100, capped at 1000.
+    pub limit: Option<usize>,
+    /// `<rev>`, `<a>..<b>` or `<a>...<b>`; HEAD when absent.
+    pub range: Option<String>,
+    /// Only commits touching this path.
+    pub path: Option<String>,
+    pub author: Option<String>,
+    pub grep: Option<String>,
+}
+
+/// One row of the history view. Details come from `get_commit_info`.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct CommitSummary {
+    pub hash: String,
+    /// Parent hashes, first parent first — enough to draw the graph.
+    pub parents: Vec<String>,
+    pub author: String,
+    pub date: String,
+    pub subject: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LogPage {
+    pub commits: Vec<CommitSummary>,
+    /// More commits exist past this page.
+    pub has_more: bool,
+}
+
+/// A local or remote-tracking branch, as listed by `list_branches`.
+#[derive(Serialize, Clone, Debug)]
+pub struct BranchInfo {
+    /// Short name: `main`, `origin/main`.
+    pub name: String,
+    pub is_remote: bool,
+    pub is_current: bool,
+    /// Tip commit hash.
+    pub head: String,
+    /// Upstream short name for local branches that track one.
+    pub upstream: Option<String>,
+    /// The configured upstream no longer exists.
+    pub upstream_gone: bool,
+    pub ahead: u32,
+    pub behind: u32,
+    /// Committer date of the tip, ISO 8601.
+    pub last_commit_date: String,
+    pub subject: String,
+}
+
+/// Where `create_worktree` puts a new worktree named `name`.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(tag = "scheme", rename_all = "snake_case")]
+pub enum WorktreeLocation {
+    /// `<repo>/.claude/worktrees/<name>`, alongside Claude's own worktrees.
+    Claude,
+    /// `~/.codex/worktrees/<repo-name>/<name>`.
+    Codex,
+    /// `<repo-parent>/<repo-name>.worktrees/<name>`.
+    #[default]
+    Sibling,
+    /// Exactly `path`, which must be absolute.
+    Path { path: String },
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct CreateWorktreeOptions {
+    /// Directory name for the worktree, usually derived from the thread.
+    pub name: String,
+    /// Create this branch for the worktree. Without it, `start_point` is
+    /// checked out as-is (a local branch, or detached for any other ref).
+    pub branch: Option<String>,
+    /// Where the new branch starts, or what to check out; HEAD when absent.
+    pub start_point: Option<String>,
+    pub location: WorktreeLocation,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// A merge commit on the target (`--no-ff`).
+    #[default]
+    Merge,
+    /// One commit on the target with the branch's combined changes.
+    Squash,
+    /// Rebase the branch onto the target, then fast-forward the target.
+    Rebase,
+}
+
+/// A file that conflicts (or would) when merging, with git's description.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MergeConflict {
+    pub path: String,
+    /// "contents", "modify/delete", "rename/delete", ... — the part of git's
+    /// `CONFLICT (<kind>)` tag in parentheses.
+    pub kind: String,
+    pub message: String,
+}
+
+/// What merging a worktree's branch into a target branch would do, from a
+/// `git merge-tree` dry run that touches neither worktree.
+#[derive(Serialize, Clone, Debug)]
+pub struct MergePreview {
+    pub source_branch: String,
+    pub target_branch: String,
+    /// Worktree where the target branch is checked out, which is where the
+    /// merge happens. None when it isn't checked out anywhere.
+    pub target_worktree: Option<String>,
+    pub merge_base: Option<String>,
+    /// Commits on the source branch that the target lacks.
+    pub ahead: u32,
+    /// Commits on the target branch that the source lacks.
+    pub behind: u32,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct MergeBackOptions {
+    /// The linked worktree whose branch is merged back.
+    pub worktree_path: String,
+    pub target_branch: String,
+    pub strategy: MergeStrategy,
+    /// Commit message for a merge or squash; git's default (or the squashed
+    /// subjects) when absent.
+    pub message: Option<String>,
+    /// Go ahead when the preview predicts conflicts and leave them for
+    /// resolution. Otherwise conflicts stop the workflow with nothing changed.
+    pub allow_conflicts: bool,
+    pub remove_worktree: bool,
+    pub delete_branch: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeBackStep {
+    Preview,
+    Rebase,
+    Merge,
+    Squash,
+    FastForward,
+    RemoveWorktree,
+    DeleteBranch,
+    Done,
+}
+
+/// Progress streamed by `merge_back`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "data")]
+pub enum MergeBackEvent {
+    Step { step: MergeBackStep, message: String },
+    Conflict(MergeConflict),
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MergeBackOutcome {
+    Merged {
+        /// The target branch's new tip.
+        commit: String,
+        worktree_removed: bool,
+        branch_deleted: bool,
+        /// Why removing the worktree or deleting the branch failed; the merge
+        /// itself succeeded.
+        cleanup_error: Option<String>,
+    },
+    Conflicts {
+        conflicts: Vec<MergeConflict>,
+        /// Worktree left mid-merge or mid-rebase for resolution; None when
+        /// nothing was changed.
+        in_progress: Option<String>,
+    },
+}
+
+/// One `<<<<<<<` … `>>>>>>>` block in a conflicted file. Line numbers are
+/// 1-based and include the marker lines.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConflictRegion {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: String,
+    /// Only present with `merge.conflictStyle = diff3` (or zdiff3).
+    pub base: Option<String>,
+    pub theirs: String,
+}
+
+/// A conflicted file's three index stages and its marked-up working copy.
+/// A stage is None when that side doesn't have the file (e.g. deleted on
+/// one side) or the file is binary.
+#[derive(Serialize, Clone, Debug)]
+pub struct ConflictFile {
+    pub path: String,
+    pub base: Option<String>,
+    pub ours: Option<String>,
+    pub theirs: Option<String>,
+    pub binary: bool,
+    pub regions: Vec<ConflictRegion>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "side", rename_all = "snake_case")]
+pub enum RegionChoice {
+    Ours,
+    Theirs,
+    Base,
+    /// Ours followed by theirs.
+    Both,
+    Text { text: String },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// One choice per region, in file order.
+    Regions { choices: Vec<RegionChoice> },
+    /// Replace the whole file.
+    Content { content: String },
+    /// Resolve by deleting the file (e.g. accepting a modify/delete).
+    Delete,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitOperationKind {
+    Merge,
+    Rebase,
+    CherryPick,
+}
+
+/// A merge, rebase or cherry-pick that stopped part-way.
+#[derive(Serialize, Clone, Debug)]
+pub struct GitOperation {
+    pub kind: GitOperationKind,
+    /// Files still unmerged.
+    pub conflicted: Vec<String>,
+    /// Rebase progress: the step stopped at and the total.
+    pub step: Option<(u32, u32)>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WhitespaceMode {
+    #[default]
+    Show,
+    /// `--ignore-space-at-eol`
+    IgnoreAtEol,
+    /// `--ignore-space-change`
+    IgnoreChange,
+    /// `--ignore-all-space`
+    IgnoreAll,
+}
+
+/// How to compute a structured diff.
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct DiffOptions {
+    /// Lines of context around each hunk; git's default (3) when absent.
+    pub context_lines: Option<u32>,
+    pub whitespace: WhitespaceMode,
+    /// Split changed lines that pair up into changed and unchanged segments.
+    pub word_diff: bool,
+    /// Report renames and copies instead of a delete plus an add.
+    pub detect_renames: bool,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffChange {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffLineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+/// Part of a changed line; `changed` marks the words that differ from the
+/// line it was paired with.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct LineSegment {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DiffLine {
+    pub kind: DiffLineKind,
+    pub old_line: Option<u32>,
+    pub new_line: Option<u32>,
+    /// Without the leading `+`/`-`/` ` or the line ending.
+    pub content: String,
+    /// Followed by `\ No newline at end of file`.
+    pub no_newline: bool,
+    /// Only with `word_diff`, for a changed line that pairs with one on the
+    /// other side and shares some of its words.
+    pub segments: Option<Vec<LineSegment>>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    /// The function/section name git prints after the `@@` range.
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DiffFile {
+    /// None for an added file.
+    pub old_path: Option<String>,
+    /// None for a deleted file.
+    pub new_path: Option<String>,
+    pub change: DiffChange,
+    /// Rename/copy similarity percentage.
+    pub similarity: Option<u32>,
+    /// File modes, set when added, deleted or changed.
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub binary: bool,
+    pub added: u32,
+    pub removed: u32,
+    pub hunks: Vec<DiffHunk>,
+}
+
+/// One side of a comparison between two states of the repo.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiffEndpoint {
+    /// A commit, branch or tag.
+    Ref { rev: String },
+    /// Where two refs diverged, e.g. the point a worktree branch left main.
+    MergeBase { a: String, b: String },
+    /// What's staged in the worktree the command runs in.
+    Index,
+    /// The files on disk in a worktree of the repo (this one or a linked
+    /// one), untracked files included and ignored ones not.
+    WorkingTree { path: String },
+}
+
+/// A path that differs between two endpoints, without its content changes.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ChangedFile {
+    pub path: String,
+    /// Source path of a rename or copy.
+    pub old_path: Option<String>,
+    pub change: DiffChange,
+    /// Rename/copy similarity percentage.
+    pub similarity: Option<u32>,
+}
+
+/// Consecutive lines of a file last changed by the same commit.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct BlameRange {
+    /// 1-based line in the blamed version of the file.
+    pub start_line: u32,
+    pub line_count: u32,
+    /// Where the lines start in `commit`'s version of the file.
+    pub orig_start_line: u32,
+    /// The file's path in `commit`, which differs after a rename.
+    pub orig_path: String,
+    pub commit: String,
+    /// Not committed yet: `commit` is all zeros and the author fields are
+    /// git's placeholder.
+    pub uncommitted: bool,
+    pub author: String,
+    pub author_email: String,
+    pub date: String,
+    pub summary: String,
+}
+
+/// Streamed by `get_git_blame` as git finds each batch of ranges, in no
+/// particular line order.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "data")]
+pub enum BlameEvent {
+    Ranges(Vec<BlameRange>),
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StashEntry {
+    /// Position in `git stash list`, newest first; `name` is `stash@{index}`.
+    pub index: u32,
+    pub name: String,
+    pub commit: String,
+    /// When the stash was made.
+    pub date: String,
+    /// The branch it was made on; None when HEAD was detached.
+    pub branch: Option<String>,
+    pub message: String,
+    /// Untracked files included.
+    pub file_stats: Vec<FileDiffStat>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StashApplyOutcome {
+    /// Paths left with conflict markers; empty when the stash applied cleanly.
+    pub conflicted: Vec<String>,
+    /// The stash was popped. A pop that conflicts keeps it.
+    pub dropped: bool,
+}
+
+/// Progress streamed by `git_fetch`, `git_pull` and `git_push`.
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "event", content = "data")]
+pub enum RemoteEvent {
+    /// A `Receiving objects: 45% (9/20)`-style update; `phase` is the part
+    /// before the colon.
+    Progress {
+        phase: String,
+        percent: u32,
+        current: u64,
+        total: u64,
+    },
+    /// Any other line git prints: ref updates, hints, messages from the remote.
+    Message(String),
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct FetchOptions {
+    /// The current branch's remote (or `origin`) when None.
+    pub remote: Option<String>,
+    /// Fetch every remote; `remote` is ignored.
+    pub all: bool,
+    /// Delete remote-tracking branches that no longer exist on the remote.
+    pub prune: bool,
+    /// Kill the fetch after this long; five minutes when None.
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PullMode {
+    /// Refuse to create a merge commit; fails if the branches have diverged.
+    #[default]
+    FastForwardOnly,
+    Rebase,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PullOptions {
+    /// The upstream of the current branch when both are None.
+    pub remote: Option<String>,
+    /// Needs `remote`.
+    pub branch: Option<String>,
+    pub mode: PullMode,
+    pub timeout_secs: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct PushOptions {
+    pub remote: Option<String>,
+    /// The current branch when None.
+    pub branch: Option<String>,
+    /// Record the pushed branch as the upstream.
+    pub set_upstream: bool,
+    /// Overwrite the remote branch, but only if it's still where it was
+    /// last fetched.
+    pub force_with_lease: bool,
+    pub timeout_secs: Option<u64>,
+}
+
+/// Everything the UI shows about a repo's uncommitted state, as cached by
+/// the git state service and pushed in `git-state-changed` events.
+#[derive(Serialize, Clone, Debug)]
+pub struct GitSnapshot {
+    pub path: String,
+    pub status: GitStatus,
+    /// Totals across `file_stats`.
+    pub added: u32,
+    pub removed: u32,
+    /// Uncommitted line counts per file against HEAD, untracked files counted
+    /// as all added; largest first.
+    pub file_stats: Vec<FileDiffStat>,
+    /// How long the refresh took.
+    pub duration_ms: u64,
+    /// Milliseconds since the Unix epoch.
+    pub refreshed_at: u64,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NestedRepoKind {
+    Submodule,
+    /// An independent repo checked out inside the project, e.g. vendored
+    /// code or a sub-repo of a monorepo.
+    Nested,
+}
+
+/// A git repo inside a project. Pass `path` to the status, state and diff
+/// commands to query it on its own.
+#[derive(Serialize, Clone, Debug)]
+pub struct NestedRepo {
+    pub path: String,
+    /// Relative to the project root.
+    pub relative_path: String,
+    pub kind: NestedRepoKind,
+    /// Submodules only: the commit the superproject records.
+    pub recorded_commit: Option<String>,
+    /// False for a submodule that isn't checked out, which has no repo of
+    /// its own to query yet.
+    pub initialized: bool,
+    /// The commit checked out.
+    pub head: Option<String>,
+    /// None when HEAD is detached, as it usually is in a submodule.
+    pub branch: Option<String>,
+    /// Submodules only: HEAD isn't the recorded commit.
+    pub out_of_sync: bool,
+    /// Any uncommitted change, untracked files included.
+    pub dirty: bool,
+    /// Uncommitted line counts, as in [`GitSnapshot`].
+    pub added: u32,
+    pub removed: u32,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SubmoduleUpdateOptions {
+    /// Submodule paths relative to the repo; all of them when empty.
+    pub paths: Vec<String>,
+    /// Initialize submodules that aren't yet.
+    pub init: bool,
+    /// Update submodules of submodules too.
+    pub recursive: bool,
+    /// Give up after this long; five minutes when None.
+    pub timeout_secs: Option<u64>,
+}
+
+/// What `untrack_directory` did, or would do on a dry run.
+#[derive(Serialize, Clone, Debug)]
+pub struct UntrackDirReport {
+    pub dry_run: bool,
+    pub dir: String,
+    /// Tracked files taken out of the index. They stay on disk.
+    pub untracked_files: u32,
+    /// The line appended to `.gitignore`; None when it already ignores `dir`.
+    pub gitignore_entry: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ConfigChange {
+    pub key: String,
+    /// None when unset.
+    pub from: Option<String>,
+    pub to: String,
+}
+
+/// What `enable_status_cache` changed, or would change on a dry run, and how
+/// long `git status` took either side of it.
+#[derive(Serialize, Clone, Debug)]
+pub struct StatusCacheReport {
+    pub dry_run: bool,
+    /// False when this git has no built-in fsmonitor daemon (Linux builds);
+    /// only the untracked cache is enabled then.
+    pub fsmonitor_supported: bool,
+    /// Empty when everything was already enabled.
+    pub changes: Vec<ConfigChange>,
+    pub before_ms: u64,
+    /// None on a dry run.
+    pub after_ms: Option<u64>,
+}
+
+/// `git count-objects -v`, sizes in KiB.
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ObjectStats {
+    pub loose_objects: u64,
+    pub loose_size_kb: u64,
+    pub packs: u64,
+    pub pack_size_kb: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MaintenanceReport {
+    pub dry_run: bool,
+    /// The git command run, or that would be.
+    pub command: String,
+    pub before: ObjectStats,
+    /// None on a dry run.
+    pub after: Option<ObjectStats>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct IndexLock {
+    pub path: String,
+    pub age_secs: u64,
+    /// Git processes working in the repo, or anything with the lock open.
+    /// The lock is left alone while this isn't empty.
+    pub held_by: Vec<u32>,
+    pub removed: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct IndexLockReport {
+    pub dry_run: bool,
+    /// Every `index.lock` found, in the main git dir and each worktree's.
+    pub locks: Vec<IndexLock>,
+}
+
+/// An executable hook script.
+#[derive(Serialize, Clone, Debug)]
+pub struct GitHook {
+    pub name: String,
+    pub path: String,
+    /// False for a hook in `.git/hooks` that `core.hooksPath` overrides:
+    /// git won't run it.
+    pub active: bool,
+    /// The hook framework that installed it, when recognizable.
+    pub framework: Option<String>,
+}
+
+/// A hook manager (pre-commit, husky, lefthook) configured in the repo.
+#[derive(Serialize, Clone, Debug)]
+pub struct HookFramework {
+    pub name: String,
+    /// Relative to the worktree root.
+    pub config_path: String,
+    /// An active hook runs it. False means it's configured, but its install
+    /// step (`pre-commit install`, `npm install`...) hasn't run here.
+    pub installed: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HooksOverview {
+    /// Where git looks for hooks: `core.hooksPath` if set, else `.git/hooks`.
+    pub hooks_dir: String,
+    /// `core.hooksPath` as configured.
+    pub hooks_path: Option<String>,
+    pub hooks: Vec<GitHook>,
+    pub frameworks: Vec<HookFramework>,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct RunHookOptions {
+    /// The commit message given to `prepare-commit-msg` and `commit-msg`.
+    pub message: Option<String>,
+    /// Kill the hook after this long; ten minutes when None.
+    pub timeout_secs: Option<u64>,
+}
+
+/// Output streamed by `run_git_hook`, line by line.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "event", content = "data")]
+pub enum HookEvent {
+    Stdout(String),
+    Stderr(String),
+    /// The last event. `code` is None when the hook was killed by a signal.
+    Exited { code: Option<i32> },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HookRunResult {
+    pub hook: String,
+    pub success: bool,
+    pub code: Option<i32>,
+    /// The commit message after `prepare-commit-msg` or `commit-msg` ran,
+    /// which may have rewritten it.
+    pub message: Option<String>,
+    pub duration_ms: u64,
+}
+
+/// Where a thread's worktree stood when the thread began: a commit whose
+/// tree is the files on disk at the time, untracked ones included.
+#[derive(Serialize, Clone, Debug)]
+pub struct ThreadSnapshot {
+    pub thread_id: String,
+    pub commit: String,
+    /// HEAD at the time; None before the first commit.
+    pub head: Option<String>,
+    pub created_at: String,
+}
+
+/// Where `export_patches` writes.
+#[derive(Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PatchOutput {
+    /// One numbered `.patch` file per commit in `dir`, created if needed.
+    Series { dir: String },
+    /// Every commit in one mbox file, ready for `git am`.
+    Mbox { path: String },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PatchExport {
+    /// The files written.
+    pub files: Vec<String>,
+    pub commits: u32,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct SummaryCommit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub subject: String,
+}
+
+/// The changed files under one directory (`.` for the root), not counting
+/// subdirectories.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct DirectoryChanges {
+    pub dir: String,
+    pub added: u32,
+    pub removed: u32,
+    pub files: Vec<FileDiffStat>,
+}
+
+/// What a range of commits changes, shaped for a pull request description.
+#[derive(Serialize, Clone, Debug)]
+pub struct ChangeSummary {
+    /// The commits on the head side, oldest first.
+    pub commits: Vec<SummaryCommit>,
+    pub files_changed: u32,
+    pub added: u32,
+    pub removed: u32,
+    pub directories: Vec<DirectoryChanges>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryFormat {
+    Json,
+    Markdown,
+}
@@ -0,0 +1,355 @@
+//! Creating, removing, pruning and locking linked worktrees.
+
+use super::backend::GitBackend;
+use super::branches::{dirty_error, validate_branch_name};
+use super::types::{CreateWorktreeOptions, WorktreeInfo, WorktreeLocation};
+use super::{git_failure, query_backend, repo_dir, run_git, validate_rev, BACKEND_TIMEOUT};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// `worktree add` checks out a full tree and `remove` deletes one, which in
+/// a large repo takes far longer than a query.
+const WORKTREE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Compare worktree paths the way the user sees them: symlinks resolved
+/// when the directory still exists, verbatim when it doesn't.
+pub(crate) fn same_path(a: &str, b: &Path) -> bool {
+    let a = Path::new(a);
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// The linked worktree of `repo` at `path`. The main worktree is refused:
+/// none of the operations here make sense for it.
+pub(crate) fn find_linked(
+    git: &dyn GitBackend,
+    repo: &Path,
+    path: &str,
+) -> Result<WorktreeInfo, String> {
+    let worktrees = git.worktrees(repo)?;
+    let index = worktrees
+        .iter()
+        .position(|w| same_path(&w.path, Path::new(path)))
+        .ok_or_else(|| format!("Not a worktree of this repository: {}", path))?;
+    if index == 0 {
+        return Err("The main worktree can't be removed or locked".to_string());
+    }
+    Ok(worktrees.into_iter().nth(index).unwrap())
+}
+
+fn validate_worktree_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.starts_with('-')
+        || name.contains(['/', '\\'])
+    {
+        return Err(format!("Invalid worktree name: {}", name));
+    }
+    Ok(())
+}
+
+/// Resolve `location` for a worktree called `name` of the repo whose main
+/// worktree is `main`.
+fn worktree_path(main: &Path, name: &str, location: &WorktreeLocation) -> Result<PathBuf, String> {
+    let repo_name = main
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Cannot name worktrees for {}", main.display()))?;
+    Ok(match location {
+        WorktreeLocation::Claude => main.join(".claude/worktrees").join(name),
+        WorktreeLocation::Codex => {
+            let home = std::env::var("HOME").map_err(|_| "HOME not set".to_string())?;
+            Path::new(&home)
+                .join(".codex/worktrees")
+                .join(repo_name)
+                .join(name)
+        }
+        WorktreeLocation::Sibling => main
+            .with_file_name(format!("{}.worktrees", repo_name))
+            .join(name),
+        WorktreeLocation::Path { path } => {
+            let path = PathBuf::from(path);
+            if !path.is_absolute() {
+                return Err(format!(
+                    "Worktree path must be absolute: {}",
+                    path.display()
+                ));
+            }
+            path
+        }
+    })
+}
+
+fn create(
+    git: &dyn GitBackend,
+    repo: &Path,
+    options: &CreateWorktreeOptions,
+) -> Result<WorktreeInfo, String> {
+    if let Some(branch) = &options.branch {
+        validate_branch_name(repo, branch)?;
+    }
+
+    let main = git
+        .worktrees(repo)?
+        .into_iter()
+        .next()
+        .ok_or_else(|| "Not a git repository".to_string())?;
+    let path = worktree_path(Path::new(&main.path), &options.name, &options.location)?;
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()));
+    }
+    let path_arg = path.to_string_lossy().to_string();
+
+    let mut args = vec!["worktree", "add", "--quiet"];
+    match (&options.branch, &options.start_point) {
+        (Some(branch), start) => {
+            args.extend(["-b", branch.as_str(), path_arg.as_str()]);
+            args.extend(start.as_deref());
+        }
+        (None, Some(start)) => args.extend([path_arg.as_str(), start.as_str()]),
+        (None, None) => args.extend(["--detach", path_arg.as_str()]),
+    }
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        return Err(git_failure("create worktree", &output));
+    }
+
+    git.worktrees(repo)?
+        .into_iter()
+        .find(|w| same_path(&w.path, &path))
+        .ok_or_else(|| format!("Created {} but git doesn't list it", path.display()))
+}
+
+/// Create a worktree for a new thread, either on a new branch or checking
+/// out an existing ref, and return its listing entry.
+#[tauri::command]
+pub async fn create_worktree(
+    repo_path: String,
+    options: CreateWorktreeOptions,
+) -> Result<WorktreeInfo, String> {
+    if let Some(start) = &options.start_point {
+        validate_rev(start)?;
+    }
+    if !matches!(options.location, WorktreeLocation::Path { .. }) {
+        validate_worktree_name(&options.name)?;
+    }
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, WORKTREE_TIMEOUT, move |git, repo| {
+        create(git, repo, &options)
+    })
+    .await
+}
+
+pub(crate) fn remove(
+    git: &dyn GitBackend,
+    repo: &Path,
+    worktree_path: &str,
+    force: bool,
+) -> Result<(), String> {
+    let worktree = find_linked(git, repo, worktree_path)?;
+    if worktree.locked {
+        return Err(format!(
+            "Cannot remove {}: the worktree is locked{}",
+            worktree.path,
+            worktree
+                .lock_reason
+                .map(|r| format!(" ({})", r))
+                .unwrap_or_default()
+        ));
+    }
+    if worktree.prunable {
+        return Err(format!(
+            "{} no longer exists; prune worktrees to clean it up",
+            worktree.path
+        ));
+    }
+    if !force {
+        let dirty: Vec<String> = git
+            .status(Path::new(&worktree.path))?
+            .unwrap_or_default()
+            .entries
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        if !dirty.is_empty() {
+            return Err(dirty_error(&format!("remove {}", worktree.path), &dirty));
+        }
+    }
+
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(&worktree.path);
+    let output = run_git(repo, &args)?;
+    if !output.status.success() {
+        return Err(git_failure("remove worktree", &output));
+    }
+    Ok(())
+}
+
+/// Remove a linked worktree. Uncommitted changes or untracked files block
+/// removal unless `force`; a locked worktree must be unlocked first.
+#[tauri::command]
+pub async fn remove_worktree(
+    repo_path: String,
+    worktree_path: String,
+    force: Option<bool>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, WORKTREE_TIMEOUT, move |git, repo| {
+        remove(git, repo, &worktree_path, force.unwrap_or(false))
+    })
+    .await
+}
+
+/// Forget worktrees whose directories are gone. Returns their paths; with
+/// `dry_run` nothing is removed.
+#[tauri::command]
+pub async fn prune_worktrees(
+    repo_path: String,
+    dry_run: Option<bool>,
+) -> Result<Vec<String>, String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let stale: Vec<String> = git
+            .worktrees(repo)?
+            .into_iter()
+            .filter(|w| w.prunable)
+            .map(|w| w.path)
+            .collect();
+        if !dry_run.unwrap_or(false) && !stale.is_empty() {
+            let output = run_git(repo, ["worktree", "prune"])?;
+            if !output.status.success() {
+                return Err(git_failure("prune worktrees", &output));
+            }
+        }
+        Ok(stale)
+    })
+    .await
+}
+
+/// Lock a worktree so it isn't pruned, moved or removed (e.g. while it lives
+/// on a detached drive or an agent is still using it).
+#[tauri::command]
+pub async fn lock_worktree(
+    repo_path: String,
+    worktree_path: String,
+    reason: Option<String>,
+) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let worktree = find_linked(git, repo, &worktree_path)?;
+        let mut args = vec!["worktree", "lock"];
+        if let Some(reason) = reason.as_deref().filter(|r| !r.trim().is_empty()) {
+            args.extend(["--reason", reason]);
+        }
+        args.push(&worktree.path);
+        let output = run_git(repo, &args)?;
+        if !output.status.success() {
+            return Err(git_failure("lock worktree", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn unlock_worktree(repo_path: String, worktree_path: String) -> Result<(), String> {
+    let repo = repo_dir(&repo_path)?;
+    query_backend(repo, BACKEND_TIMEOUT, move |git, repo| {
+        let worktree = find_linked(git, repo, &worktree_path)?;
+        let output = run_git(repo, ["worktree", "unlock", &worktree.path])?;
+        if !output.status.success() {
+            return Err(git_failure("unlock worktree", &output));
+        }
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git::get_git_worktrees;
+    use crate::git::test_util::{commit_file, test_repo};
+    use std::fs;
+
+    #[tokio::test]
+    async fn worktree_lifecycle() {
+        let repo = test_repo("worktrees");
+        let root = repo.to_string_lossy().to_string();
+        commit_file(&repo, "a.txt", "1\n", "init");
+        let sibling_dir = repo.with_file_name(format!(
+            "{}.worktrees",
+            repo.file_name().unwrap().to_string_lossy()
+        ));
+
+        let created = create_worktree(
+            root.clone(),
+            CreateWorktreeOptions {
+                name: "thread-1".into(),
+                branch: Some("agent/thread-1".into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.branch.as_deref(), Some("agent/thread-1"));
+        assert!(same_path(&created.path, &sibling_dir.join("thread-1")));
+        let wt = created.path.clone();
+
+        fs::write(Path::new(&wt).join("new.txt"), "x").unwrap();
+        let listed = get_git_worktrees(root.clone()).await.unwrap();
+        assert!(!listed[0].dirty);
+        assert!(listed[1].dirty);
+        let err = remove_worktree(root.clone(), wt.clone(), None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("new.txt"), "{err}");
+
+        lock_worktree(root.clone(), wt.clone(), Some("agent running".into()))
+            .await
+            .unwrap();
+        let listed = get_git_worktrees(root.clone()).await.unwrap();
+        assert!(listed[1].locked);
+        assert_eq!(listed[1].lock_reason.as_deref(), Some("agent running"));
+        let err = remove_worktree(root.clone(), wt.clone(), Some(true))
+            .await
+            .unwrap_err();
+        assert!(err.contains("locked"), "{err}");
+        unlock_worktree(root.clone(), wt.clone()).await.unwrap();
+        remove_worktree(root.clone(), wt.clone(), Some(true))
+            .await
+            .unwrap();
+        assert!(!Path::new(&wt).exists());
+
+        // A worktree whose directory vanished is prunable.
+        let gone = create_worktree(
+            root.clone(),
+            CreateWorktreeOptions {
+                name: "detached".into(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert!(gone.detached);
+        fs::remove_dir_all(&gone.path).unwrap();
+        assert!(get_git_worktrees(root.clone()).await.unwrap()[1].prunable);
+        assert_eq!(
+            prune_worktrees(root.clone(), Some(true)).await.unwrap(),
+            vec![gone.path.clone()]
+        );
+        assert_eq!(prune_worktrees(root.clone(), None).await.unwrap().len(), 1);
+        assert_eq!(get_git_worktrees(root.clone()).await.unwrap().len(), 1);
+
+        assert!(remove_worktree(root.clone(), root.clone(), None)
+            .await
+            .is_err());
+        let _ = fs::remove_dir_all(&sibling_dir);
+        let _ = fs::remove_dir_all(repo);
+    }
+}
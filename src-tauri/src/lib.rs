@@ -319,6 +319,8 @@ pub fn run() {
     let pty_state_for_menu = pty_state.clone();
     let pty_state_for_window = pty_state.clone();
     let watcher_state: fs::watcher::WatcherState = Arc::new(std::sync::Mutex::new(None));
+    let disk_usage_state: fs::disk_usage::DiskUsageState = Arc::default();
+    let remote_op_state: git::remote::RemoteOpState = Arc::default();
     let transcript_state: transcript::TranscriptState =
         Arc::new(std::sync::Mutex::new(transcript::TranscriptManager::new()));
     let usage_state: usage::UsageState = usage::new_state();
@@ -349,6 +351,8 @@ pub fn run() {
         .manage(pty_state.clone())
         .manage(pty_session_count)
         .manage(watcher_state)
+        .manage(disk_usage_state)
+        .manage(remote_op_state)
         .manage(transcript_state)
         .manage(usage_state)
         .manage(MenuState {
@@ -367,6 +371,13 @@ pub fn run() {
             // would otherwise double-fire), then consume Heed's state.json.
             cutover::run();
             heed_client::start_state_watcher(app.handle().clone());
+            {
+                use tauri::Emitter;
+                let handle = app.handle().clone();
+                app.manage(git::state::GitStateService::new(move |snapshot| {
+                    let _ = handle.emit(git::state::GIT_STATE_EVENT, snapshot);
+                }));
+            }
             #[cfg(target_os = "macos")]
             {
                 use tauri::menu::{CheckMenuItem, Menu, MenuItemBuilder, PredefinedMenuItem, Submenu};
@@ -614,15 +625,21 @@ pub fn run() {
             resize_pty,
             kill_pty,
             fs::read_directory,
+            fs::archive::read_archive_directory,
+            fs::archive::read_archive_file,
             fs::scan_all_files,
             fs::get_recent_files,
             fs::read_file,
             fs::write_file,
+            fs::sniff_file,
             fs::read_file_base64,
+            fs::inspect::read_file_hex,
             fs::preview_file,
             fs::reveal_in_finder,
             fs::open_in_default_app,
             fs::path_exists,
+            fs::disk_usage::analyze_disk_usage,
+            fs::disk_usage::cancel_disk_usage,
             fs::watcher::start_watching,
             fs::watcher::stop_watching,
             git::get_git_branch,
@@ -634,7 +651,69 @@ pub fn run() {
             git::get_all_file_diff_stats,
             git::get_commit_info,
             git::get_commit_diff,
+            git::get_git_log,
+            git::blame::get_git_blame,
+            git::blame::get_line_history,
+            git::diff::get_structured_diff,
+            git::diff::get_structured_commit_diff,
+            git::compare::get_diff_between,
+            git::compare::get_numstat_between,
+            git::compare::get_changed_files_between,
             git::diagnose_repo_health,
+            git::health::untrack_directory,
+            git::health::enable_status_cache,
+            git::health::run_git_maintenance,
+            git::health::remove_stale_index_locks,
+            git::stash::list_stashes,
+            git::stash::create_stash,
+            git::stash::apply_stash,
+            git::stash::pop_stash,
+            git::stash::drop_stash,
+            git::stash::show_stash,
+            git::remote::git_fetch,
+            git::remote::git_pull,
+            git::remote::git_push,
+            git::remote::cancel_remote_operation,
+            git::state::get_git_state,
+            git::state::refresh_git_state,
+            git::state::forget_git_state,
+            git::nested::list_nested_repos,
+            git::nested::init_submodules,
+            git::nested::update_submodules,
+            git::hooks::list_git_hooks,
+            git::hooks::run_git_hook,
+            git::snapshots::create_thread_snapshot,
+            git::snapshots::get_thread_snapshot,
+            git::snapshots::get_thread_changes,
+            git::snapshots::get_thread_numstat,
+            git::snapshots::delete_thread_snapshot,
+            git::patches::export_patches,
+            git::patches::get_change_summary,
+            git::patches::export_change_summary,
+            git::branches::list_branches,
+            git::branches::create_branch,
+            git::branches::switch_branch,
+            git::branches::rename_branch,
+            git::branches::delete_branch,
+            git::worktrees::create_worktree,
+            git::worktrees::remove_worktree,
+            git::worktrees::prune_worktrees,
+            git::worktrees::lock_worktree,
+            git::worktrees::unlock_worktree,
+            git::merge::preview_merge_back,
+            git::merge::merge_back,
+            git::conflicts::get_conflict,
+            git::conflicts::resolve_conflict,
+            git::conflicts::get_git_operation,
+            git::conflicts::abort_git_operation,
+            git::conflicts::continue_git_operation,
+            git::staging::stage_files,
+            git::staging::unstage_files,
+            git::staging::discard_file_changes,
+            git::staging::stage_selection,
+            git::staging::unstage_selection,
+            git::staging::discard_selection,
+            git::commit::git_commit,
             transcript::watch_transcript,
             transcript::unwatch_transcript,
             transcript::switch_transcript,
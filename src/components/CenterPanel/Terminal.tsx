@@ -8,6 +8,7 @@ import { Channel } from "@tauri-apps/api/core";
 import {
   spawnPty,
   registerHeedOwner,
+  createThreadSnapshot,
   writePty,
   resizePty,
   killPty,
@@ -23,7 +24,7 @@ import {
   RESIZE_DEBOUNCE_MS,
 } from "../../lib/constants";
 import { getTerminalTheme, DARK_PALETTE, LIGHT_PALETTE } from "../../lib/themes";
-import { useAppStore } from "../../store/appStore";
+import { threadRepoPath, useAppStore } from "../../store/appStore";
 import { attributeEnv } from "../../lib/worktree";
 import type { Thread, ThreadType, ScheduledJob } from "../../store/types";
 import { THREAD_NEW_LABELS } from "../../store/types";
@@ -1721,7 +1722,32 @@ function createTerminalInstance(
       .catch(console.error);
   };
 
-  spawnWithCommand(command);
+  // Record where the tree stands before the agent can touch it, for the
+  // thread's "changes since it began" view. Resumed threads keep their first
+  // snapshot; projects that aren't git repos just don't get one. A failure
+  // is reported in the terminal but doesn't keep the thread from starting.
+  const snapshotRepo = threadRepoPath(useAppStore.getState().projects, thread);
+  const snapshotReady = snapshotRepo
+    ? createThreadSnapshot(snapshotRepo, thread.id).then(
+        () => {},
+        (err) => {
+          console.error(`[terminal] failed to snapshot ${snapshotRepo} for ${thread.id}:`, err);
+          try {
+            terminal.write(
+              `\x1b[33m[Could not record the thread's starting point: ${err}]\x1b[0m\r\n`,
+            );
+          } catch {
+            // Terminal already disposed.
+          }
+        },
+      )
+    : Promise.resolve();
+
+  void snapshotReady.then(() => {
+    // Closed while the snapshot ran: there's nothing left to attach a PTY to.
+    if (instances.get(sessionId) !== instance) return;
+    spawnWithCommand(command);
+  });
 
   // Tag this thread in Heed's ownership overlay so it shows up as ours in
   // ~/.heed/state.json. Claude (and resumed Codex) carry a known native id and
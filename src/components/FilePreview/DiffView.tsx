@@ -1,7 +1,5 @@
 import { useEffect, useState } from "react";
-import { html as diff2htmlHtml } from "diff2html";
-import { getGitDiff } from "../../lib/tauri";
-import { sanitizeHtml } from "../../lib/sanitize";
+import { getStructuredDiff, type DiffFile, type DiffHunk, type DiffLine } from "../../lib/tauri";
 import { useAppStore } from "../../store/appStore";
 import { resolveProjectRootForPath } from "../../lib/worktree";
 
@@ -11,8 +9,7 @@ interface DiffViewProps {
 }
 
 export default function DiffView({ filePath, layout }: DiffViewProps) {
-  const [diffHtml, setDiffHtml] = useState<string | null>(null);
-  const [empty, setEmpty] = useState(false);
+  const [files, setFiles] = useState<DiffFile[] | null>(null);
   const [error, setError] = useState<string | null>(null);
 
   const activeProject = useAppStore((s) => s.getActiveProject());
@@ -26,8 +23,7 @@ export default function DiffView({ filePath, layout }: DiffViewProps) {
   );
 
   useEffect(() => {
-    setDiffHtml(null);
-    setEmpty(false);
+    setFiles(null);
     setError(null);
 
     if (!projectPath) return;
@@ -35,40 +31,166 @@ export default function DiffView({ filePath, layout }: DiffViewProps) {
     const root = projectPath.endsWith("/") ? projectPath : projectPath + "/";
     const relPath = filePath.startsWith(root) ? filePath.slice(root.length) : filePath;
 
-    getGitDiff(projectPath, relPath)
-      .then((diffText) => {
-        if (!diffText.trim()) {
-          setEmpty(true);
-          return;
-        }
-        const rendered = diff2htmlHtml(diffText, {
-          drawFileList: false,
-          matching: "lines",
-          outputFormat: layout === "side-by-side" ? "side-by-side" : "line-by-line",
-        });
-        setDiffHtml(rendered);
-      })
+    getStructuredDiff(projectPath, relPath, { word_diff: true })
+      .then(setFiles)
       .catch((err) => setError(String(err)));
-  }, [filePath, projectPath, layout]);
+  }, [filePath, projectPath]);
 
   if (error) {
     return <div style={styles.message}>{error}</div>;
   }
-  if (empty) {
-    return <div style={styles.message}>No uncommitted changes</div>;
-  }
-  if (diffHtml === null) {
+  if (files === null) {
     return <div style={styles.message}>Loading diff...</div>;
   }
+  if (files.length === 0) {
+    return <div style={styles.message}>No uncommitted changes</div>;
+  }
 
   return (
     <div style={styles.container}>
-      <style>{DIFF_DARK_CSS}</style>
-      <div dangerouslySetInnerHTML={{ __html: sanitizeHtml(diffHtml) }} />
+      {files.map((file, i) => (
+        <FileDiff key={i} file={file} layout={layout} />
+      ))}
+    </div>
+  );
+}
+
+function FileDiff({ file, layout }: { file: DiffFile; layout: DiffViewProps["layout"] }) {
+  const notes: string[] = [];
+  if (file.change === "renamed" || file.change === "copied") {
+    const verb = file.change === "renamed" ? "Renamed" : "Copied";
+    const similarity = file.similarity !== null ? ` (${file.similarity}% similar)` : "";
+    notes.push(`${verb} from ${file.old_path}${similarity}`);
+  }
+  if (file.old_mode && file.new_mode) {
+    notes.push(`Mode changed ${file.old_mode} → ${file.new_mode}`);
+  }
+  if (file.binary) {
+    const what = file.change === "added" || file.change === "deleted" ? file.change : "changed";
+    notes.push(`Binary file ${what}`);
+  }
+
+  return (
+    <div>
+      {notes.map((note) => (
+        <div key={note} style={styles.info}>
+          {note}
+        </div>
+      ))}
+      {file.hunks.map((hunk, i) =>
+        layout === "side-by-side" ? <SplitHunk key={i} hunk={hunk} /> : <UnifiedHunk key={i} hunk={hunk} />,
+      )}
     </div>
   );
 }
 
+function hunkTitle(hunk: DiffHunk): string {
+  const range = `@@ -${hunk.old_start},${hunk.old_lines} +${hunk.new_start},${hunk.new_lines} @@`;
+  return hunk.header ? `${range} ${hunk.header}` : range;
+}
+
+const SIGN: Record<DiffLine["kind"], string> = { context: " ", added: "+", removed: "-" };
+
+function LineContent({ line }: { line: DiffLine }) {
+  const emphasis = line.kind === "added" ? styles.addedWord : styles.removedWord;
+  return (
+    <>
+      {line.segments
+        ? line.segments.map((seg, i) => (
+            <span key={i} style={seg.changed ? emphasis : undefined}>
+              {seg.text}
+            </span>
+          ))
+        : line.content}
+      {line.no_newline && (
+        <span style={styles.noNewline} title="No newline at end of file">
+          {" ∅"}
+        </span>
+      )}
+    </>
+  );
+}
+
+function rowStyle(kind: DiffLine["kind"] | null) {
+  if (kind === "added") return styles.added;
+  if (kind === "removed") return styles.removed;
+  if (kind === null) return styles.placeholder;
+  return undefined;
+}
+
+function UnifiedHunk({ hunk }: { hunk: DiffHunk }) {
+  return (
+    <table style={styles.table}>
+      <tbody>
+        <tr>
+          <td colSpan={3} style={styles.info}>
+            {hunkTitle(hunk)}
+          </td>
+        </tr>
+        {hunk.lines.map((line, i) => (
+          <tr key={i} style={rowStyle(line.kind)}>
+            <td style={styles.lineNumber}>{line.old_line ?? ""}</td>
+            <td style={styles.lineNumber}>{line.new_line ?? ""}</td>
+            <td style={styles.code}>
+              {SIGN[line.kind]}
+              <LineContent line={line} />
+            </td>
+          </tr>
+        ))}
+      </tbody>
+    </table>
+  );
+}
+
+/** Pair each run of removals with the additions after it; context lines sit on both sides. */
+function splitRows(lines: DiffLine[]): [DiffLine | null, DiffLine | null][] {
+  const rows: [DiffLine | null, DiffLine | null][] = [];
+  let i = 0;
+  while (i < lines.length) {
+    if (lines[i].kind === "context") {
+      rows.push([lines[i], lines[i]]);
+      i++;
+      continue;
+    }
+    const removed: DiffLine[] = [];
+    const added: DiffLine[] = [];
+    while (i < lines.length && lines[i].kind === "removed") removed.push(lines[i++]);
+    while (i < lines.length && lines[i].kind === "added") added.push(lines[i++]);
+    for (let k = 0; k < Math.max(removed.length, added.length); k++) {
+      rows.push([removed[k] ?? null, added[k] ?? null]);
+    }
+  }
+  return rows;
+}
+
+function SplitHunk({ hunk }: { hunk: DiffHunk }) {
+  const side = (line: DiffLine | null, number: number | null | undefined) => [
+    <td key="n" style={{ ...styles.lineNumber, ...rowStyle(line?.kind ?? null) }}>
+      {number ?? ""}
+    </td>,
+    <td key="c" style={{ ...styles.code, ...styles.half, ...rowStyle(line?.kind ?? null) }}>
+      {line && <LineContent line={line} />}
+    </td>,
+  ];
+  return (
+    <table style={styles.table}>
+      <tbody>
+        <tr>
+          <td colSpan={4} style={styles.info}>
+            {hunkTitle(hunk)}
+          </td>
+        </tr>
+        {splitRows(hunk.lines).map(([left, right], i) => (
+          <tr key={i}>
+            {side(left, left?.old_line)}
+            {side(right, right?.new_line)}
+          </tr>
+        ))}
+      </tbody>
+    </table>
+  );
+}
+
 const styles = {
   container: {
     fontSize: "13px",
@@ -83,88 +205,49 @@ const styles = {
     color: "var(--text-secondary)",
     fontSize: "13px",
   },
+  table: {
+    borderCollapse: "collapse" as const,
+    width: "100%",
+  },
+  info: {
+    background: "var(--bg-panel)",
+    color: "var(--diff-info-color)",
+    padding: "2px 8px",
+  },
+  lineNumber: {
+    background: "var(--bg-panel)",
+    color: "var(--text-hint)",
+    borderRight: "1px solid var(--border-default)",
+    padding: "0 6px",
+    textAlign: "right" as const,
+    userSelect: "none" as const,
+    width: "1%",
+    whiteSpace: "nowrap" as const,
+  },
+  code: {
+    color: "var(--text-primary)",
+    paddingLeft: "8px",
+    whiteSpace: "pre" as const,
+  },
+  half: {
+    width: "50%",
+  },
+  added: {
+    background: "rgba(35, 134, 54, 0.2)",
+  },
+  removed: {
+    background: "rgba(248, 81, 73, 0.2)",
+  },
+  placeholder: {
+    background: "var(--bg-empty-placeholder)",
+  },
+  addedWord: {
+    background: "rgba(35, 134, 54, 0.4)",
+  },
+  removedWord: {
+    background: "rgba(248, 81, 73, 0.4)",
+  },
+  noNewline: {
+    color: "var(--text-hint)",
+  },
 };
-
-// Dark theme overrides for diff2html
-const DIFF_DARK_CSS = `
-  .d2h-wrapper {
-    background: transparent !important;
-  }
-  .d2h-file-header {
-    display: none !important;
-  }
-  .d2h-file-wrapper {
-    border: none !important;
-    margin: 0 !important;
-  }
-  .d2h-code-wrapper {
-    background: transparent !important;
-  }
-  .d2h-code-line,
-  .d2h-code-side-line {
-    background: var(--bg-primary) !important;
-    color: var(--text-primary) !important;
-    padding-left: 8px !important;
-  }
-  .d2h-code-line-ctn {
-    color: var(--text-primary) !important;
-  }
-  .d2h-ins .d2h-code-line,
-  .d2h-ins .d2h-code-side-line,
-  .d2h-ins.d2h-code-side-line {
-    background: rgba(35, 134, 54, 0.2) !important;
-  }
-  .d2h-ins .d2h-code-line-ctn {
-    background: rgba(35, 134, 54, 0.2) !important;
-  }
-  .d2h-del .d2h-code-line,
-  .d2h-del .d2h-code-side-line,
-  .d2h-del.d2h-code-side-line {
-    background: rgba(248, 81, 73, 0.2) !important;
-  }
-  .d2h-del .d2h-code-line-ctn {
-    background: rgba(248, 81, 73, 0.2) !important;
-  }
-  .d2h-code-linenumber {
-    background: var(--bg-panel) !important;
-    color: var(--text-hint) !important;
-    border-right: 1px solid var(--border-default) !important;
-  }
-  .d2h-code-side-linenumber {
-    background: var(--bg-panel) !important;
-    color: var(--text-hint) !important;
-    border-right: 1px solid var(--border-default) !important;
-  }
-  .d2h-diff-table {
-    border-collapse: collapse;
-    width: 100%;
-  }
-  .d2h-diff-tbody > tr {
-    border: none !important;
-  }
-  .d2h-info {
-    background: var(--bg-panel) !important;
-    color: var(--diff-info-color) !important;
-    border: none !important;
-  }
-  .d2h-emptyplaceholder {
-    background: var(--bg-empty-placeholder) !important;
-    border: none !important;
-  }
-  .d2h-tag {
-    display: none !important;
-  }
-  /* Word-level highlights */
-  .d2h-ins ins,
-  .d2h-ins .d2h-change {
-    background: rgba(35, 134, 54, 0.4) !important;
-    color: var(--text-primary) !important;
-    text-decoration: none !important;
-  }
-  .d2h-del del,
-  .d2h-del .d2h-change {
-    background: rgba(248, 81, 73, 0.4) !important;
-    color: var(--text-primary) !important;
-    text-decoration: none !important;
-  }
-`;
@@ -1,87 +1,19 @@
-import { useState, useEffect, useCallback, useRef } from "react";
-import { listen } from "@tauri-apps/api/event";
-import { getGitDiffStat } from "../lib/tauri";
-import { useAppStore } from "../store/appStore";
+import { useMemo } from "react";
+import { useGitState } from "./useGitState";
 
 export type GitDiffStat = { added: number; removed: number } | null;
 
+/**
+ * Uncommitted +/- totals for a project, untracked files counted as additions.
+ * A transient git failure (e.g. index.lock contention) keeps the last-known
+ * snapshot on the backend, so the number doesn't blank.
+ */
 export function useGitDiffStat(projectPath: string | null): GitDiffStat {
-  const [stat, setStat] = useState<GitDiffStat>(null);
-  const prevPath = useRef<string | null>(null);
-  const refreshTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
-  const inFlight = useRef(false);
-
-  const fetchStat = useCallback(async () => {
-    if (!projectPath) {
-      setStat((prev) => (prev === null ? prev : null));
-      return;
-    }
-    // On slow repos one git call can outlive the poll interval; never stack them.
-    if (inFlight.current) return;
-    inFlight.current = true;
-    try {
-      const startedAt = performance.now();
-      const [added, removed] = await getGitDiffStat(projectPath);
-      useAppStore.getState().reportGitTiming(projectPath, performance.now() - startedAt);
-      setStat((prev) => (
-        prev && prev.added === added && prev.removed === removed
-          ? prev
-          : { added, removed }
-      ));
-    } catch (err) {
-      // Keep the last-known value on a transient git failure (e.g. index.lock
-      // contention while another git process runs) rather than blanking the
-      // number — the stat reappears on the next clean read.
-      console.error("Failed to fetch git diff stat:", err);
-    } finally {
-      inFlight.current = false;
-    }
-  }, [projectPath]);
-
-  const scheduleFetchStat = useCallback((delayMs = 350) => {
-    if (refreshTimer.current) clearTimeout(refreshTimer.current);
-    refreshTimer.current = setTimeout(() => {
-      refreshTimer.current = null;
-      fetchStat();
-    }, delayMs);
-  }, [fetchStat]);
-
-  useEffect(() => {
-    if (projectPath !== prevPath.current) {
-      prevPath.current = projectPath;
-      fetchStat();
-    }
-  }, [projectPath, fetchStat]);
-
-  useEffect(() => {
-    if (!projectPath) return;
-    const unlisten = listen<string[]>("fs-change", () => {
-      scheduleFetchStat();
-    });
-    return () => {
-      if (refreshTimer.current) {
-        clearTimeout(refreshTimer.current);
-        refreshTimer.current = null;
-      }
-      unlisten.then((fn) => fn());
-    };
-  }, [projectPath, scheduleFetchStat]);
-
-  // Poll every 10s, but only if the project had activity in the past 60s.
-  // Read threads via getState() inside the interval to avoid subscribing to the
-  // full threads array, which would re-render this hook on every thread mutation.
-  useEffect(() => {
-    if (!projectPath) return;
-    const id = setInterval(() => {
-      if (document.hidden) return;
-      const state = useAppStore.getState();
-      const now = Date.now();
-      const projectThreads = state.threads.filter((t) => t.projectId === state.activeProjectId);
-      const recentActivity = projectThreads.some((t) => now - t.lastActivityAt < 60_000);
-      if (recentActivity) fetchStat();
-    }, 10_000);
-    return () => clearInterval(id);
-  }, [projectPath, fetchStat]);
-
-  return stat;
+  const snapshot = useGitState(projectPath);
+  const added = snapshot?.added;
+  const removed = snapshot?.removed;
+  return useMemo(
+    () => (added === undefined || removed === undefined ? null : { added, removed }),
+    [added, removed],
+  );
 }
@@ -0,0 +1,48 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { GIT_STATE_EVENT, getGitState, type GitSnapshot } from "../lib/tauri";
+import { useAppStore } from "../store/appStore";
+
+/** Re-read rather than take a cached snapshot older than this on mount. */
+const MAX_AGE_MS = 10_000;
+
+/**
+ * The backend's cached git state for a repo: fetched once, then replaced by each
+ * git-state-changed push for it. The backend refreshes on file, index, HEAD and
+ * ref changes and coalesces requests, so hooks sharing a path share one refresh.
+ * The cache is only trusted for MAX_AGE_MS, so switching back to a project the
+ * watcher stopped covering doesn't show what it looked like when it left.
+ */
+export function useGitState(path: string | null, enabled = true): GitSnapshot | null {
+  const [snapshot, setSnapshot] = useState<GitSnapshot | null>(null);
+  // Events carry the canonical path, which can differ from `path` (symlinks).
+  const resolvedPath = useRef<string | null>(null);
+
+  useEffect(() => {
+    setSnapshot(null);
+    resolvedPath.current = null;
+    if (!path || !enabled) return;
+
+    let cancelled = false;
+    getGitState(path, MAX_AGE_MS)
+      .then((s) => {
+        if (cancelled) return;
+        resolvedPath.current = s.path;
+        useAppStore.getState().reportGitTiming(path, s.duration_ms);
+        setSnapshot(s);
+      })
+      .catch((err) => console.error("Failed to fetch git state:", err));
+
+    const unlisten = listen<GitSnapshot>(GIT_STATE_EVENT, ({ payload }) => {
+      if (payload.path !== resolvedPath.current && payload.path !== path) return;
+      useAppStore.getState().reportGitTiming(path, payload.duration_ms);
+      setSnapshot(payload);
+    });
+    return () => {
+      cancelled = true;
+      unlisten.then((fn) => fn());
+    };
+  }, [path, enabled]);
+
+  return snapshot;
+}
@@ -1,7 +1,6 @@
-import { useState, useEffect, useCallback, useRef } from "react";
-import { listen } from "@tauri-apps/api/event";
-import { getGitStatus, type GitFileStatus } from "../lib/tauri";
-import { useAppStore } from "../store/appStore";
+import { useMemo, useRef } from "react";
+import type { GitFileStatus, GitStatusEntry } from "../lib/tauri";
+import { useGitState } from "./useGitState";
 
 const STATUS_PRIORITY: Record<GitFileStatus, number> = {
   Conflicted: 6,
@@ -23,81 +22,37 @@ function mapsEqual(a: GitStatusMap, b: GitStatusMap): boolean {
   return true;
 }
 
-export function useGitStatus(projectPath: string | null): GitStatusMap {
-  const [statusMap, setStatusMap] = useState<GitStatusMap>(new Map());
-  const prevPath = useRef<string | null>(null);
-  const refreshTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
-  const inFlight = useRef(false);
-
-  const fetchStatus = useCallback(async () => {
-    if (!projectPath) {
-      setStatusMap((prev) => (prev.size === 0 ? prev : new Map()));
-      return;
-    }
-
-    // On slow repos one git call can outlive the refresh debounce; never stack them.
-    if (inFlight.current) return;
-    inFlight.current = true;
-    try {
-      const startedAt = performance.now();
-      const entries = await getGitStatus(projectPath);
-      useAppStore.getState().reportGitTiming(projectPath, performance.now() - startedAt);
-      const map: GitStatusMap = new Map();
-      const root = projectPath.endsWith("/") ? projectPath : projectPath + "/";
-
-      for (const entry of entries) {
-        const absPath = root + entry.path;
-        map.set(absPath, entry.status);
-
-        // Folder rollup: propagate status to every ancestor
-        const parts = entry.path.split("/");
-        for (let i = 1; i < parts.length; i++) {
-          const dirAbsolute = root + parts.slice(0, i).join("/");
-          const existing = map.get(dirAbsolute);
-          if (!existing || STATUS_PRIORITY[entry.status] > STATUS_PRIORITY[existing]) {
-            map.set(dirAbsolute, entry.status);
-          }
-        }
+function buildStatusMap(projectPath: string, entries: GitStatusEntry[]): GitStatusMap {
+  const map: GitStatusMap = new Map();
+  const root = projectPath.endsWith("/") ? projectPath : projectPath + "/";
+
+  for (const entry of entries) {
+    const absPath = root + entry.path;
+    map.set(absPath, entry.status);
+
+    // Folder rollup: propagate status to every ancestor
+    const parts = entry.path.split("/");
+    for (let i = 1; i < parts.length; i++) {
+      const dirAbsolute = root + parts.slice(0, i).join("/");
+      const existing = map.get(dirAbsolute);
+      if (!existing || STATUS_PRIORITY[entry.status] > STATUS_PRIORITY[existing]) {
+        map.set(dirAbsolute, entry.status);
       }
-
-      setStatusMap((prev) => (mapsEqual(prev, map) ? prev : map));
-    } catch (err) {
-      console.error("Failed to fetch git status:", err);
-      setStatusMap((prev) => (prev.size === 0 ? prev : new Map()));
-    } finally {
-      inFlight.current = false;
     }
-  }, [projectPath]);
-
-  const scheduleFetchStatus = useCallback((delayMs = 350) => {
-    if (refreshTimer.current) clearTimeout(refreshTimer.current);
-    refreshTimer.current = setTimeout(() => {
-      refreshTimer.current = null;
-      fetchStatus();
-    }, delayMs);
-  }, [fetchStatus]);
-
-  useEffect(() => {
-    if (projectPath !== prevPath.current) {
-      prevPath.current = projectPath;
-      fetchStatus();
-    }
-  }, [projectPath, fetchStatus]);
+  }
+  return map;
+}
 
-  // Re-fetch on file system changes
-  useEffect(() => {
-    if (!projectPath) return;
-    const unlisten = listen<string[]>("fs-change", () => {
-      scheduleFetchStatus();
-    });
-    return () => {
-      if (refreshTimer.current) {
-        clearTimeout(refreshTimer.current);
-        refreshTimer.current = null;
-      }
-      unlisten.then((fn) => fn());
-    };
-  }, [projectPath, scheduleFetchStatus]);
+const EMPTY: GitStatusMap = new Map();
 
-  return statusMap;
+export function useGitStatus(projectPath: string | null): GitStatusMap {
+  const snapshot = useGitState(projectPath);
+  const prevMap = useRef<GitStatusMap>(EMPTY);
+
+  return useMemo(() => {
+    const map = projectPath && snapshot ? buildStatusMap(projectPath, snapshot.status.entries) : EMPTY;
+    // Keep the previous identity when nothing changed, so consumers don't re-render.
+    if (!mapsEqual(prevMap.current, map)) prevMap.current = map;
+    return prevMap.current;
+  }, [projectPath, snapshot]);
 }
@@ -1,17 +1,21 @@
 import { useEffect, useCallback, useRef } from "react";
 import { listen } from "@tauri-apps/api/event";
-import { getGitDiffStat } from "../lib/tauri";
+import { GIT_STATE_EVENT, getGitState, type GitSnapshot } from "../lib/tauri";
 import { useAppStore } from "../store/appStore";
 
+/** Poll envs the file watcher can't see at most this often. */
+const POLL_MAX_AGE_MS = 10_000;
+
 /**
  * Keeps each environment's uncommitted +/- totals fresh in store.envDiffStats,
  * keyed by env path: the active project root (main) plus every non-main worktree.
  * Writing the stats also prunes touch records for any env now clean (0/0), so a
  * surviving touch == uncommitted work. Mounted in the always-rendered TitleBar.
  *
- * Mirrors useGitDiffStat: refresh on the env set changing, on fs-change, and on a
- * 10s activity-gated poll; never stacks concurrent batches. getGitDiffStat returns
- * a [added, removed] tuple, destructured here into the {added, removed} store shape.
+ * Reads the backend's cached git state for each env on the env set changing, then
+ * applies git-state-changed pushes as they arrive. Envs outside the watched tree get
+ * no pushes, so a 10s activity-gated poll re-reads anything older than that; never
+ * stacks concurrent batches.
  */
 export function useWorktreeDiffStats(): void {
   const worktrees = useAppStore((s) => s.worktrees);
@@ -19,10 +23,11 @@ export function useWorktreeDiffStats(): void {
     (s) => s.projects.find((p) => p.id === s.activeProjectId)?.path ?? null,
   );
   const setEnvDiffStats = useAppStore((s) => s.setEnvDiffStats);
-  const refreshTimer = useRef<ReturnType<typeof setTimeout> | null>(null);
   const inFlight = useRef(false);
+  // Canonical snapshot path -> env path, to match pushes to envs.
+  const envByResolvedPath = useRef(new Map<string, string>());
 
-  const fetchAll = useCallback(async () => {
+  const fetchAll = useCallback(async (maxAgeMs?: number) => {
     // Env path set: the project root (main, keyed by the store path so it matches
     // attributeEnv + the ThreadItem dot), then each non-main worktree by its path.
     const envPaths: string[] = [];
@@ -44,8 +49,9 @@ export function useWorktreeDiffStats(): void {
       const entries = await Promise.all(
         envPaths.map(async (p) => {
           try {
-            const [added, removed] = await getGitDiffStat(p);
-            return [p, { added, removed }] as const;
+            const snapshot = await getGitState(p, maxAgeMs);
+            envByResolvedPath.current.set(snapshot.path, p);
+            return [p, { added: snapshot.added, removed: snapshot.removed }] as const;
           } catch {
             return [p, prev[p] ?? { added: 0, removed: 0 }] as const;
           }
@@ -57,32 +63,23 @@ export function useWorktreeDiffStats(): void {
     }
   }, [worktrees, projectPath, setEnvDiffStats]);
 
-  const scheduleFetch = useCallback((delayMs = 350) => {
-    if (refreshTimer.current) clearTimeout(refreshTimer.current);
-    refreshTimer.current = setTimeout(() => {
-      refreshTimer.current = null;
-      fetchAll();
-    }, delayMs);
-  }, [fetchAll]);
-
   // Refresh whenever the env set changes (project switch, worktree add/remove).
   useEffect(() => {
     fetchAll();
   }, [fetchAll]);
 
   useEffect(() => {
-    if (!projectPath) return;
-    const unlisten = listen<string[]>("fs-change", () => {
-      scheduleFetch();
+    const unlisten = listen<GitSnapshot>(GIT_STATE_EVENT, ({ payload }) => {
+      const env = envByResolvedPath.current.get(payload.path);
+      if (!env) return;
+      const { envDiffStats } = useAppStore.getState();
+      if (!(env in envDiffStats)) return;
+      setEnvDiffStats({ ...envDiffStats, [env]: { added: payload.added, removed: payload.removed } });
     });
     return () => {
-      if (refreshTimer.current) {
-        clearTimeout(refreshTimer.current);
-        refreshTimer.current = null;
-      }
       unlisten.then((fn) => fn());
     };
-  }, [projectPath, scheduleFetch]);
+  }, [setEnvDiffStats]);
 
   // Poll every 10s, but only when the project had activity in the past 60s.
   useEffect(() => {
@@ -93,7 +90,7 @@ export function useWorktreeDiffStats(): void {
       const now = Date.now();
       const projectThreads = state.threads.filter((t) => t.projectId === state.activeProjectId);
       const recentActivity = projectThreads.some((t) => now - t.lastActivityAt < 60_000);
-      if (recentActivity) fetchAll();
+      if (recentActivity) fetchAll(POLL_MAX_AGE_MS);
     }, 10_000);
     return () => clearInterval(id);
   }, [projectPath, fetchAll]);
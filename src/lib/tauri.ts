@@ -109,11 +109,41 @@ export function killPty(sessionId: string): Promise<void> {
 export interface FileEntry {
   name: string;
   path: string;
+  /** For a symlink, its target's type when followed (by default, only in-root links are). */
   is_dir: boolean;
+  /** A zip/tar file that can be expanded with readArchiveDirectory. */
+  is_archive: boolean;
+  is_symlink: boolean;
+  /** Raw link contents, possibly relative. */
+  link_target: string | null;
+  /** Symlink whose target doesn't exist. */
+  broken: boolean;
+  points_outside_root: boolean;
+  size: number;
+  mtime_ms: number;
+  executable: boolean;
+}
+
+/**
+ * List one level. Symlinks to directories inside the project are expandable
+ * directories and ones leading outside are leaves, unless `followLinks`
+ * forces either way.
+ */
+export function readDirectory(path: string, projectRoot: string, followLinks?: boolean): Promise<FileEntry[]> {
+  return invoke("read_directory", { path, projectRoot, followLinks });
 }
 
-export function readDirectory(path: string, projectRoot: string): Promise<FileEntry[]> {
-  return invoke("read_directory", { path, projectRoot });
+/**
+ * List a directory inside an archive. Paths are virtual: the archive path
+ * followed by the member path, e.g. `/proj/release.zip/bin`.
+ */
+export function readArchiveDirectory(path: string, projectRoot: string): Promise<FileEntry[]> {
+  return invoke("read_archive_directory", { path, projectRoot });
+}
+
+/** Read a text member of an archive by virtual path (same limits as readFile). */
+export function readArchiveFile(path: string, projectRoot: string): Promise<string> {
+  return invoke("read_archive_file", { path, projectRoot });
 }
 
 /** Recursively scan all files in a directory (respects .gitignore). Returns absolute paths. */
@@ -139,10 +169,77 @@ export function writeFile(path: string, projectRoot: string, content: string): P
   return invoke("write_file", { path, projectRoot, content });
 }
 
+export type FileKind =
+  | { kind: "text"; encoding: string; bom: boolean; line_ending: "lf" | "crlf" }
+  | { kind: "image"; mime: string }
+  | { kind: "binary"; mime: string };
+
+/** Classify a file as text (with encoding), image or binary from its leading bytes. */
+export function sniffFile(path: string, projectRoot: string): Promise<FileKind> {
+  return invoke("sniff_file", { path, projectRoot });
+}
+
 export function readFileBase64(path: string, projectRoot: string): Promise<string> {
   return invoke("read_file_base64", { path, projectRoot });
 }
 
+export interface HexRow {
+  offset: number;
+  /** Space-separated lowercase hex bytes. */
+  hex: string;
+  /** Printable ASCII, "." for everything else. */
+  ascii: string;
+}
+
+export type ArchiveKind = "zip" | "tar" | "tar_gz" | "tar_zst";
+
+export interface ArchiveEntry {
+  path: string;
+  size: number;
+  is_dir: boolean;
+}
+
+export type FormatHeader =
+  | { format: "elf"; class: string; endian: string; os_abi: string; file_type: string; machine: string; entry: number }
+  | {
+      format: "mach_o";
+      cpu: string;
+      file_type: string;
+      load_commands: number;
+      slices: { cpu: string; offset: number; size: number }[];
+    }
+  | { format: "pe"; machine: string; pe32_plus: boolean; sections: number; timestamp: number; subsystem: string; dll: boolean }
+  | { format: "archive"; kind: ArchiveKind; entries: ArchiveEntry[]; truncated: boolean }
+  | {
+      format: "sqlite";
+      page_size: number;
+      page_count: number;
+      text_encoding: string;
+      user_version: number;
+      schema: { kind: string; name: string; table: string; sql: string | null }[];
+    }
+  | { format: "image"; mime: string; width: number; height: number; exif: [string, string][] };
+
+export interface HexPage {
+  file_size: number;
+  offset: number;
+  rows: HexRow[];
+  mime: string | null;
+  /** Parsed for the first page (offset 0) only. */
+  header: FormatHeader | null;
+  header_error: string | null;
+}
+
+/** Paginated hexdump (default 4 KiB, max 64 KiB per page) with a header summary on page one. */
+export function readFileHex(
+  path: string,
+  projectRoot: string,
+  offset?: number,
+  length?: number,
+): Promise<HexPage> {
+  return invoke("read_file_hex", { path, projectRoot, offset, length });
+}
+
 export function previewFile(path: string, projectRoot: string): Promise<void> {
   return invoke("preview_file", { path, projectRoot });
 }
@@ -159,6 +256,54 @@ export function openInDefaultApp(path: string, projectRoot: string): Promise<voi
   return invoke("open_in_default_app", { path, projectRoot });
 }
 
+export interface DiskUsageNode {
+  name: string;
+  /** Relative to the scanned project ("" for the root). */
+  path: string;
+  size: number;
+  file_count: number;
+  /** Largest first; empty below the requested depth. */
+  children: DiskUsageNode[];
+  /** Children dropped by the per-directory cap, summed. */
+  other_size: number;
+  other_count: number;
+}
+
+export interface WorktreeUsage {
+  path: string;
+  branch: string | null;
+  source: string;
+  size: number;
+  file_count: number;
+}
+
+export interface DiskUsageReport {
+  root: DiskUsageNode;
+  worktrees: WorktreeUsage[];
+  include_ignored: boolean;
+  duration_ms: number;
+}
+
+export interface DiskUsageEvent {
+  event: "Progress";
+  data: { files_scanned: number; bytes_scanned: number; current: string };
+}
+
+/** Size up a project (allocated bytes, largest first). Cancel with cancelDiskUsage(scanId). */
+export function analyzeDiskUsage(
+  path: string,
+  scanId: string,
+  includeIgnored: boolean,
+  channel: Channel<DiskUsageEvent>,
+  maxDepth?: number,
+): Promise<DiskUsageReport> {
+  return invoke("analyze_disk_usage", { path, scanId, includeIgnored, maxDepth, channel });
+}
+
+export function cancelDiskUsage(scanId: string): Promise<void> {
+  return invoke("cancel_disk_usage", { scanId });
+}
+
 export function startWatching(path: string, projectRoot: string): Promise<void> {
   return invoke("start_watching", { path, projectRoot });
 }
@@ -177,16 +322,58 @@ export type GitFileStatus =
   | "Ignored"
   | "Conflicted";
 
+/** One side (index or worktree) of a status entry. */
+export type FileState =
+  | "Unmodified"
+  | "Modified"
+  | "TypeChanged"
+  | "Added"
+  | "Deleted"
+  | "Renamed"
+  | "Copied"
+  | "Unmerged"
+  | "Untracked"
+  | "Ignored";
+
+export interface SubmoduleState {
+  commit_changed: boolean;
+  modified: boolean;
+  untracked: boolean;
+}
+
 export interface GitStatusEntry {
   path: string;
+  /** Both sides collapsed, for tree colouring. */
   status: GitFileStatus;
+  /** HEAD → index */
+  index: FileState;
+  /** index → worktree */
+  worktree: FileState;
+  /** Source path of a rename or copy. */
+  orig_path: string | null;
+  submodule: SubmoduleState | null;
+}
+
+export interface BranchStatus {
+  /** null before the first commit */
+  oid: string | null;
+  /** null when detached */
+  head: string | null;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+}
+
+export interface GitStatus {
+  branch: BranchStatus;
+  entries: GitStatusEntry[];
 }
 
 export function getGitBranch(path: string): Promise<string> {
   return invoke("get_git_branch", { path });
 }
 
-export function getGitStatus(path: string): Promise<GitStatusEntry[]> {
+export function getGitStatus(path: string): Promise<GitStatus> {
   return invoke("get_git_status", { path });
 }
 
@@ -198,12 +385,55 @@ export interface WorktreeInfo {
   head: string;
   /** "main" | "claude" | "codex" | "manual" — classified by path. */
   source: string;
+  locked: boolean;
+  lock_reason: string | null;
+  /** The directory is gone; pruneWorktrees would remove the entry. */
+  prunable: boolean;
+  /** Uncommitted changes or untracked files. */
+  dirty: boolean;
 }
 
 export function getGitWorktrees(path: string): Promise<WorktreeInfo[]> {
   return invoke("get_git_worktrees", { path });
 }
 
+export type WorktreeLocation =
+  | { scheme: "claude" } // <repo>/.claude/worktrees/<name>
+  | { scheme: "codex" } // ~/.codex/worktrees/<repo-name>/<name>
+  | { scheme: "sibling" } // <repo-parent>/<repo-name>.worktrees/<name>
+  | { scheme: "path"; path: string };
+
+export interface CreateWorktreeOptions {
+  name: string;
+  /** New branch to create; without it start_point is checked out as-is. */
+  branch?: string;
+  start_point?: string;
+  /** Defaults to sibling. */
+  location?: WorktreeLocation;
+}
+
+export function createWorktree(repoPath: string, options: CreateWorktreeOptions): Promise<WorktreeInfo> {
+  return invoke("create_worktree", { repoPath, options });
+}
+
+/** Refuses dirty worktrees unless force, and locked ones always. */
+export function removeWorktree(repoPath: string, worktreePath: string, force?: boolean): Promise<void> {
+  return invoke("remove_worktree", { repoPath, worktreePath, force });
+}
+
+/** Returns the pruned (or, with dryRun, prunable) worktree paths. */
+export function pruneWorktrees(repoPath: string, dryRun?: boolean): Promise<string[]> {
+  return invoke("prune_worktrees", { repoPath, dryRun });
+}
+
+export function lockWorktree(repoPath: string, worktreePath: string, reason?: string): Promise<void> {
+  return invoke("lock_worktree", { repoPath, worktreePath, reason });
+}
+
+export function unlockWorktree(repoPath: string, worktreePath: string): Promise<void> {
+  return invoke("unlock_worktree", { repoPath, worktreePath });
+}
+
 export interface FileDiffStat {
   path: string;
   added: number;
@@ -218,6 +448,34 @@ export function getGitDiffStat(path: string): Promise<[number, number]> {
   return invoke("get_git_diff_stat", { path });
 }
 
+/** Emitted with a GitSnapshot whenever a cached repo's state changes. */
+export const GIT_STATE_EVENT = "git-state-changed";
+
+export interface GitSnapshot {
+  /** Canonical repo path; match events against the path from getGitState. */
+  path: string;
+  status: GitStatus;
+  added: number;
+  removed: number;
+  /** Untracked files counted as all added; largest first. */
+  file_stats: FileDiffStat[];
+  duration_ms: number;
+  refreshed_at: number;
+}
+
+/** Cached status and diff stats, refreshed by the backend as files and refs change. */
+export function getGitState(path: string, maxAgeMs?: number): Promise<GitSnapshot> {
+  return invoke("get_git_state", { path, maxAgeMs });
+}
+
+export function refreshGitState(path: string): Promise<GitSnapshot> {
+  return invoke("refresh_git_state", { path });
+}
+
+export function forgetGitState(path: string): Promise<void> {
+  return invoke("forget_git_state", { path });
+}
+
 export interface SuspiciousTrackedDir {
   dir: string;
   count: number;
@@ -234,6 +492,74 @@ export function diagnoseRepoHealth(path: string): Promise<RepoHealth> {
   return invoke("diagnose_repo_health", { path });
 }
 
+// Repo health fixes. Each takes `dryRun`, which reports without changing anything.
+export interface UntrackDirReport {
+  dry_run: boolean;
+  dir: string;
+  untracked_files: number;
+  /** Null when .gitignore already covers the dir. */
+  gitignore_entry: string | null;
+}
+
+export interface ConfigChange {
+  key: string;
+  from: string | null;
+  to: string;
+}
+
+export interface StatusCacheReport {
+  dry_run: boolean;
+  /** False on git builds without the fsmonitor daemon (Linux). */
+  fsmonitor_supported: boolean;
+  changes: ConfigChange[];
+  before_ms: number;
+  after_ms: number | null;
+}
+
+export interface ObjectStats {
+  loose_objects: number;
+  loose_size_kb: number;
+  packs: number;
+  pack_size_kb: number;
+}
+
+export interface MaintenanceReport {
+  dry_run: boolean;
+  command: string;
+  before: ObjectStats;
+  after: ObjectStats | null;
+}
+
+export interface IndexLock {
+  path: string;
+  age_secs: number;
+  /** Pids keeping the lock; it isn't removed while any are listed. */
+  held_by: number[];
+  removed: boolean;
+}
+
+export interface IndexLockReport {
+  dry_run: boolean;
+  locks: IndexLock[];
+}
+
+/** Untrack `dir` (files stay on disk) and add it to .gitignore. */
+export function untrackDirectory(repoPath: string, dir: string, dryRun: boolean): Promise<UntrackDirReport> {
+  return invoke("untrack_directory", { repoPath, dir, dryRun });
+}
+
+export function enableStatusCache(repoPath: string, dryRun: boolean): Promise<StatusCacheReport> {
+  return invoke("enable_status_cache", { repoPath, dryRun });
+}
+
+export function runGitMaintenance(repoPath: string, dryRun: boolean): Promise<MaintenanceReport> {
+  return invoke("run_git_maintenance", { repoPath, dryRun });
+}
+
+export function removeStaleIndexLocks(repoPath: string, dryRun: boolean): Promise<IndexLockReport> {
+  return invoke("remove_stale_index_locks", { repoPath, dryRun });
+}
+
 export function getFileDiffStat(repoPath: string, filePath: string): Promise<[number, number]> {
   return invoke("get_file_diff_stat", { repoPath, filePath });
 }
@@ -265,10 +591,713 @@ export function getCommitInfo(repoPath: string, commitRef: string): Promise<Comm
   return invoke("get_commit_info", { repoPath, commitRef });
 }
 
+/** A commit's patch, or the diff between the two sides of `a..b` / `a...b`. */
 export function getCommitDiff(repoPath: string, commitRef: string): Promise<string> {
   return invoke("get_commit_diff", { repoPath, commitRef });
 }
 
+// Structured diffs
+export type WhitespaceMode = "show" | "ignore_at_eol" | "ignore_change" | "ignore_all";
+
+export interface DiffOptions {
+  /** Git's default (3) when omitted. */
+  context_lines?: number;
+  whitespace?: WhitespaceMode;
+  /** Split paired changed lines into changed/unchanged segments. */
+  word_diff?: boolean;
+  detect_renames?: boolean;
+}
+
+export type DiffChange = "added" | "deleted" | "modified" | "renamed" | "copied";
+
+export interface LineSegment {
+  text: string;
+  changed: boolean;
+}
+
+export interface DiffLine {
+  kind: "context" | "added" | "removed";
+  old_line: number | null;
+  new_line: number | null;
+  content: string;
+  no_newline: boolean;
+  segments: LineSegment[] | null;
+}
+
+export interface DiffHunk {
+  old_start: number;
+  old_lines: number;
+  new_start: number;
+  new_lines: number;
+  header: string;
+  lines: DiffLine[];
+}
+
+export interface DiffFile {
+  old_path: string | null;
+  new_path: string | null;
+  change: DiffChange;
+  similarity: number | null;
+  old_mode: string | null;
+  new_mode: string | null;
+  binary: boolean;
+  added: number;
+  removed: number;
+  hunks: DiffHunk[];
+}
+
+/** HEAD → working tree, untracked files included as added. */
+export function getStructuredDiff(repoPath: string, filePath: string | null, options: DiffOptions = {}): Promise<DiffFile[]> {
+  return invoke("get_structured_diff", { repoPath, filePath, options });
+}
+
+/** A commit against its first parent, or the two sides of `a..b` / `a...b`. */
+export function getStructuredCommitDiff(repoPath: string, commitRef: string, options: DiffOptions = {}): Promise<DiffFile[]> {
+  return invoke("get_structured_commit_diff", { repoPath, commitRef, options });
+}
+
+// Comparisons
+export type DiffEndpoint =
+  | { kind: "ref"; rev: string }
+  | { kind: "merge_base"; a: string; b: string }
+  /** What's staged in the worktree at `repoPath`. */
+  | { kind: "index" }
+  /** Files on disk in a worktree of the repo, untracked included. */
+  | { kind: "working_tree"; path: string };
+
+export interface ChangedFile {
+  path: string;
+  old_path: string | null;
+  change: DiffChange;
+  similarity: number | null;
+}
+
+export function getDiffBetween(
+  repoPath: string,
+  base: DiffEndpoint,
+  target: DiffEndpoint,
+  filePath: string | null = null,
+  options: DiffOptions = {},
+): Promise<DiffFile[]> {
+  return invoke("get_diff_between", { repoPath, base, target, filePath, options });
+}
+
+export function getNumstatBetween(repoPath: string, base: DiffEndpoint, target: DiffEndpoint): Promise<FileDiffStat[]> {
+  return invoke("get_numstat_between", { repoPath, base, target });
+}
+
+export function getChangedFilesBetween(
+  repoPath: string,
+  base: DiffEndpoint,
+  target: DiffEndpoint,
+  detectRenames = true,
+): Promise<ChangedFile[]> {
+  return invoke("get_changed_files_between", { repoPath, base, target, detectRenames });
+}
+
+// Blame
+export interface BlameRange {
+  /** 1-based line in the blamed version. */
+  start_line: number;
+  line_count: number;
+  orig_start_line: number;
+  orig_path: string;
+  commit: string;
+  /** Not committed yet; the author fields are git's placeholder. */
+  uncommitted: boolean;
+  author: string;
+  author_email: string;
+  date: string;
+  summary: string;
+}
+
+/** Batches of ranges as git finds them, in no particular line order. */
+export type BlameEvent = { event: "Ranges"; data: BlameRange[] };
+
+/** Blame the working-tree file, or its version at `rev`. Resolves with every range, ordered by line. */
+export function getGitBlame(
+  repoPath: string,
+  filePath: string,
+  rev: string | null,
+  channel: Channel<BlameEvent>,
+): Promise<BlameRange[]> {
+  return invoke("get_git_blame", { repoPath, filePath, rev, channel });
+}
+
+/** Commits that changed lines `startLine..=endLine`, newest first; stats cover only those lines. */
+export function getLineHistory(
+  repoPath: string,
+  filePath: string,
+  startLine: number,
+  endLine: number,
+  rev: string | null = null,
+  limit?: number,
+): Promise<CommitInfo[]> {
+  return invoke("get_line_history", { repoPath, filePath, startLine, endLine, rev, limit });
+}
+
+// Stashes
+export interface StashEntry {
+  /** Position in the stash list, newest first; `name` is `stash@{index}`. */
+  index: number;
+  name: string;
+  commit: string;
+  date: string;
+  /** null when the stash was made on a detached HEAD. */
+  branch: string | null;
+  message: string;
+  /** Untracked files included. */
+  file_stats: FileDiffStat[];
+}
+
+export interface StashApplyOutcome {
+  /** Paths left with conflict markers; empty when it applied cleanly. */
+  conflicted: string[];
+  /** Popped stashes are kept when they conflict. */
+  dropped: boolean;
+}
+
+export function listStashes(repoPath: string): Promise<StashEntry[]> {
+  return invoke("list_stashes", { repoPath });
+}
+
+/** Rejects with "No local changes to stash" when there's nothing to save. */
+export function createStash(
+  repoPath: string,
+  message: string | null = null,
+  includeUntracked = false,
+  keepIndex = false,
+): Promise<StashEntry> {
+  return invoke("create_stash", { repoPath, message, includeUntracked, keepIndex });
+}
+
+export function applyStash(repoPath: string, index: number, restoreIndex = false): Promise<StashApplyOutcome> {
+  return invoke("apply_stash", { repoPath, index, restoreIndex });
+}
+
+export function popStash(repoPath: string, index: number, restoreIndex = false): Promise<StashApplyOutcome> {
+  return invoke("pop_stash", { repoPath, index, restoreIndex });
+}
+
+export function dropStash(repoPath: string, index: number): Promise<void> {
+  return invoke("drop_stash", { repoPath, index });
+}
+
+/** Untracked files in the stash show as added. */
+export function showStash(repoPath: string, index: number, options: DiffOptions = {}): Promise<DiffFile[]> {
+  return invoke("show_stash", { repoPath, index, options });
+}
+
+// Remotes
+export type RemoteEvent =
+  | { event: "Progress"; data: { phase: string; percent: number; current: number; total: number } }
+  /** Any other line git prints: ref updates, hints, remote messages. */
+  | { event: "Message"; data: string };
+
+export interface FetchOptions {
+  remote?: string;
+  all?: boolean;
+  prune?: boolean;
+  /** Five minutes when omitted. */
+  timeout_secs?: number;
+}
+
+export interface PullOptions {
+  remote?: string;
+  /** Needs `remote`. */
+  branch?: string;
+  mode?: "fast_forward_only" | "rebase";
+  timeout_secs?: number;
+}
+
+export interface PushOptions {
+  remote?: string;
+  branch?: string;
+  set_upstream?: boolean;
+  force_with_lease?: boolean;
+  timeout_secs?: number;
+}
+
+/** Cancel with cancelRemoteOperation(opId). Never prompts for credentials; rejects instead. */
+export function gitFetch(repoPath: string, opId: string, options: FetchOptions, channel: Channel<RemoteEvent>): Promise<void> {
+  return invoke("git_fetch", { repoPath, opId, options, channel });
+}
+
+/** Fast-forward only unless `mode` is "rebase". */
+export function gitPull(repoPath: string, opId: string, options: PullOptions, channel: Channel<RemoteEvent>): Promise<void> {
+  return invoke("git_pull", { repoPath, opId, options, channel });
+}
+
+export function gitPush(repoPath: string, opId: string, options: PushOptions, channel: Channel<RemoteEvent>): Promise<void> {
+  return invoke("git_push", { repoPath, opId, options, channel });
+}
+
+export function cancelRemoteOperation(opId: string): Promise<void> {
+  return invoke("cancel_remote_operation", { opId });
+}
+
+// Submodules and nested repos
+/** Pass `path` to getGitStatus, getGitState or the diff commands to query the repo itself. */
+export interface NestedRepo {
+  path: string;
+  relative_path: string;
+  kind: "submodule" | "nested";
+  /** Submodules only. */
+  recorded_commit: string | null;
+  /** False for a submodule that isn't checked out yet. */
+  initialized: boolean;
+  head: string | null;
+  /** Null when detached, as submodules usually are. */
+  branch: string | null;
+  /** Submodules only: HEAD isn't the recorded commit. */
+  out_of_sync: boolean;
+  dirty: boolean;
+  added: number;
+  removed: number;
+}
+
+export interface SubmoduleUpdateOptions {
+  /** All submodules when omitted or empty. */
+  paths?: string[];
+  init?: boolean;
+  recursive?: boolean;
+  /** Five minutes when omitted. */
+  timeout_secs?: number;
+}
+
+export function listNestedRepos(path: string): Promise<NestedRepo[]> {
+  return invoke("list_nested_repos", { path });
+}
+
+export function initSubmodules(repoPath: string, paths: string[] = []): Promise<NestedRepo[]> {
+  return invoke("init_submodules", { repoPath, paths });
+}
+
+export function updateSubmodules(repoPath: string, options: SubmoduleUpdateOptions): Promise<NestedRepo[]> {
+  return invoke("update_submodules", { repoPath, options });
+}
+
+// Hooks
+export interface GitHook {
+  name: string;
+  path: string;
+  /** False for a .git/hooks script that core.hooksPath overrides. */
+  active: boolean;
+  framework: string | null;
+}
+
+export interface HookFramework {
+  name: "pre-commit" | "husky" | "lefthook";
+  config_path: string;
+  /** False when configured but its install step hasn't run. */
+  installed: boolean;
+}
+
+export interface HooksOverview {
+  hooks_dir: string;
+  hooks_path: string | null;
+  hooks: GitHook[];
+  frameworks: HookFramework[];
+}
+
+export type HookEvent =
+  | { event: "Stdout"; data: string }
+  | { event: "Stderr"; data: string }
+  /** Always last; `code` is null when killed by a signal. */
+  | { event: "Exited"; data: { code: number | null } };
+
+export interface RunHookOptions {
+  /** For prepare-commit-msg and commit-msg. */
+  message?: string;
+  /** Ten minutes when omitted. */
+  timeout_secs?: number;
+}
+
+export interface HookRunResult {
+  hook: string;
+  success: boolean;
+  code: number | null;
+  /** The message as the hook left it, for the message hooks. */
+  message: string | null;
+  duration_ms: number;
+}
+
+export function listGitHooks(repoPath: string): Promise<HooksOverview> {
+  return invoke("list_git_hooks", { repoPath });
+}
+
+/** Runs a commit hook against what's staged, without committing. */
+export function runGitHook(
+  repoPath: string,
+  hook: "pre-commit" | "pre-merge-commit" | "prepare-commit-msg" | "commit-msg",
+  options: RunHookOptions,
+  channel: Channel<HookEvent>,
+): Promise<HookRunResult> {
+  return invoke("run_git_hook", { repoPath, hook, options, channel });
+}
+
+// Thread snapshots
+export interface ThreadSnapshot {
+  thread_id: string;
+  commit: string;
+  /** HEAD when the thread began; null before the first commit. */
+  head: string | null;
+  created_at: string;
+}
+
+/**
+ * Idempotent: a thread that already has a snapshot keeps its original one.
+ * Resolves to null when `repoPath` isn't in a git work tree.
+ */
+export function createThreadSnapshot(repoPath: string, threadId: string): Promise<ThreadSnapshot | null> {
+  return invoke("create_thread_snapshot", { repoPath, threadId });
+}
+
+export function getThreadSnapshot(repoPath: string, threadId: string): Promise<ThreadSnapshot | null> {
+  return invoke("get_thread_snapshot", { repoPath, threadId });
+}
+
+/** Everything changed since the thread began, committed or not. */
+export function getThreadChanges(
+  repoPath: string,
+  threadId: string,
+  filePath: string | null = null,
+  options: DiffOptions = {},
+): Promise<DiffFile[]> {
+  return invoke("get_thread_changes", { repoPath, threadId, filePath, options });
+}
+
+export function getThreadNumstat(repoPath: string, threadId: string): Promise<FileDiffStat[]> {
+  return invoke("get_thread_numstat", { repoPath, threadId });
+}
+
+export function deleteThreadSnapshot(repoPath: string, threadId: string): Promise<void> {
+  return invoke("delete_thread_snapshot", { repoPath, threadId });
+}
+
+// Patches and change summaries. Ranges read like a pull request: `base..head`,
+// diffed from the merge base; a single rev means `rev..HEAD`.
+export type PatchOutput =
+  /** One numbered .patch file per commit; `dir` is created if needed. */
+  | { kind: "series"; dir: string }
+  | { kind: "mbox"; path: string };
+
+export interface PatchExport {
+  files: string[];
+  commits: number;
+}
+
+export interface SummaryCommit {
+  hash: string;
+  short_hash: string;
+  author: string;
+  subject: string;
+}
+
+export interface DirectoryChanges {
+  /** "." for the repo root. */
+  dir: string;
+  added: number;
+  removed: number;
+  files: FileDiffStat[];
+}
+
+export interface ChangeSummary {
+  /** Oldest first. */
+  commits: SummaryCommit[];
+  files_changed: number;
+  added: number;
+  removed: number;
+  directories: DirectoryChanges[];
+}
+
+export function exportPatches(repoPath: string, range: string, output: PatchOutput): Promise<PatchExport> {
+  return invoke("export_patches", { repoPath, range, output });
+}
+
+export function getChangeSummary(repoPath: string, range: string): Promise<ChangeSummary> {
+  return invoke("get_change_summary", { repoPath, range });
+}
+
+/** The summary as pretty JSON, or Markdown for a PR description. */
+export function exportChangeSummary(repoPath: string, range: string, format: "json" | "markdown"): Promise<string> {
+  return invoke("export_change_summary", { repoPath, range, format });
+}
+
+// History
+/** Author/grep are case-insensitive substring matches. */
+export interface LogQuery {
+  skip?: number;
+  /** Default 100, max 1000. */
+  limit?: number;
+  /** `rev`, `a..b` or `a...b`; HEAD when omitted. */
+  range?: string;
+  path?: string;
+  author?: string;
+  grep?: string;
+}
+
+export interface CommitSummary {
+  hash: string;
+  /** First parent first. */
+  parents: string[];
+  author: string;
+  date: string;
+  subject: string;
+}
+
+export interface LogPage {
+  commits: CommitSummary[];
+  has_more: boolean;
+}
+
+export function getGitLog(repoPath: string, query: LogQuery = {}): Promise<LogPage> {
+  return invoke("get_git_log", { repoPath, query });
+}
+
+// Staging
+export interface LineRange {
+  /** "old" for removed lines, "new" for added ones. */
+  side: "old" | "new";
+  start: number;
+  end: number;
+}
+
+/** Part of one file's diff: whole hunks by index plus individual lines. */
+export interface PatchSelection {
+  hunks?: number[];
+  lines?: LineRange[];
+}
+
+export function stageFiles(repoPath: string, paths: string[]): Promise<void> {
+  return invoke("stage_files", { repoPath, paths });
+}
+
+export function unstageFiles(repoPath: string, paths: string[]): Promise<void> {
+  return invoke("unstage_files", { repoPath, paths });
+}
+
+/** Restore files from the index (or HEAD with includeStaged); untracked files are deleted. */
+export function discardFileChanges(repoPath: string, paths: string[], includeStaged?: boolean): Promise<void> {
+  return invoke("discard_file_changes", { repoPath, paths, includeStaged });
+}
+
+/** Line numbers refer to the unstaged (index → worktree) diff. */
+export function stageSelection(repoPath: string, filePath: string, selection: PatchSelection): Promise<void> {
+  return invoke("stage_selection", { repoPath, filePath, selection });
+}
+
+/** Line numbers refer to the staged (HEAD → index) diff. */
+export function unstageSelection(repoPath: string, filePath: string, selection: PatchSelection): Promise<void> {
+  return invoke("unstage_selection", { repoPath, filePath, selection });
+}
+
+export function discardSelection(repoPath: string, filePath: string, selection: PatchSelection): Promise<void> {
+  return invoke("discard_selection", { repoPath, filePath, selection });
+}
+
+// Committing
+export interface CommitOptions {
+  /** May be empty only with amend, which then keeps the existing message. */
+  message: string;
+  amend?: boolean;
+  signoff?: boolean;
+  /** "Name <email>" */
+  author?: string;
+  allow_empty?: boolean;
+  no_verify?: boolean;
+}
+
+export type CommitOutcome =
+  | { status: "committed"; commit: CommitInfo; hook_output: string }
+  /** `hooks` are the installed commit hooks; git doesn't say which one failed. */
+  | { status: "hook_rejected"; hooks: string[]; output: string };
+
+export function gitCommit(repoPath: string, options: CommitOptions): Promise<CommitOutcome> {
+  return invoke("git_commit", { repoPath, options });
+}
+
+// Branches
+export interface BranchInfo {
+  /** Short name: "main", "origin/main". */
+  name: string;
+  is_remote: boolean;
+  is_current: boolean;
+  head: string;
+  upstream: string | null;
+  upstream_gone: boolean;
+  ahead: number;
+  behind: number;
+  last_commit_date: string;
+  subject: string;
+}
+
+/** Local branches (current first), then remote-tracking branches. */
+export function listBranches(repoPath: string): Promise<BranchInfo[]> {
+  return invoke("list_branches", { repoPath });
+}
+
+export function createBranch(
+  repoPath: string,
+  name: string,
+  startPoint?: string,
+  checkout?: boolean,
+): Promise<void> {
+  return invoke("create_branch", { repoPath, name, startPoint, checkout });
+}
+
+/** Refuses while tracked files are modified unless allowDirty. */
+export function switchBranch(repoPath: string, name: string, allowDirty?: boolean): Promise<void> {
+  return invoke("switch_branch", { repoPath, name, allowDirty });
+}
+
+export function renameBranch(repoPath: string, oldName: string, newName: string): Promise<void> {
+  return invoke("rename_branch", { repoPath, oldName, newName });
+}
+
+/** Without force, git refuses to delete an unmerged branch. */
+export function deleteBranch(repoPath: string, name: string, force?: boolean): Promise<void> {
+  return invoke("delete_branch", { repoPath, name, force });
+}
+
+// Merge-back
+export type MergeStrategy = "merge" | "squash" | "rebase";
+
+export interface MergeConflict {
+  path: string;
+  /** "contents", "modify/delete", "rename/delete", ... */
+  kind: string;
+  message: string;
+}
+
+export interface MergePreview {
+  source_branch: string;
+  target_branch: string;
+  /** Where the target is checked out; the merge happens there. */
+  target_worktree: string | null;
+  merge_base: string | null;
+  ahead: number;
+  behind: number;
+  conflicts: MergeConflict[];
+}
+
+export interface MergeBackOptions {
+  worktree_path: string;
+  target_branch: string;
+  strategy?: MergeStrategy;
+  message?: string;
+  /** Proceed despite predicted conflicts and leave them for resolution. */
+  allow_conflicts?: boolean;
+  remove_worktree?: boolean;
+  delete_branch?: boolean;
+}
+
+export type MergeBackStep =
+  | "preview"
+  | "rebase"
+  | "merge"
+  | "squash"
+  | "fast_forward"
+  | "remove_worktree"
+  | "delete_branch"
+  | "done";
+
+export type MergeBackEvent =
+  | { event: "Step"; data: { step: MergeBackStep; message: string } }
+  | { event: "Conflict"; data: MergeConflict };
+
+export type MergeBackOutcome =
+  | {
+      status: "merged";
+      commit: string;
+      worktree_removed: boolean;
+      branch_deleted: boolean;
+      /** The merge succeeded but removing the worktree or branch didn't. */
+      cleanup_error: string | null;
+    }
+  /** in_progress is the worktree left mid-merge/rebase, or null if nothing changed. */
+  | { status: "conflicts"; conflicts: MergeConflict[]; in_progress: string | null };
+
+export function previewMergeBack(repoPath: string, worktreePath: string, targetBranch: string): Promise<MergePreview> {
+  return invoke("preview_merge_back", { repoPath, worktreePath, targetBranch });
+}
+
+/** Both worktrees must have no uncommitted changes to tracked files. */
+export function mergeBack(
+  repoPath: string,
+  options: MergeBackOptions,
+  channel: Channel<MergeBackEvent>,
+): Promise<MergeBackOutcome> {
+  return invoke("merge_back", { repoPath, options, channel });
+}
+
+// Conflicts
+/** Line numbers are 1-based and include the marker lines. */
+export interface ConflictRegion {
+  start_line: number;
+  end_line: number;
+  ours_label: string;
+  theirs_label: string;
+  ours: string;
+  /** Only with merge.conflictStyle=diff3/zdiff3. */
+  base: string | null;
+  theirs: string;
+}
+
+/** A side is null when it lacks the file or the file is binary. */
+export interface ConflictFile {
+  path: string;
+  base: string | null;
+  ours: string | null;
+  theirs: string | null;
+  binary: boolean;
+  regions: ConflictRegion[];
+}
+
+export type RegionChoice =
+  | { side: "ours" }
+  | { side: "theirs" }
+  | { side: "base" }
+  | { side: "both" }
+  | { side: "text"; text: string };
+
+export type ConflictResolution =
+  /** One choice per region, in file order. */
+  | { kind: "regions"; choices: RegionChoice[] }
+  | { kind: "content"; content: string }
+  | { kind: "delete" };
+
+export type GitOperationKind = "merge" | "rebase" | "cherry_pick";
+
+export interface GitOperation {
+  kind: GitOperationKind;
+  conflicted: string[];
+  /** Rebase progress: [current, total]. */
+  step: [number, number] | null;
+}
+
+export function getConflict(repoPath: string, filePath: string): Promise<ConflictFile> {
+  return invoke("get_conflict", { repoPath, filePath });
+}
+
+/** Writes the resolution and stages the file. */
+export function resolveConflict(repoPath: string, filePath: string, resolution: ConflictResolution): Promise<void> {
+  return invoke("resolve_conflict", { repoPath, filePath, resolution });
+}
+
+export function getGitOperation(repoPath: string): Promise<GitOperation | null> {
+  return invoke("get_git_operation", { repoPath });
+}
+
+export function abortGitOperation(repoPath: string): Promise<void> {
+  return invoke("abort_git_operation", { repoPath });
+}
+
+/** Returns the operation if it stopped again on new conflicts, null once finished. */
+export function continueGitOperation(repoPath: string): Promise<GitOperation | null> {
+  return invoke("continue_git_operation", { repoPath });
+}
+
 export async function pickDirectory(): Promise<string | null> {
   const selected = await open({ directory: true, multiple: false });
   if (typeof selected === "string") return selected;
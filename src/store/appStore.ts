@@ -4,7 +4,7 @@ import type { UsageSnapshot, UsageAgent } from "./usageTypes";
 import { THREAD_LABELS } from "./types";
 import type { TranscriptInfo } from "./transcriptTypes";
 import type { RepoHealth, WorktreeInfo } from "../lib/tauri";
-import { getGitWorktrees } from "../lib/tauri";
+import { deleteThreadSnapshot, getGitWorktrees } from "../lib/tauri";
 import { attributeEnv } from "../lib/worktree";
 import type { AccentColorId, AppearanceMode } from "../lib/themes";
 
@@ -23,6 +23,17 @@ export interface RepoHealthDismissal {
   statusDurationMs: number;
 }
 
+/**
+ * The repo a thread's start-of-thread snapshot lives in. Creating and
+ * deleting the snapshot both resolve it here so they can't disagree.
+ */
+export function threadRepoPath(
+  projects: Project[],
+  thread: Pick<Thread, "projectId">,
+): string | undefined {
+  return projects.find((p) => p.id === thread.projectId)?.path;
+}
+
 interface AppState {
   // Data
   projects: Project[];
@@ -525,6 +536,11 @@ export const useAppStore = create<AppState>((set, get) => ({
   },
 
   removeThread: (threadId) => {
+    const removed = get().threads.find((t) => t.id === threadId);
+    const repoPath = removed && threadRepoPath(get().projects, removed);
+    if (repoPath) {
+      void deleteThreadSnapshot(repoPath, threadId).catch(() => { /* none to drop */ });
+    }
     set((s) => {
       // Clean up transcriptInfo for the removed thread
       const nextTranscriptInfo = { ...s.transcriptInfo };